@@ -0,0 +1,473 @@
+// * 缓存器(Cacher): 持有一个闭包, 并缓存它对每个参数的计算结果
+// + 解决了 main.rs 中提到的两个限制:
+//   - 用 HashMap 存储不同参数的结果
+//   - 参数和返回值不再局限于 u32, 只要 key 可以哈希、值可以克隆即可
+// + 默认不会淘汰任何缓存, 长时间运行的程序可以通过 with_policy 指定淘汰策略, 见 policy.rs
+// + 可以通过 persist_to 把缓存保存到文件, 重启后继续使用, 见 snapshot.rs
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+
+pub mod clock;
+pub mod policy;
+pub mod snapshot;
+pub mod sync_cacher;
+
+pub use sync_cacher::SyncCacher;
+
+use policy::{Policy, Unbounded};
+use snapshot::{Persist, SnapshotError};
+
+// * 命中和未命中的次数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    // * 被淘汰策略丢掉的缓存数量(包括过期的)
+    pub evictions: u64,
+}
+
+impl Stats {
+    // * 命中率, 还没有任何请求时为 0
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+pub struct Cacher<K, V, F, P = Unbounded>
+where
+    F: Fn(K) -> V,
+{
+    calculation: F,
+    map: HashMap<K, V>,
+    policy: P,
+    stats: Stats,
+    snapshot: Option<Snapshot<K, V>>,
+}
+
+// * 缓存被 drop 时保存到哪里
+// ? Drop 不能比结构体本身要求更多的约束(K: Persist), 所以在设置路径时把对应的保存函数一起存下来
+struct Snapshot<K, V> {
+    path: PathBuf,
+    save: fn(&Path, &HashMap<K, V>) -> Result<(), SnapshotError>,
+}
+
+impl<K, V, F> Cacher<K, V, F>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    F: Fn(K) -> V,
+{
+    pub fn new(calculation: F) -> Cacher<K, V, F> {
+        Cacher::with_policy(calculation, Unbounded)
+    }
+}
+
+impl<K, V, F, P> Cacher<K, V, F, P>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    F: Fn(K) -> V,
+    P: Policy<K>,
+{
+    // * 例如: Cacher::with_policy(f, Lru::new(100))
+    pub fn with_policy(calculation: F, policy: P) -> Cacher<K, V, F, P> {
+        Cacher {
+            calculation,
+            map: HashMap::new(),
+            policy,
+            stats: Stats::default(),
+            snapshot: None,
+        }
+    }
+
+    // * 有缓存直接返回, 没有缓存才执行闭包, 并把结果存起来
+    pub fn get(&mut self, arg: K) -> V {
+        if let Some(v) = self.map.get(&arg) {
+            if !self.policy.is_expired(&arg) {
+                self.stats.hits += 1;
+                self.policy.on_hit(&arg);
+                return v.clone();
+            }
+            // * 过期的缓存当作未命中处理
+            self.map.remove(&arg);
+            self.policy.on_remove(&arg);
+            self.stats.evictions += 1;
+        }
+        self.stats.misses += 1;
+        // ? 闭包会取得参数的所有权, 所以先克隆一份作为 key
+        let v = (self.calculation)(arg.clone());
+        self.policy.on_insert(&arg);
+        self.map.insert(arg, v.clone());
+        self.evict();
+        v
+    }
+
+    // * 让策略决定淘汰哪些缓存, 直到策略认为不需要再淘汰
+    fn evict(&mut self) {
+        while let Some(key) = self.policy.evict(self.map.len()) {
+            if self.map.remove(&key).is_some() {
+                self.stats.evictions += 1;
+            }
+        }
+    }
+
+    // * 手动清理, 例如 TTL 策略下没有新的插入时, 也可以定期调用它释放过期的缓存
+    pub fn purge(&mut self) {
+        self.evict();
+    }
+
+    // * 删除某个参数的缓存, 下次 get 时会重新计算
+    pub fn invalidate(&mut self, arg: &K) -> Option<V> {
+        self.policy.on_remove(arg);
+        self.map.remove(arg)
+    }
+
+    // * 清空所有缓存, 统计数据保留
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.policy.clear();
+    }
+
+    pub fn contains(&self, arg: &K) -> bool {
+        self.map.contains_key(arg)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+}
+
+impl<K, V, F, P> Cacher<K, V, F, P>
+where
+    K: Hash + Eq + Clone + Persist,
+    V: Clone + Persist,
+    F: Fn(K) -> V,
+    P: Policy<K>,
+{
+    // * 从文件加载缓存, 并在 drop 时自动保存回这个文件
+    // + 文件不存在时返回 Ok(0), 这是第一次运行时的正常情况
+    // + 文件损坏时返回错误, 缓存保持为空, drop 时会用新的内容覆盖掉损坏的文件
+    pub fn persist_to<T: AsRef<Path>>(&mut self, path: T) -> Result<usize, SnapshotError> {
+        let path = path.as_ref().to_path_buf();
+        let loaded = match self.load(&path) {
+            Err(SnapshotError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            result => result,
+        };
+        self.snapshot = Some(Snapshot {
+            path,
+            save: snapshot::save::<K, V>,
+        });
+        loaded
+    }
+
+    // * 把文件中的缓存合并进来, 返回加载的条目数量
+    // ? 对淘汰策略来说, 加载的条目相当于刚刚插入, TTL 会从现在开始计时
+    pub fn load<T: AsRef<Path>>(&mut self, path: T) -> Result<usize, SnapshotError> {
+        let entries = snapshot::load::<K, V>(path.as_ref())?;
+        let count = entries.len();
+        for (key, value) in entries {
+            self.policy.on_insert(&key);
+            self.map.insert(key, value);
+        }
+        self.evict();
+        Ok(count)
+    }
+
+    // * 立即保存到 persist_to 指定的文件, 没有指定时什么也不做
+    pub fn save(&self) -> Result<(), SnapshotError> {
+        match &self.snapshot {
+            Some(snapshot) => (snapshot.save)(&snapshot.path, &self.map),
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_to<T: AsRef<Path>>(&self, path: T) -> Result<(), SnapshotError> {
+        snapshot::save(path.as_ref(), &self.map)
+    }
+}
+
+impl<K, V, F, P> Drop for Cacher<K, V, F, P>
+where
+    F: Fn(K) -> V,
+{
+    fn drop(&mut self) {
+        if let Some(snapshot) = &self.snapshot {
+            // ? drop 中无法返回错误, 需要知道保存是否成功时应该先手动调用 save
+            let _ = (snapshot.save)(&snapshot.path, &self.map);
+        }
+    }
+}
+
+// * 把一个已有的函数包装成带缓存的版本
+// + 返回的闭包持有 Cacher, 调用时需要修改缓存, 所以是 FnMut
+pub fn memoize<K, V, F>(f: F) -> impl FnMut(K) -> V
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    F: Fn(K) -> V,
+{
+    let mut cacher = Cacher::new(f);
+    move |arg| cacher.get(arg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clock::FakeClock;
+    use super::policy::{Lfu, Lru, Ttl};
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn caches_each_argument_once() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|s: String| {
+            calls.set(calls.get() + 1);
+            s.len()
+        });
+        assert_eq!(c.get(String::from("hello")), 5);
+        assert_eq!(c.get(String::from("hello")), 5);
+        assert_eq!(c.get(String::from("hi")), 2);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(c.len(), 2);
+        assert_eq!(
+            c.stats(),
+            Stats {
+                hits: 1,
+                misses: 2,
+                evictions: 0
+            }
+        );
+        assert!((c.stats().hit_rate() - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn invalidate_and_clear() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|(a, b): (i32, i32)| {
+            calls.set(calls.get() + 1);
+            vec![a; b as usize]
+        });
+        assert_eq!(c.get((7, 2)), vec![7, 7]);
+        assert_eq!(c.invalidate(&(7, 2)), Some(vec![7, 7]));
+        assert_eq!(c.invalidate(&(7, 2)), None);
+        assert!(!c.contains(&(7, 2)));
+        c.get((7, 2));
+        c.get((1, 1));
+        assert_eq!(calls.get(), 3);
+
+        c.clear();
+        assert!(c.is_empty());
+        assert_eq!(c.stats().misses, 3);
+    }
+
+    #[test]
+    fn memoize_wraps_a_function() {
+        fn square(x: u64) -> u64 {
+            x * x
+        }
+        let mut square = memoize(square);
+        assert_eq!(square(12), 144);
+        assert_eq!(square(12), 144);
+
+        let calls = Cell::new(0);
+        let mut upper = memoize(|s: &str| {
+            calls.set(calls.get() + 1);
+            s.to_uppercase()
+        });
+        assert_eq!(upper("rust"), "RUST");
+        assert_eq!(upper("rust"), "RUST");
+        assert_eq!(calls.get(), 1);
+        assert_eq!(Stats::default().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut c = Cacher::with_policy(|x: u32| x * 10, Lru::new(2));
+        c.get(1);
+        c.get(2);
+        // * 访问 1, 这样 2 就变成了最久没被访问的
+        c.get(1);
+        c.get(3);
+        assert_eq!(c.len(), 2);
+        assert!(c.contains(&1));
+        assert!(!c.contains(&2));
+        assert!(c.contains(&3));
+        assert_eq!(c.stats().evictions, 1);
+
+        // * 手动删除的 key 不会再被淘汰
+        c.invalidate(&1);
+        c.get(4);
+        assert!(c.contains(&3) && c.contains(&4));
+        assert_eq!(c.stats().evictions, 1);
+    }
+
+    #[test]
+    fn lfu_evicts_least_frequently_used() {
+        let mut c = Cacher::with_policy(|x: u32| x, Lfu::new(2));
+        c.get(1);
+        c.get(1);
+        c.get(1);
+        c.get(2);
+        // * 2 只被访问过一次, 3 插入时 2 被淘汰, 而不是最久没被访问的 1
+        c.get(3);
+        assert!(c.contains(&1));
+        assert!(!c.contains(&2));
+        assert!(c.contains(&3));
+        c.get(3);
+        c.get(4);
+        assert!(c.contains(&1) && c.contains(&4));
+        assert!(!c.contains(&3));
+    }
+
+    #[test]
+    fn ttl_expires_entries_with_a_fake_clock() {
+        let clock = FakeClock::new();
+        let calls = Cell::new(0);
+        let mut c = Cacher::with_policy(
+            |x: u32| {
+                calls.set(calls.get() + 1);
+                x + 1
+            },
+            Ttl::with_clock(Duration::from_secs(60), clock.clone()),
+        );
+        c.get(1);
+        clock.advance(Duration::from_secs(30));
+        c.get(2);
+        assert_eq!(c.get(1), 2);
+        assert_eq!(calls.get(), 2);
+
+        // * 1 写入已经 60 秒, 过期了, 需要重新计算
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(c.get(1), 2);
+        assert_eq!(calls.get(), 3);
+        assert_eq!(c.stats().evictions, 1);
+
+        // * 2 也过期了, 没有新的插入时由 purge 清理
+        clock.advance(Duration::from_secs(30));
+        c.purge();
+        assert_eq!(c.len(), 1);
+        assert!(c.contains(&1));
+        clock.advance(Duration::from_secs(30));
+        c.purge();
+        assert!(c.is_empty());
+        assert_eq!(c.stats().evictions, 3);
+    }
+
+    #[test]
+    fn ttl_purges_expired_entries_on_insert() {
+        let clock = FakeClock::new();
+        let mut c = Cacher::with_policy(
+            |x: u32| x,
+            Ttl::with_clock(Duration::from_millis(10), clock.clone()),
+        );
+        for i in 0..100 {
+            c.get(i);
+            clock.advance(Duration::from_millis(1));
+        }
+        // * 只有最近 10 毫秒内写入的还在
+        assert_eq!(c.len(), 10);
+        c.clear();
+        c.get(1000);
+        assert_eq!(c.len(), 1);
+    }
+
+    // * 每个测试使用自己的文件, 避免并行运行时互相干扰
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "closures-{}-{}.snapshot",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn snapshot_survives_restart() {
+        let path = temp_path("restart");
+        let calls = Cell::new(0);
+        let expensive = |n: u32| {
+            calls.set(calls.get() + 1);
+            n * 2
+        };
+
+        {
+            let mut c = Cacher::new(expensive);
+            assert_eq!(c.persist_to(&path).unwrap(), 0);
+            c.get(1);
+            c.get(2);
+        }
+        // * 上一个 Cacher drop 时已经保存, 新的 Cacher 加载之后不需要再计算
+        let mut c = Cacher::new(expensive);
+        assert_eq!(c.persist_to(&path).unwrap(), 2);
+        assert_eq!(c.get(1), 2);
+        assert_eq!(c.get(2), 4);
+        assert_eq!(calls.get(), 2);
+
+        c.get(3);
+        c.save().unwrap();
+        let mut other = Cacher::new(expensive);
+        assert_eq!(other.load(&path).unwrap(), 3);
+        drop(other);
+        drop(c);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_snapshot_is_reported_and_replaced() {
+        let path = temp_path("corrupted");
+        let mut c = Cacher::new(|n: u32| n + 1);
+        c.get(1);
+        c.save_to(&path).unwrap();
+        drop(c);
+
+        let mut data = std::fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        std::fs::write(&path, data).unwrap();
+
+        {
+            let mut c = Cacher::new(|n: u32| n + 1);
+            assert!(matches!(
+                c.persist_to(&path),
+                Err(SnapshotError::ChecksumMismatch)
+            ));
+            assert!(c.is_empty());
+            c.get(5);
+        }
+        // * 损坏的文件在 drop 时被新的快照覆盖
+        let mut c = Cacher::new(|n: u32| n + 1);
+        assert_eq!(c.load(&path).unwrap(), 1);
+        assert!(c.contains(&5));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn loading_respects_policy() {
+        let path = temp_path("policy");
+        let mut c = Cacher::new(|n: u32| n);
+        for i in 0..5 {
+            c.get(i);
+        }
+        c.save_to(&path).unwrap();
+
+        let mut lru = Cacher::with_policy(|n: u32| n, Lru::new(3));
+        assert_eq!(lru.load(&path).unwrap(), 5);
+        assert_eq!(lru.len(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
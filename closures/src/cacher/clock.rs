@@ -0,0 +1,69 @@
+// * 可替换的时钟
+// + TTL 过期需要知道 "现在" 是什么时候, 直接调用 Instant::now() 的话测试只能真的 sleep
+// + 所以把时钟抽象成 trait, 正常使用 SystemClock, 测试时使用可以手动拨动的 FakeClock
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// * 测试用的时钟, 只有调用 advance 时时间才会前进
+// + 克隆出来的时钟共享同一个时间, 把一个交给缓存, 另一个留在测试里拨动
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    start: Instant,
+    // ? 用纳秒数保存偏移量, 原子类型让它可以在多个线程之间共享
+    offset: Arc<AtomicU64>,
+}
+
+impl FakeClock {
+    pub fn new() -> FakeClock {
+        FakeClock {
+            start: Instant::now(),
+            offset: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.offset
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        FakeClock::new()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.start + Duration::from_nanos(self.offset.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fake_clock_only_moves_when_advanced() {
+        let clock = FakeClock::new();
+        let shared = clock.clone();
+        let t0 = clock.now();
+        assert_eq!(clock.now(), t0);
+        shared.advance(Duration::from_secs(5));
+        assert_eq!(clock.now() - t0, Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,255 @@
+// * 淘汰策略
+// + Cacher 本身只负责存取, 什么时候该丢掉哪些缓存由策略决定
+// + 策略只记录 key 的访问情况, 真正的值仍然保存在 Cacher 的 HashMap 中
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use super::clock::{Clock, SystemClock};
+
+pub trait Policy<K> {
+    // * 命中缓存
+    fn on_hit(&mut self, key: &K);
+
+    // * 新插入了一个 key
+    fn on_insert(&mut self, key: &K);
+
+    // * key 被手动删除或者因为过期被删除
+    fn on_remove(&mut self, key: &K);
+
+    // * 插入之后调用, len 为当前缓存的数量
+    // + 需要淘汰时, 返回被淘汰的 key (同时从策略自己的记录中删除), 不需要时返回 None
+    // + Cacher 会一直调用, 直到返回 None
+    fn evict(&mut self, len: usize) -> Option<K>;
+
+    // * 缓存是否已经过期, 过期的缓存会被当作未命中
+    fn is_expired(&self, _key: &K) -> bool {
+        false
+    }
+
+    fn clear(&mut self);
+}
+
+// * 默认策略: 从不淘汰
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unbounded;
+
+impl<K> Policy<K> for Unbounded {
+    fn on_hit(&mut self, _key: &K) {}
+    fn on_insert(&mut self, _key: &K) {}
+    fn on_remove(&mut self, _key: &K) {}
+    fn evict(&mut self, _len: usize) -> Option<K> {
+        None
+    }
+    fn clear(&mut self) {}
+}
+
+// * 最近最少使用(LRU): 超出容量时, 淘汰最久没有被访问的 key
+// + 每次访问都分配一个递增的序号, BTreeMap 中序号最小的就是最久没被访问的
+pub struct Lru<K> {
+    capacity: usize,
+    tick: u64,
+    ticks: HashMap<K, u64>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone> Lru<K> {
+    pub fn new(capacity: usize) -> Lru<K> {
+        Lru {
+            capacity,
+            tick: 0,
+            ticks: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn touch(&mut self, key: &K) {
+        self.tick += 1;
+        if let Some(old) = self.ticks.insert(key.clone(), self.tick) {
+            self.order.remove(&old);
+        }
+        self.order.insert(self.tick, key.clone());
+    }
+}
+
+impl<K: Hash + Eq + Clone> Policy<K> for Lru<K> {
+    fn on_hit(&mut self, key: &K) {
+        self.touch(key);
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        self.touch(key);
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        if let Some(tick) = self.ticks.remove(key) {
+            self.order.remove(&tick);
+        }
+    }
+
+    fn evict(&mut self, len: usize) -> Option<K> {
+        if len <= self.capacity {
+            return None;
+        }
+        let (_, key) = self.order.pop_first()?;
+        self.ticks.remove(&key);
+        Some(key)
+    }
+
+    fn clear(&mut self) {
+        self.ticks.clear();
+        self.order.clear();
+    }
+}
+
+// * 最不经常使用(LFU): 超出容量时, 淘汰访问次数最少的 key, 次数相同时淘汰最久没被访问的
+pub struct Lfu<K> {
+    capacity: usize,
+    tick: u64,
+    // * key -> (访问次数, 最后一次访问的序号)
+    entries: HashMap<K, (u64, u64)>,
+    order: BTreeMap<(u64, u64), K>,
+}
+
+impl<K: Hash + Eq + Clone> Lfu<K> {
+    pub fn new(capacity: usize) -> Lfu<K> {
+        Lfu {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    // * 访问次数 +1
+    fn touch(&mut self, key: &K) {
+        self.tick += 1;
+        let count = match self.entries.get(key) {
+            Some(&old) => {
+                self.order.remove(&old);
+                old.0 + 1
+            }
+            None => 1,
+        };
+        self.entries.insert(key.clone(), (count, self.tick));
+        self.order.insert((count, self.tick), key.clone());
+    }
+
+    // * 某个 key 被访问的次数, 主要用于调试和测试
+    pub fn frequency(&self, key: &K) -> Option<u64> {
+        self.entries.get(key).map(|&(count, _)| count)
+    }
+}
+
+impl<K: Hash + Eq + Clone> Policy<K> for Lfu<K> {
+    fn on_hit(&mut self, key: &K) {
+        self.touch(key);
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        self.touch(key);
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry);
+        }
+    }
+
+    fn evict(&mut self, len: usize) -> Option<K> {
+        if len <= self.capacity {
+            return None;
+        }
+        // ? 刚插入的 key 访问次数为 1, 如果它就是次数最少的, 会被立刻淘汰
+        // ? 为了让新 key 有机会积累访问次数, 淘汰时跳过最新插入的那一个
+        let victim = self
+            .order
+            .iter()
+            .find(|(&(_, tick), _)| tick != self.tick)
+            .map(|(&entry, _)| entry)?;
+        let key = self.order.remove(&victim)?;
+        self.entries.remove(&key);
+        Some(key)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+// * 存活时间(TTL): 缓存写入超过 ttl 之后就算过期
+// + 读取时发现过期会当作未命中重新计算
+// + 每次插入时还会顺便清理掉已经过期的缓存, 这样长时间运行也不会一直占着内存
+pub struct Ttl<K, C = SystemClock> {
+    ttl: Duration,
+    clock: C,
+    tick: u64,
+    inserted: HashMap<K, (Instant, u64)>,
+    // * 按写入时间排序, 最早写入的在最前面
+    order: BTreeMap<(Instant, u64), K>,
+}
+
+impl<K: Hash + Eq + Clone> Ttl<K, SystemClock> {
+    pub fn new(ttl: Duration) -> Ttl<K, SystemClock> {
+        Ttl::with_clock(ttl, SystemClock)
+    }
+}
+
+impl<K: Hash + Eq + Clone, C: Clock> Ttl<K, C> {
+    pub fn with_clock(ttl: Duration, clock: C) -> Ttl<K, C> {
+        Ttl {
+            ttl,
+            clock,
+            tick: 0,
+            inserted: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn expired_at(&self, inserted_at: Instant) -> bool {
+        self.clock.now().duration_since(inserted_at) >= self.ttl
+    }
+}
+
+impl<K: Hash + Eq + Clone, C: Clock> Policy<K> for Ttl<K, C> {
+    // ? 命中不会延长存活时间
+    fn on_hit(&mut self, _key: &K) {}
+
+    fn on_insert(&mut self, key: &K) {
+        self.tick += 1;
+        let entry = (self.clock.now(), self.tick);
+        if let Some(old) = self.inserted.insert(key.clone(), entry) {
+            self.order.remove(&old);
+        }
+        self.order.insert(entry, key.clone());
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        if let Some(entry) = self.inserted.remove(key) {
+            self.order.remove(&entry);
+        }
+    }
+
+    // * 和容量无关, 只淘汰已经过期的
+    fn evict(&mut self, _len: usize) -> Option<K> {
+        let (&entry, _) = self.order.first_key_value()?;
+        if !self.expired_at(entry.0) {
+            return None;
+        }
+        let key = self.order.remove(&entry)?;
+        self.inserted.remove(&key);
+        Some(key)
+    }
+
+    fn is_expired(&self, key: &K) -> bool {
+        self.inserted
+            .get(key)
+            .is_some_and(|&(at, _)| self.expired_at(at))
+    }
+
+    fn clear(&mut self) {
+        self.inserted.clear();
+        self.order.clear();
+    }
+}
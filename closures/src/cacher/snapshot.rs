@@ -0,0 +1,372 @@
+// * 把缓存保存到文件, 程序重启之后可以重新加载, 不用再执行一遍耗时的计算
+// + 文件格式(所有整数都是小端序):
+//   - 8 字节标记 "CACHESNP"
+//   - 2 字节版本号, 目前是 1, 以后格式变化时递增, 旧程序遇到新版本会拒绝加载
+//   - 8 字节条目数量, 然后是每个条目的 key 和 value
+//   - 最后 8 字节是前面所有内容的 FNV-1a 校验和, 用来发现文件被截断或被改动
+// + key 和 value 需要实现 Persist, 标准库中常用的类型已经实现好了
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const MAGIC: &[u8; 8] = b"CACHESNP";
+pub const VERSION: u16 = 1;
+
+// * 标记 + 版本号 + 条目数量
+const HEADER_LEN: usize = 8 + 2 + 8;
+const CHECKSUM_LEN: usize = 8;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    // * 不是快照文件
+    BadMagic,
+    UnsupportedVersion(u16),
+    ChecksumMismatch,
+    Corrupted(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "读写快照文件失败: {}", e),
+            SnapshotError::BadMagic => write!(f, "不是缓存快照文件"),
+            SnapshotError::UnsupportedVersion(v) => {
+                write!(f, "不支持的快照版本 {}, 当前版本为 {}", v, VERSION)
+            }
+            SnapshotError::ChecksumMismatch => write!(f, "快照校验和不一致, 文件可能已损坏"),
+            SnapshotError::Corrupted(msg) => write!(f, "快照内容损坏: {}", msg),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+// * 按顺序从字节中读取数据
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if len > self.data.len() {
+            return Err(SnapshotError::Corrupted("数据意外结束"));
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // * 长度字段, 写入时统一用 u64
+    fn read_len(&mut self) -> Result<usize, SnapshotError> {
+        let len = u64::decode(self)?;
+        // ? 长度不可能超过剩下的字节数, 提前检查可以避免损坏的文件导致分配巨大的内存
+        if len > self.data.len() as u64 {
+            return Err(SnapshotError::Corrupted("长度超出文件范围"));
+        }
+        Ok(len as usize)
+    }
+}
+
+// * 可以写入快照的类型
+pub trait Persist: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError>;
+}
+
+macro_rules! impl_persist_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Persist for $t {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+                    let bytes = reader.take(std::mem::size_of::<$t>())?;
+                    Ok(<$t>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_persist_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+// ? usize 的宽度和平台有关, 统一按 u64 保存
+impl Persist for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out);
+    }
+
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        usize::try_from(u64::decode(reader)?)
+            .map_err(|_| SnapshotError::Corrupted("数值超出 usize 范围"))
+    }
+}
+
+impl Persist for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::Corrupted("非法的布尔值")),
+        }
+    }
+}
+
+impl Persist for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let len = reader.read_len()?;
+        let bytes = reader.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| SnapshotError::Corrupted("字符串不是 UTF-8"))
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).encode(out);
+        for item in self {
+            item.encode(out);
+        }
+    }
+
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let len = reader.read_len()?;
+        (0..len).map(|_| T::decode(reader)).collect()
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.is_some().encode(out);
+        if let Some(value) = self {
+            value.encode(out);
+        }
+    }
+
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        if bool::decode(reader)? {
+            Ok(Some(T::decode(reader)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        Ok((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+// * FNV-1a, 64 位版本
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub fn encode<K: Persist, V: Persist>(map: &HashMap<K, V>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + CHECKSUM_LEN);
+    out.extend_from_slice(MAGIC);
+    VERSION.encode(&mut out);
+    (map.len() as u64).encode(&mut out);
+    for (key, value) in map {
+        key.encode(&mut out);
+        value.encode(&mut out);
+    }
+    let sum = checksum(&out);
+    sum.encode(&mut out);
+    out
+}
+
+pub fn decode<K: Persist, V: Persist>(data: &[u8]) -> Result<Vec<(K, V)>, SnapshotError> {
+    if !data.starts_with(MAGIC) {
+        return Err(SnapshotError::BadMagic);
+    }
+    if data.len() < HEADER_LEN + CHECKSUM_LEN {
+        return Err(SnapshotError::Corrupted("文件太短"));
+    }
+    let mut reader = Reader::new(&data[MAGIC.len()..]);
+    let version = u16::decode(&mut reader)?;
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    let (body, sum) = data.split_at(data.len() - CHECKSUM_LEN);
+    if checksum(body) != u64::from_le_bytes(sum.try_into().unwrap()) {
+        return Err(SnapshotError::ChecksumMismatch);
+    }
+
+    let mut reader = Reader::new(&body[MAGIC.len() + 2..]);
+    let count = u64::decode(&mut reader)?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let key = K::decode(&mut reader)?;
+        let value = V::decode(&mut reader)?;
+        entries.push((key, value));
+    }
+    if !reader.is_empty() {
+        return Err(SnapshotError::Corrupted("条目之后还有多余的数据"));
+    }
+    Ok(entries)
+}
+
+// * 同一个进程中每次保存使用不同的临时文件
+static SAVES: AtomicU64 = AtomicU64::new(0);
+
+// * 先写到临时文件再重命名, 写到一半程序崩溃时不会留下损坏的快照
+// + 临时文件名中带有进程 id 和序号, 多个线程或进程同时保存同一个快照时不会写到同一个临时文件, 最后一次重命名的生效
+pub fn save<K: Persist, V: Persist>(path: &Path, map: &HashMap<K, V>) -> Result<(), SnapshotError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        SAVES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = fs::write(&tmp, encode(map)).and_then(|_| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+pub fn load<K: Persist, V: Persist>(path: &Path) -> Result<Vec<(K, V)>, SnapshotError> {
+    decode(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> HashMap<String, Vec<u32>> {
+        let mut map = HashMap::new();
+        map.insert(String::from("squats"), vec![10, 12, 15]);
+        map.insert(String::from("跑步"), vec![]);
+        map
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = encode(&sample());
+        assert_eq!(&data[..8], MAGIC);
+        let entries: HashMap<String, Vec<u32>> = decode(&data).unwrap().into_iter().collect();
+        assert_eq!(entries, sample());
+
+        let mut out = Vec::new();
+        (Some(-3i64), (true, 2.5f64)).encode(&mut out);
+        let value = <(Option<i64>, (bool, f64))>::decode(&mut Reader::new(&out)).unwrap();
+        assert_eq!(value, (Some(-3), (true, 2.5)));
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let data = encode(&sample());
+
+        let mut flipped = data.clone();
+        flipped[HEADER_LEN + 3] ^= 0x40;
+        assert!(matches!(
+            decode::<String, Vec<u32>>(&flipped),
+            Err(SnapshotError::ChecksumMismatch)
+        ));
+
+        assert!(matches!(
+            decode::<String, Vec<u32>>(&data[..data.len() - 5]),
+            Err(SnapshotError::ChecksumMismatch)
+        ));
+        assert!(matches!(
+            decode::<String, Vec<u32>>(&data[..12]),
+            Err(SnapshotError::Corrupted(_))
+        ));
+        assert!(matches!(
+            decode::<String, Vec<u32>>(b"not a snapshot at all"),
+            Err(SnapshotError::BadMagic)
+        ));
+
+        let mut newer = data.clone();
+        newer[8] = 2;
+        assert!(matches!(
+            decode::<String, Vec<u32>>(&newer),
+            Err(SnapshotError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn rejects_wrong_types() {
+        // * 校验和正确, 但是按别的类型去解析: u8 只占 1 字节, 剩下的数据对不上
+        let mut map = HashMap::new();
+        map.insert(1u32, 2u32);
+        assert!(matches!(
+            decode::<u8, u8>(&encode(&map)),
+            Err(SnapshotError::Corrupted(_))
+        ));
+    }
+
+    #[test]
+    fn concurrent_saves_use_their_own_temp_files() {
+        let path =
+            std::env::temp_dir().join(format!("closures-{}-concurrent.snapshot", process::id()));
+        let maps: Vec<HashMap<u32, u32>> =
+            (0..8).map(|i| (0..100).map(|k| (k, i)).collect()).collect();
+        std::thread::scope(|scope| {
+            for map in &maps {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        save(path, map).unwrap();
+                    }
+                });
+            }
+        });
+        // * 读到的一定是某一次完整的保存
+        let mut entries = load::<u32, u32>(&path).unwrap();
+        entries.sort();
+        assert!(maps.iter().any(|map| {
+            let mut expected: Vec<_> = map.iter().map(|(&k, &v)| (k, v)).collect();
+            expected.sort();
+            expected == entries
+        }));
+        fs::remove_file(&path).unwrap();
+    }
+}
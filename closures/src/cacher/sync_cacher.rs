@@ -0,0 +1,313 @@
+// * 线程安全的缓存器, 可以放在 Arc 中被多个线程共享
+// + 和 Cacher 不同, get 只需要 &self, 内部用 Mutex 保护 HashMap
+// + 多个线程同时请求同一个还没有缓存的 key 时, 只有第一个线程会执行闭包(single-flight),
+//   其余线程会等待这次计算的结果, 而不是各自再执行一遍耗时的计算
+// + 计算期间不会持有 HashMap 的锁, 所以不同 key 的计算可以并行进行
+// ? 闭包 panic 时, 这个 key 的占位会被清理掉, 正在等待的线程会重新尝试计算, 其他 key 不受影响
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use super::Stats;
+
+// * 一次正在进行中的计算
+struct Flight<V> {
+    // * None 表示还没算完, Some(None) 表示计算过程中 panic 了
+    result: Mutex<Option<Option<V>>>,
+    done: Condvar,
+}
+
+impl<V: Clone> Flight<V> {
+    fn new() -> Flight<V> {
+        Flight {
+            result: Mutex::new(None),
+            done: Condvar::new(),
+        }
+    }
+
+    fn finish(&self, value: Option<V>) {
+        *lock(&self.result) = Some(value);
+        self.done.notify_all();
+    }
+
+    // * 阻塞直到计算结束, panic 时返回 None
+    fn wait(&self) -> Option<V> {
+        let mut result = lock(&self.result);
+        loop {
+            if let Some(value) = result.as_ref() {
+                return value.clone();
+            }
+            result = self
+                .done
+                .wait(result)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+enum Slot<V> {
+    Ready(V),
+    Pending(Arc<Flight<V>>),
+}
+
+// * 锁里面的数据只会在持有锁的短时间内被修改, 并且不会在持有锁时调用闭包
+// ? 所以即使别的线程持锁时 panic 导致锁被标记为 poisoned, 数据本身也是一致的, 可以继续使用
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub struct SyncCacher<K, V, F>
+where
+    F: Fn(K) -> V,
+{
+    calculation: F,
+    map: Mutex<HashMap<K, Slot<V>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+// * 负责执行闭包的线程持有它
+// + 正常结束时调用 complete; 如果闭包 panic, drop 时会清理占位并通知等待的线程
+struct FlightGuard<'a, K: Hash + Eq, V: Clone> {
+    map: &'a Mutex<HashMap<K, Slot<V>>>,
+    key: Option<K>,
+    flight: Arc<Flight<V>>,
+}
+
+impl<K: Hash + Eq, V: Clone> FlightGuard<'_, K, V> {
+    fn complete(mut self, value: V) {
+        if let Some(key) = self.key.take() {
+            let mut map = lock(self.map);
+            if self.owns_slot(&map, &key) {
+                map.insert(key, Slot::Ready(value.clone()));
+            }
+        }
+        self.flight.finish(Some(value));
+    }
+
+    // ? 计算期间这个 key 可能已经被 invalidate 或 clear, 甚至又被别的线程重新占位
+    fn owns_slot(&self, map: &HashMap<K, Slot<V>>, key: &K) -> bool {
+        matches!(map.get(key), Some(Slot::Pending(f)) if Arc::ptr_eq(f, &self.flight))
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Drop for FlightGuard<'_, K, V> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            let mut map = lock(self.map);
+            if self.owns_slot(&map, &key) {
+                map.remove(&key);
+            }
+            drop(map);
+            self.flight.finish(None);
+        }
+    }
+}
+
+impl<K, V, F> SyncCacher<K, V, F>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    F: Fn(K) -> V,
+{
+    pub fn new(calculation: F) -> SyncCacher<K, V, F> {
+        SyncCacher {
+            calculation,
+            map: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, arg: K) -> V {
+        loop {
+            let mut map = lock(&self.map);
+            let flight = match map.get(&arg) {
+                Some(Slot::Ready(v)) => {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return v.clone();
+                }
+                // * 别的线程正在计算这个 key, 释放锁之后等待它的结果
+                Some(Slot::Pending(flight)) => Arc::clone(flight),
+                None => {
+                    // * 自己负责计算, 先放一个占位, 让之后的线程知道这个 key 正在计算
+                    self.misses.fetch_add(1, Ordering::Relaxed);
+                    let flight = Arc::new(Flight::new());
+                    map.insert(arg.clone(), Slot::Pending(Arc::clone(&flight)));
+                    drop(map);
+
+                    let guard = FlightGuard {
+                        map: &self.map,
+                        key: Some(arg.clone()),
+                        flight,
+                    };
+                    let value = (self.calculation)(arg);
+                    guard.complete(value.clone());
+                    return value;
+                }
+            };
+            drop(map);
+            if let Some(value) = flight.wait() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return value;
+            }
+            // ? 负责计算的线程 panic 了, 回到循环开头重新尝试
+        }
+    }
+
+    // * 删除某个 key 的缓存, 正在进行中的计算不受影响, 但结果不会再写回
+    pub fn invalidate(&self, arg: &K) -> Option<V> {
+        match lock(&self.map).remove(arg) {
+            Some(Slot::Ready(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn clear(&self) {
+        lock(&self.map).clear();
+    }
+
+    // * 已经算好的缓存数量, 不包括正在计算中的
+    pub fn len(&self) -> usize {
+        lock(&self.map)
+            .values()
+            .filter(|slot| matches!(slot, Slot::Ready(_)))
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // * 等待别的线程的计算结果也算作命中
+    pub fn stats(&self) -> Stats {
+        Stats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+    use std::sync::atomic::{AtomicBool, AtomicUsize};
+    use std::sync::{mpsc, Barrier};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn concurrent_requests_share_one_computation() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        // * 模拟 generate_workout 中耗时的计算, 这里只睡 100 毫秒
+        let cacher = Arc::new(SyncCacher::new(move |num: u32| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(100));
+            num
+        }));
+        let barrier = Arc::new(Barrier::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cacher = Arc::clone(&cacher);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    cacher.get(25)
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 25);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cacher.stats(), Stats { hits: 7, misses: 1, evictions: 0 });
+        assert_eq!(cacher.len(), 1);
+    }
+
+    // * 正在等待 key 的计算结果的线程数
+    fn waiters<F: Fn(u32) -> u32>(cacher: &SyncCacher<u32, u32, F>, key: u32) -> usize {
+        match lock(&cacher.map).get(&key) {
+            // ? 一份在 map 中, 一份在负责计算的线程的 FlightGuard 中, 其余的都在等待的线程中
+            Some(Slot::Pending(flight)) => Arc::strong_count(flight) - 2,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn different_keys_compute_in_parallel() {
+        // ? 4 个计算都要等到 4 个线程同时到达之后才能结束, 如果计算时持有锁, 这个测试就永远不会结束
+        let barrier = Barrier::new(4);
+        let cacher = Arc::new(SyncCacher::new(move |num: u64| {
+            barrier.wait();
+            num * 2
+        }));
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let cacher = Arc::clone(&cacher);
+                thread::spawn(move || cacher.get(i))
+            })
+            .collect();
+        let results: Vec<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, vec![0, 2, 4, 6]);
+    }
+
+    #[test]
+    fn panics_do_not_poison_other_keys() {
+        let panicked = AtomicBool::new(false);
+        let (started_tx, started) = mpsc::channel();
+        let (release, release_rx) = mpsc::channel::<()>();
+        let release_rx = Mutex::new(release_rx);
+        let cacher = Arc::new(SyncCacher::new(move |num: u32| {
+            // * key 为 0 时第一次计算会 panic, panic 之前先等测试通知
+            if num == 0 && !panicked.swap(true, Ordering::SeqCst) {
+                started_tx.send(()).unwrap();
+                release_rx.lock().unwrap().recv().unwrap();
+                panic!("simulated failure");
+            }
+            num + 100
+        }));
+
+        let failing = {
+            let cacher = Arc::clone(&cacher);
+            thread::spawn(move || cacher.get(0))
+        };
+        // * 等第一个线程开始计算之后, 再让另一个线程请求同一个 key
+        started.recv().unwrap();
+        let waiting = {
+            let cacher = Arc::clone(&cacher);
+            thread::spawn(move || cacher.get(0))
+        };
+        // * 确认第二个线程已经在等待这次计算, 然后才让它 panic
+        while waiters(&cacher, 0) == 0 {
+            thread::yield_now();
+        }
+        release.send(()).unwrap();
+        assert!(failing.join().is_err());
+        // * 等待中的线程在第一次计算失败后会自己重新计算
+        assert_eq!(waiting.join().unwrap(), 100);
+
+        assert_eq!(cacher.get(1), 101);
+        assert_eq!(cacher.get(0), 100);
+        assert_eq!(cacher.len(), 2);
+    }
+
+    #[test]
+    fn invalidate_and_clear() {
+        let cacher = SyncCacher::new(|s: String| s.len());
+        assert_eq!(cacher.get(String::from("abc")), 3);
+        assert_eq!(cacher.invalidate(&String::from("abc")), Some(3));
+        assert!(cacher.is_empty());
+        cacher.get(String::from("a"));
+        cacher.clear();
+        assert!(cacher.is_empty());
+        // * 闭包的 panic 会原样传给调用 get 的线程
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            SyncCacher::new(|_: u8| -> u8 { panic!("boom") }).get(1)
+        }));
+        assert!(result.is_err());
+    }
+}
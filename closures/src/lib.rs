@@ -0,0 +1,5 @@
+// ? 这里使用分号, 而不是代码块, 是告诉 Rust 在另一个与模块同名的文件中加载模块的内容
+// * 缓存闭包的调用结果, 见 cacher.rs
+pub mod cacher;
+// * 训练计划生成器, 见 workout.rs
+pub mod workout;
@@ -1,7 +1,8 @@
 /*
   函数式语言的特性: 闭包(closures)
 */
-use std::collections::HashMap;
+use closures::workout::export;
+use closures::workout::{Activity, Date, Generator, Level, Profile};
 use std::thread;
 use std::time::Duration;
 
@@ -22,7 +23,11 @@ fn main() {
         - 仅在必要时调用该算法
         - 只调用一次
     */
-    println!("Hello, world!");
+    let simulated_user_specified_value = 10;
+    // * 原来这里传入的是一个"随机数", 现在改成随机数生成器的种子, 同样的种子得到同样的计划
+    let seed = 7;
+
+    generate_workout(simulated_user_specified_value, seed);
 }
 
 /* fn simulated_expensive_calculation(intensity: u32) -> u32 {
@@ -46,33 +51,32 @@ fn main() {
     let n = example_closure(5);
 */
 
-fn generate_workout(intensity: u32, random_number: u32) {
-    // ? 定义闭包, 这里属于是定义了一个匿名函数, 然后使用变量 expensive_closure 来接收定义的匿名函数
-    // * 因为结构体可能会对value进行修改, 所以这里要加一个mut标志
-    let mut expensive_closure = Cacher::new(|num: u32| -> u32 {
-        println!("calculating slowly...");
-        thread::sleep(Duration::from_secs(2));
-        return num;
-    });
-
-    // let calculation_result: u32 = expensive_closure(intensity);
-    // * 可以创建一个 struct, 它持有闭包极其调用结果
+fn generate_workout(intensity: u32, seed: u64) {
+    // ? 定义闭包, 这里属于是定义了一个匿名函数, 交给生成器使用
+    // * 生成器内部用 Cacher 持有这个闭包, 同样的强度只会计算一次
     //  - 只会在需要结果时才执行该闭包
     //  - 可缓存结果
     // * 这种模式通常叫做记忆化(memoization) 或者是 延迟计算(lazy evaluation) (其实在js中很常见)
-    if intensity < 25 {
-        println!("Today, do {} pushups!", expensive_closure.value(intensity));
-        println!("Next, do {} situps!", expensive_closure.value(intensity));
-    } else {
-        if random_number == 3 {
-            println!("Take a break today! Remember to stay hydrated!");
-        } else {
-            println!(
-                "Today, run for {} minutes!",
-                expensive_closure.value(intensity)
-            );
+    let expensive_closure = |num: u32| -> u32 {
+        println!("calculating slowly...");
+        thread::sleep(Duration::from_secs(2));
+        num
+    };
+    let profile = Profile::new("you", Level::Beginner, intensity);
+    let mut generator = Generator::with_calculation(profile, seed, expensive_closure);
+    let today = Date::today();
+    let plan = generator.generate(today, 4);
+
+    match plan.on(today).map(|day| day.activity) {
+        Some(Activity::Strength { pushups, situps }) => {
+            println!("Today, do {} pushups!", pushups);
+            println!("Next, do {} situps!", situps);
         }
+        Some(Activity::Run { minutes }) => println!("Today, run for {} minutes!", minutes),
+        _ => println!("Take a break today! Remember to stay hydrated!"),
     }
+    println!();
+    print!("{}", export::to_markdown(&plan));
 }
 
 // * 如何让 struct 持有闭包?
@@ -89,62 +93,20 @@ fn generate_workout(intensity: u32, random_number: u32) {
       ? FnOnce
 */
 
-struct Cacher<T>
-where
-    T: Fn(u32) -> u32,
-{
-    calculation: T,
-    // value: Option<u32>,
-    // * 用map存储已执行过的值, 修复value只能存储一个值的情况
-    map: HashMap<u32, u32>,
-}
-
-impl<T> Cacher<T>
-where
-    T: Fn(u32) -> u32,
-{
-    fn new(calculation: T) -> Cacher<T> {
-        let mut map = HashMap::new();
-        return Cacher {
-            calculation,
-            // value: None,
-            map,
-        };
-    }
-
-    fn value(&mut self, arg: u32) -> u32 {
-        // * 这里匹配一下self.value这个字段, 如果字段有结果, 直接返回, 说明执行过, 如果没有结果, 也就是None, 说明没有执行过, 那么执行闭包, 然后将结果赋给value, 并返回当前结果
-        // self.map.entry(arg).or_insert((self.calculation)(arg));
-        return match self.map.get(&arg) {
-            Some(v) => *v,
-            None => {
-                let v = (self.calculation)(arg);
-                self.map.insert(arg, v);
-                return v;
-            }
-        };
-        // match self.map {
-        //     Some(v) => v,
-        //     None => {
-        //         let v = (self.calculation)(arg);
-        //         self.value = Some(v);
-        //         return v;
-        //     }
-        // }
-    }
-}
+// * Cacher 的实现见 cacher.rs, 已经改成了支持任意 key 和 value 类型的泛型版本
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use closures::cacher::Cacher;
 
     #[test]
     fn call_with_different_values() {
-        let mut c = super::Cacher::new(|a| a);
-        let v1 = c.value(1);
-        let v2 = c.value(2);
+        let mut c = Cacher::new(|a| a);
+        let _v1 = c.get(1);
+        let v2 = c.get(2);
 
-        // * 这个测试必然失败, 因为在第一次调用value后, value值变成了1, 那么匹配到Some(1)后, 会直接返回1, 因此这里的v2, 值还是1
+        // * 只用一个 value 字段时这个测试必然失败, 因为在第一次调用value后, value值变成了1, 那么匹配到Some(1)后, 会直接返回1, 因此这里的v2, 值还是1
+        // * 改用 HashMap 后, 每个参数都有自己的缓存结果
         assert_eq!(v2, 2);
     }
 }
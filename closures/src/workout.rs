@@ -0,0 +1,15 @@
+// * 训练计划生成器, main 中的 generate_workout 就是基于它实现的
+// + profile: 用户档案, 训练水平和强度
+// + plan: 按周生成计划, 包括渐进超负荷和休息日安排
+// + rng: 可以指定种子的随机数生成器, 代替原来传入的"随机数"
+// + calendar: 日期计算
+// + export: 导出为 Markdown 或 iCalendar
+pub mod calendar;
+pub mod export;
+pub mod plan;
+pub mod profile;
+pub mod rng;
+
+pub use calendar::{Date, Weekday};
+pub use plan::{Activity, Day, Generator, Plan, Week};
+pub use profile::{Level, Profile};
@@ -0,0 +1,132 @@
+// * 把训练计划导出成 Markdown 表格或者 iCalendar(.ics) 文件
+// + Markdown 每周一个表格, 方便贴到笔记里
+// + iCalendar 每个训练日一个全天事件, 可以导入到日历应用中, 休息日不生成事件
+use std::fmt::Write;
+
+use super::plan::{Activity, Plan};
+
+pub fn to_markdown(plan: &Plan) -> String {
+    let profile = &plan.profile;
+    let mut out = String::new();
+    // ? 写入 String 不会失败, 所以这里的 unwrap 是安全的
+    writeln!(out, "# Workout plan for {}", profile.name).unwrap();
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "Level: {}, {} training days per week",
+        profile.level.name(),
+        profile.training_days
+    )
+    .unwrap();
+    for week in &plan.weeks {
+        writeln!(out).unwrap();
+        write!(out, "## Week {} (intensity {}", week.number, week.intensity).unwrap();
+        if week.deload {
+            write!(out, ", deload").unwrap();
+        }
+        writeln!(out, ")").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "| Day | Date | Workout |").unwrap();
+        writeln!(out, "| --- | --- | --- |").unwrap();
+        for day in &week.days {
+            writeln!(
+                out,
+                "| {} | {} | {} |",
+                day.date.weekday().short_name(),
+                day.date,
+                day.activity
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+// * iCalendar 的文本值中逗号、分号、反斜杠需要转义
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// ? RFC 5545 要求每行以 CRLF 结尾
+pub fn to_icalendar(plan: &Plan) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//closures//workout plan//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+    // * DTSTAMP 是必填项, 用计划开始的日期, 保证同样的计划导出的内容完全一样
+    let stamp = plan
+        .days()
+        .next()
+        .map(|day| day.date.compact())
+        .unwrap_or_default();
+    for day in plan.days() {
+        if day.activity == Activity::Rest {
+            continue;
+        }
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!(
+            "UID:{}-{}@workout.closures",
+            day.date.compact(),
+            escape(&plan.profile.name).replace(' ', "-")
+        ));
+        lines.push(format!("DTSTAMP:{}T000000Z", stamp));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", day.date.compact()));
+        lines.push(format!("DTEND;VALUE=DATE:{}", day.date.add_days(1).compact()));
+        lines.push(format!("SUMMARY:{}", escape(&day.activity.to_string())));
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    let mut out = lines.join("\r\n");
+    out.push_str("\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workout::calendar::Date;
+    use crate::workout::plan::Generator;
+    use crate::workout::profile::{Level, Profile};
+
+    fn plan() -> Plan {
+        let profile = Profile::new("Ann Lee", Level::Beginner, 10).with_training_days(2);
+        Generator::new(profile, 5).generate(Date::new(2026, 10, 19).unwrap(), 1)
+    }
+
+    #[test]
+    fn exports_markdown_table() {
+        let md = to_markdown(&plan());
+        assert!(md.starts_with("# Workout plan for Ann Lee\n"));
+        assert!(md.contains("## Week 1 (intensity 10)\n"));
+        assert!(md.contains("| Mon | 2026-10-19 | "));
+        assert!(md.contains("| Sun | 2026-10-25 | Rest |"));
+        assert_eq!(md.lines().filter(|l| l.starts_with("| ")).count(), 2 + 7);
+    }
+
+    #[test]
+    fn exports_icalendar_events() {
+        let ics = to_icalendar(&plan());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261019\r\nDTEND;VALUE=DATE:20261020\r\n"));
+        assert!(ics.contains("UID:20261019-Ann-Lee@workout.closures\r\n"));
+        // * 训练内容中的逗号被转义
+        assert!(ics.contains(" pushups\\, "));
+        assert!(!ics.replace("\r\n", "").contains('\n'));
+    }
+}
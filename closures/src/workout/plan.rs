@@ -0,0 +1,297 @@
+// * 按周生成训练计划
+// + 每周的训练日尽量均匀分布, 其余日子休息
+// + 渐进超负荷: 每过一个训练周, 强度按训练水平增加一定比例
+// + 每 DELOAD_EVERY 周安排一个减量周, 强度降到上一周的 80%, 让身体恢复
+// + 每天具体练什么沿用原来 generate_workout 的规则:
+//   - 强度低于 25 时做俯卧撑和仰卧起坐
+//   - 否则跑步, 但有 1/10 的概率临时休息一天
+// + 根据强度算出训练量的过程很耗时, 所以用 Cacher 缓存, 同样的强度只算一次
+use std::fmt;
+
+use super::calendar::Date;
+use super::profile::{Level, Profile};
+use super::rng::Rng;
+use crate::cacher::{Cacher, Stats};
+
+pub const DELOAD_EVERY: u32 = 4;
+
+// * 原来 main 中 simulated_expensive_calculation 的结果: 训练量就等于强度
+pub fn base_amount(intensity: u32) -> u32 {
+    intensity
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Rest,
+    Strength { pushups: u32, situps: u32 },
+    Run { minutes: u32 },
+}
+
+impl fmt::Display for Activity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Activity::Rest => write!(f, "Rest"),
+            Activity::Strength { pushups, situps } => {
+                write!(f, "{} pushups, {} situps", pushups, situps)
+            }
+            Activity::Run { minutes } => write!(f, "Run for {} minutes", minutes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Day {
+    pub date: Date,
+    pub activity: Activity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Week {
+    // * 从 1 开始
+    pub number: u32,
+    pub intensity: u32,
+    pub deload: bool,
+    pub days: Vec<Day>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub profile: Profile,
+    pub weeks: Vec<Week>,
+}
+
+impl Plan {
+    pub fn days(&self) -> impl Iterator<Item = &Day> {
+        self.weeks.iter().flat_map(|week| week.days.iter())
+    }
+
+    pub fn on(&self, date: Date) -> Option<&Day> {
+        self.days().find(|day| day.date == date)
+    }
+}
+
+// * value 的 percent%, 用 u64 计算, 超出 u32 时取 u32::MAX
+fn percent_of(value: u32, percent: u64) -> u32 {
+    let result = (value as u64).saturating_mul(percent) / 100;
+    result.min(u32::MAX as u64) as u32
+}
+
+// * 第 index 周(从 0 开始)的强度, 以及这一周是否是减量周
+// + 强度很大或者计划很长时, 强度最多增加到 u32::MAX
+pub fn week_intensity(base: u32, level: Level, index: u32) -> (u32, bool) {
+    let at_step =
+        |steps: u32| percent_of(base, 100 + level.weekly_increase() as u64 * steps as u64);
+    // * 之前经过的训练周数量, 减量周不算
+    let steps = index - index / DELOAD_EVERY;
+    if (index + 1).is_multiple_of(DELOAD_EVERY) {
+        (percent_of(at_step(steps - 1), 80), true)
+    } else {
+        (at_step(steps), false)
+    }
+}
+
+// * 一周 7 天中哪几天训练, 均匀分布, 例如 3 天时是周一、周三、周五
+pub fn training_slots(training_days: u8) -> Vec<usize> {
+    let n = training_days as usize;
+    (0..n).map(|i| i * 7 / n).collect()
+}
+
+pub struct Generator<F>
+where
+    F: Fn(u32) -> u32,
+{
+    profile: Profile,
+    rng: Rng,
+    calculation: Cacher<u32, u32, F>,
+}
+
+impl Generator<fn(u32) -> u32> {
+    pub fn new(profile: Profile, seed: u64) -> Generator<fn(u32) -> u32> {
+        Generator::with_calculation(profile, seed, base_amount)
+    }
+}
+
+impl<F> Generator<F>
+where
+    F: Fn(u32) -> u32,
+{
+    // * calculation 根据强度计算训练量
+    pub fn with_calculation(profile: Profile, seed: u64, calculation: F) -> Generator<F> {
+        Generator {
+            profile,
+            rng: Rng::new(seed),
+            calculation: Cacher::new(calculation),
+        }
+    }
+
+    // * 从 start 开始生成 weeks 周的计划, 每周从 start 对应的星期几开始
+    pub fn generate(&mut self, start: Date, weeks: u32) -> Plan {
+        let slots = training_slots(self.profile.training_days);
+        let weeks = (0..weeks)
+            .map(|index| {
+                let (intensity, deload) =
+                    week_intensity(self.profile.intensity, self.profile.level, index);
+                let days = (0..7)
+                    .map(|offset| {
+                        let activity = if slots.contains(&offset) {
+                            self.activity(intensity)
+                        } else {
+                            Activity::Rest
+                        };
+                        Day {
+                            date: start.add_days(index as i64 * 7 + offset as i64),
+                            activity,
+                        }
+                    })
+                    .collect();
+                Week {
+                    number: index + 1,
+                    intensity,
+                    deload,
+                    days,
+                }
+            })
+            .collect();
+        Plan {
+            profile: self.profile.clone(),
+            weeks,
+        }
+    }
+
+    fn activity(&mut self, intensity: u32) -> Activity {
+        if intensity < 25 {
+            let amount = self.calculation.get(intensity);
+            Activity::Strength {
+                pushups: self.vary(amount),
+                situps: self.vary(amount),
+            }
+        } else if self.rng.below(10) == 3 {
+            // * "Take a break today! Remember to stay hydrated!"
+            Activity::Rest
+        } else {
+            let amount = self.calculation.get(intensity);
+            Activity::Run {
+                minutes: self.vary(amount),
+            }
+        }
+    }
+
+    // * 在 ±10% 范围内随机调整, 让每天的训练量不完全一样
+    fn vary(&mut self, amount: u32) -> u32 {
+        percent_of(amount, self.rng.between(90, 110) as u64).max(1)
+    }
+
+    // * 训练量计算的缓存命中情况
+    pub fn stats(&self) -> Stats {
+        self.calculation.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn monday() -> Date {
+        Date::new(2026, 10, 19).unwrap()
+    }
+
+    #[test]
+    fn progressive_overload_with_deload_weeks() {
+        let intensities: Vec<(u32, bool)> = (0..8)
+            .map(|i| week_intensity(100, Level::Advanced, i))
+            .collect();
+        assert_eq!(
+            intensities,
+            vec![
+                (100, false),
+                (110, false),
+                (120, false),
+                (96, true),
+                (130, false),
+                (140, false),
+                (150, false),
+                (120, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn large_intensities_saturate_instead_of_overflowing() {
+        assert_eq!(
+            week_intensity(u32::MAX, Level::Advanced, 1),
+            (u32::MAX, false)
+        );
+        assert_eq!(
+            week_intensity(u32::MAX, Level::Advanced, 3),
+            ((u32::MAX as u64 * 80 / 100) as u32, true)
+        );
+        // * 第 u32::MAX - 1 周之前有 3221225471 个训练周, 强度是 10 * (100 + 5 * 3221225471) / 100
+        assert_eq!(
+            week_intensity(10, Level::Beginner, u32::MAX - 1),
+            (1_610_612_745, false)
+        );
+
+        let profile = Profile::new("Dee", Level::Advanced, u32::MAX);
+        let plan = Generator::new(profile, 5).generate(monday(), 2);
+        assert!(plan.days().any(|day| day.activity != Activity::Rest));
+    }
+
+    #[test]
+    fn schedules_rest_days() {
+        assert_eq!(training_slots(3), vec![0, 2, 4]);
+        assert_eq!(training_slots(4), vec![0, 1, 3, 5]);
+        assert_eq!(training_slots(6), vec![0, 1, 2, 3, 4, 5]);
+
+        let profile = Profile::new("Ann", Level::Beginner, 10);
+        let plan = Generator::new(profile, 1).generate(monday(), 2);
+        assert_eq!(plan.weeks.len(), 2);
+        for week in &plan.weeks {
+            let training = week
+                .days
+                .iter()
+                .filter(|day| day.activity != Activity::Rest)
+                .count();
+            assert_eq!(training, 3);
+            assert_eq!(week.days[6].activity, Activity::Rest);
+        }
+        assert_eq!(plan.weeks[1].days[0].date, monday().add_days(7));
+    }
+
+    #[test]
+    fn same_seed_same_plan() {
+        let profile = Profile::new("Bob", Level::Intermediate, 30);
+        let a = Generator::new(profile.clone(), 9).generate(monday(), 4);
+        let b = Generator::new(profile.clone(), 9).generate(monday(), 4);
+        let c = Generator::new(profile, 10).generate(monday(), 4);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // * 强度不低于 25 时只会跑步或休息
+        assert!(a
+            .days()
+            .all(|day| matches!(day.activity, Activity::Run { .. } | Activity::Rest)));
+    }
+
+    #[test]
+    fn expensive_calculation_runs_once_per_intensity() {
+        let calls = Cell::new(0);
+        let profile = Profile::new("Cy", Level::Beginner, 10).with_training_days(5);
+        let mut generator = Generator::with_calculation(profile, 3, |intensity| {
+            calls.set(calls.get() + 1);
+            intensity
+        });
+        let plan = generator.generate(monday(), 3);
+        // * 3 周的强度分别是 10、10(10.5 向下取整)、11
+        assert_eq!(calls.get(), 2);
+        assert_eq!(generator.stats().misses, 2);
+        assert_eq!(generator.stats().hits, 13);
+        let day = plan.on(monday()).unwrap();
+        match day.activity {
+            Activity::Strength { pushups, situps } => {
+                assert!((9..=11).contains(&pushups));
+                assert!((9..=11).contains(&situps));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
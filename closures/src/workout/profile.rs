@@ -0,0 +1,81 @@
+// * 用户的训练档案
+// + intensity 就是原来 main 中的 simulated_user_specified_value, 表示用户想要的训练强度
+// + 训练水平决定了每周默认训练几天, 以及每周强度增加多少(渐进超负荷)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+impl Level {
+    pub fn default_training_days(&self) -> u8 {
+        match self {
+            Level::Beginner => 3,
+            Level::Intermediate => 4,
+            Level::Advanced => 5,
+        }
+    }
+
+    // * 每个训练周相对于第一周增加的强度(百分比)
+    pub fn weekly_increase(&self) -> u32 {
+        match self {
+            Level::Beginner => 5,
+            Level::Intermediate => 8,
+            Level::Advanced => 10,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Beginner => "beginner",
+            Level::Intermediate => "intermediate",
+            Level::Advanced => "advanced",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub level: Level,
+    pub intensity: u32,
+    // * 每周训练的天数, 其余的日子休息
+    pub training_days: u8,
+}
+
+impl Profile {
+    pub fn new(name: &str, level: Level, intensity: u32) -> Profile {
+        Profile {
+            name: name.to_string(),
+            level,
+            intensity,
+            training_days: level.default_training_days(),
+        }
+    }
+
+    // * 每周至少要休息一天
+    pub fn with_training_days(mut self, days: u8) -> Profile {
+        assert!((1..=6).contains(&days), "training days must be between 1 and 6");
+        self.training_days = days;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn training_days_default_to_level() {
+        let p = Profile::new("Ann", Level::Intermediate, 10);
+        assert_eq!(p.training_days, 4);
+        assert_eq!(p.with_training_days(2).training_days, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "training days")]
+    fn requires_a_rest_day() {
+        Profile::new("Ann", Level::Advanced, 10).with_training_days(7);
+    }
+}
@@ -0,0 +1,83 @@
+// * 可以指定种子的伪随机数生成器(xorshift64*)
+// + 原来的 generate_workout 需要调用者传入一个"随机数", 现在由生成器自己产生
+// + 同一个种子总是产生同样的序列, 所以同一个种子生成的训练计划也完全一样, 方便测试和复现
+// ? 只用于安排训练, 不能用在需要安全性的地方
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // * 状态为 0 时 xorshift 会一直输出 0, 所以先用 SplitMix64 打散一下种子
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Rng {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // * [0, n) 之间的整数
+    pub fn below(&mut self, n: u32) -> u32 {
+        assert!(n > 0, "n must be positive");
+        // ? 取高 32 位乘以 n 再右移, 比取模更均匀
+        (((self.next_u64() >> 32) * n as u64) >> 32) as u32
+    }
+
+    // * [low, high] 之间的整数, 包含两端
+    pub fn between(&mut self, low: u32, high: u32) -> u32 {
+        assert!(low <= high, "low must not be greater than high");
+        // ? 区间的长度最多是 2^32, 超出 u32 的范围, 所以用 u64 计算
+        let span = (high - low) as u64 + 1;
+        low + (((self.next_u64() >> 32) * span) >> 32) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+        let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..5).map(|_| c.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+        // * 种子为 0 也能正常工作
+        assert_ne!(Rng::new(0).next_u64(), 0);
+    }
+
+    #[test]
+    fn values_stay_in_range() {
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 10];
+        for _ in 0..1000 {
+            let v = rng.below(10);
+            seen[v as usize] = true;
+            assert!((5..=8).contains(&rng.between(5, 8)));
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    fn between_accepts_the_full_range() {
+        let mut rng = Rng::new(7);
+        let values: Vec<u32> = (0..100).map(|_| rng.between(0, u32::MAX)).collect();
+        assert!(values.iter().any(|&v| v > u32::MAX / 2));
+        assert_eq!(rng.between(u32::MAX, u32::MAX), u32::MAX);
+        assert_eq!(rng.between(0, 0), 0);
+    }
+}
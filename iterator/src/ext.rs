@@ -0,0 +1,475 @@
+// * 自定义的迭代器适配器
+// + 和 map、filter 一样, 这些方法都只是把原来的迭代器包一层, 不调用消耗性适配器的话什么都不会做
+// + 通过扩展 trait 的方式, 给所有实现了 Iterator 的类型加上这些方法
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::iter::{Fuse, Peekable};
+
+pub trait IteratorExt: Iterator + Sized {
+    // * 长度为 size 的滑动窗口, 每次向后移动一个元素: [1, 2, 3] -> [1, 2], [2, 3]
+    fn windows(self, size: usize) -> Windows<Self>
+    where
+        Self::Item: Clone,
+    {
+        assert!(size > 0, "窗口长度必须大于 0");
+        Windows {
+            iter: self.fuse(),
+            size,
+            window: VecDeque::with_capacity(size),
+        }
+    }
+
+    // * 每 size 个元素分为一组, 最后一组可能不满 size 个
+    fn chunks(self, size: usize) -> Chunks<Self> {
+        assert!(size > 0, "分组长度必须大于 0");
+        Chunks { iter: self, size }
+    }
+
+    // * 两个迭代器的元素交替输出, 其中一个耗尽后继续输出另一个剩下的元素
+    fn interleave<J>(self, other: J) -> Interleave<Self, J::IntoIter>
+    where
+        J: IntoIterator<Item = Self::Item>,
+    {
+        Interleave {
+            a: self.fuse(),
+            b: other.into_iter().fuse(),
+            flag: false,
+        }
+    }
+
+    // * 去掉 key 相同的相邻元素, 只保留第一个
+    fn dedup_by_key<K, F>(self, key: F) -> DedupByKey<Self, K, F>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        DedupByKey {
+            iter: self,
+            key,
+            last: None,
+        }
+    }
+
+    // * 把 key 相同的相邻元素收集到一组, 产生 (key, 这一组的元素)
+    // ? 和 SQL 的 group by 不同, 只有相邻的元素才会被分到一组, 需要的话可以先排序
+    fn group_by<K, F>(self, key: F) -> GroupBy<Self, F>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        GroupBy {
+            iter: self.peekable(),
+            key,
+        }
+    }
+
+    // * 在相邻的两个元素之间插入 separator
+    // ? 标准库里有一个还没稳定的同名方法, 为了避免 unstable_name_collisions 警告, 调用时可以写成 IteratorExt::intersperse(iter, sep)
+    fn intersperse(self, separator: Self::Item) -> Intersperse<Self>
+    where
+        Self::Item: Clone,
+    {
+        Intersperse {
+            iter: self.peekable(),
+            separator,
+            needs_separator: false,
+        }
+    }
+
+    // * 笛卡尔积: 对 self 的每个元素, 都和 other 的每个元素组成一个元组
+    // + other 需要能被克隆, 因为 self 的每个元素都要把 other 从头遍历一遍
+    // + other 为空时直接结束, 不会去遍历 self(self 可能是无限的)
+    fn cartesian_product<J>(self, other: J) -> CartesianProduct<Self, J::IntoIter>
+    where
+        Self::Item: Clone,
+        J: IntoIterator,
+        J::IntoIter: Clone,
+    {
+        let other = other.into_iter();
+        CartesianProduct {
+            done: other.clone().next().is_none(),
+            a: self,
+            current: None,
+            b: other.clone(),
+            b_orig: other,
+        }
+    }
+
+    // * 合并多个已经排好序的迭代器, 产生一个整体有序的迭代器
+    // + 用一个小顶堆保存每个迭代器当前的第一个元素, 每次弹出最小的那个
+    // + 元素相等时, 先输出排在前面的迭代器里的元素
+    fn k_merge(self) -> KMerge<<Self::Item as IntoIterator>::IntoIter>
+    where
+        Self::Item: IntoIterator,
+        <Self::Item as IntoIterator>::Item: Ord,
+    {
+        let mut iters: Vec<_> = self.map(IntoIterator::into_iter).collect();
+        let mut heap = BinaryHeap::with_capacity(iters.len());
+        for (index, iter) in iters.iter_mut().enumerate() {
+            if let Some(item) = iter.next() {
+                heap.push(HeapEntry { item, index });
+            }
+        }
+        KMerge { iters, heap }
+    }
+}
+
+// * 所有迭代器都自动拥有这些方法
+impl<I: Iterator> IteratorExt for I {}
+
+pub struct Windows<I: Iterator> {
+    // ? 元素不够填满窗口时会返回 None, 之后不能再去读内部的迭代器, 所以用 fuse 包一层
+    iter: Fuse<I>,
+    size: usize,
+    window: VecDeque<I::Item>,
+}
+
+impl<I> Iterator for Windows<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // ? 第一次调用时先把窗口填满, 之后每次只需要移出最前面的一个再读入一个
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        while self.window.len() < self.size {
+            self.window.push_back(self.iter.next()?);
+        }
+        Some(self.window.iter().cloned().collect())
+    }
+}
+
+pub struct Chunks<I> {
+    iter: I,
+    size: usize,
+}
+
+impl<I: Iterator> Iterator for Chunks<I> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<_> = self.iter.by_ref().take(self.size).collect();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, high) = self.iter.size_hint();
+        (
+            low.div_ceil(self.size),
+            high.map(|high| high.div_ceil(self.size)),
+        )
+    }
+}
+
+pub struct Interleave<I, J> {
+    a: Fuse<I>,
+    b: Fuse<J>,
+    // * 为 true 时下一个元素从 b 里取
+    flag: bool,
+}
+
+impl<I, J> Iterator for Interleave<I, J>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.flag = !self.flag;
+        if self.flag {
+            self.a.next().or_else(|| self.b.next())
+        } else {
+            self.b.next().or_else(|| self.a.next())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_low, a_high) = self.a.size_hint();
+        let (b_low, b_high) = self.b.size_hint();
+        let high = match (a_high, b_high) {
+            (Some(a), Some(b)) => a.checked_add(b),
+            _ => None,
+        };
+        (a_low.saturating_add(b_low), high)
+    }
+}
+
+pub struct DedupByKey<I, K, F> {
+    iter: I,
+    key: F,
+    last: Option<K>,
+}
+
+impl<I, K, F> Iterator for DedupByKey<I, K, F>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for item in self.iter.by_ref() {
+            let key = (self.key)(&item);
+            if self.last.as_ref() != Some(&key) {
+                self.last = Some(key);
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+pub struct GroupBy<I: Iterator, F> {
+    iter: Peekable<I>,
+    key: F,
+}
+
+impl<I, K, F> Iterator for GroupBy<I, F>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = (K, Vec<I::Item>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.iter.next()?;
+        let key = (self.key)(&first);
+        let mut group = vec![first];
+        // * 只要下一个元素的 key 相同, 就放进当前这一组
+        while let Some(item) = self.iter.next_if(|item| (self.key)(item) == key) {
+            group.push(item);
+        }
+        Some((key, group))
+    }
+}
+
+pub struct Intersperse<I: Iterator> {
+    iter: Peekable<I>,
+    separator: I::Item,
+    needs_separator: bool,
+}
+
+impl<I> Iterator for Intersperse<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // * 分隔符只在后面还有元素时才插入, 这样结尾不会多出一个分隔符
+        if self.needs_separator && self.iter.peek().is_some() {
+            self.needs_separator = false;
+            return Some(self.separator.clone());
+        }
+        let item = self.iter.next()?;
+        self.needs_separator = true;
+        Some(item)
+    }
+}
+
+pub struct CartesianProduct<I: Iterator, J> {
+    a: I,
+    current: Option<I::Item>,
+    b: J,
+    b_orig: J,
+    done: bool,
+}
+
+impl<I, J> Iterator for CartesianProduct<I, J>
+where
+    I: Iterator,
+    I::Item: Clone,
+    J: Iterator + Clone,
+{
+    type Item = (I::Item, J::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            if self.current.is_none() {
+                self.current = Some(self.a.next()?);
+                self.b = self.b_orig.clone();
+            }
+            match self.b.next() {
+                Some(b) => return Some((self.current.clone()?, b)),
+                // * b 遍历完了, 换 a 的下一个元素, 再从头遍历 b
+                None => self.current = None,
+            }
+        }
+    }
+}
+
+struct HeapEntry<T> {
+    item: T,
+    index: usize,
+}
+
+impl<T: Ord> PartialEq for HeapEntry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for HeapEntry<T> {}
+
+impl<T: Ord> PartialOrd for HeapEntry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for HeapEntry<T> {
+    // * BinaryHeap 是大顶堆, 这里把比较结果反过来, 让最小的元素在堆顶
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .item
+            .cmp(&self.item)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+pub struct KMerge<I: Iterator> {
+    iters: Vec<I>,
+    heap: BinaryHeap<HeapEntry<I::Item>>,
+}
+
+impl<I> Iterator for KMerge<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let HeapEntry { item, index } = self.heap.pop()?;
+        // * 从刚才弹出元素的那个迭代器里补充下一个元素
+        if let Some(next) = self.iters[index].next() {
+            self.heap.push(HeapEntry { item: next, index });
+        }
+        Some(item)
+    }
+}
+
+#[test]
+fn windows_slide_one_by_one() {
+    let windows: Vec<_> = crate::Counter::new().windows(3).collect();
+    assert_eq!(windows, vec![vec![1, 2, 3], vec![2, 3, 4], vec![3, 4, 5]]);
+    // ? 元素不够一个窗口时什么都不产生
+    assert_eq!(crate::Counter::new().windows(6).next(), None);
+    // * 内部的迭代器返回 None 之后又有元素, 也不会再被读取
+    let mut n = 0;
+    let flaky = std::iter::from_fn(|| {
+        n += 1;
+        if n % 2 == 0 {
+            None
+        } else {
+            Some(n)
+        }
+    });
+    let mut windows = flaky.windows(1);
+    assert_eq!(windows.next(), Some(vec![1]));
+    assert_eq!(windows.next(), None);
+    assert_eq!(windows.next(), None);
+}
+
+#[test]
+fn chunks_keep_the_remainder() {
+    let chunks: Vec<_> = crate::Counter::new().chunks(2).collect();
+    assert_eq!(chunks, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    assert_eq!(crate::Counter::new().chunks(2).size_hint(), (3, Some(3)));
+}
+
+#[test]
+fn interleave_continues_with_the_longer_one() {
+    let v: Vec<_> = crate::Counter::new().interleave(vec![10, 20]).collect();
+    assert_eq!(v, vec![1, 10, 2, 20, 3, 4, 5]);
+}
+
+#[test]
+fn dedup_by_key_removes_adjacent_duplicates() {
+    let words = vec!["apple", "avocado", "banana", "blueberry", "apricot"];
+    let v: Vec<_> = words
+        .into_iter()
+        .dedup_by_key(|w| w.chars().next())
+        .collect();
+    assert_eq!(v, vec!["apple", "banana", "apricot"]);
+}
+
+#[test]
+fn group_by_collects_adjacent_runs() {
+    let groups: Vec<_> = crate::Counter::range(0, 10, 1)
+        .group_by(|x| x / 4)
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            (0, vec![0, 1, 2, 3]),
+            (1, vec![4, 5, 6, 7]),
+            (2, vec![8, 9])
+        ]
+    );
+}
+
+#[test]
+fn intersperse_puts_separator_between_items() {
+    let v: Vec<_> = IteratorExt::intersperse(crate::Counter::new(), 0).collect();
+    assert_eq!(v, vec![1, 0, 2, 0, 3, 0, 4, 0, 5]);
+    let s: String = IteratorExt::intersperse(["a", "b"].into_iter(), ", ").collect();
+    assert_eq!(s, "a, b");
+    assert_eq!(
+        IteratorExt::intersperse(std::iter::empty::<u32>(), 0).next(),
+        None
+    );
+}
+
+#[test]
+fn cartesian_product_pairs_every_element() {
+    let v: Vec<_> = crate::Counter::range(0, 2, 1)
+        .cartesian_product(['a', 'b', 'c'])
+        .collect();
+    assert_eq!(
+        v,
+        vec![(0, 'a'), (0, 'b'), (0, 'c'), (1, 'a'), (1, 'b'), (1, 'c')]
+    );
+    assert_eq!(
+        crate::Counter::new()
+            .cartesian_product(Vec::<u32>::new())
+            .next(),
+        None
+    );
+    // * self 是无限的, other 为空时也能结束
+    assert_eq!(
+        std::iter::repeat(1)
+            .cartesian_product(Vec::<u32>::new())
+            .next(),
+        None
+    );
+}
+
+#[test]
+fn k_merge_sorted_iterators() {
+    let merged: Vec<_> = vec![
+        crate::Counter::range(0, 10, 3),
+        crate::Counter::range(1, 10, 4),
+        crate::Counter::range(2, 4, 1),
+        crate::Counter::range(0, 0, 1),
+    ]
+    .into_iter()
+    .k_merge()
+    .collect();
+    assert_eq!(merged, vec![0, 1, 2, 3, 3, 5, 6, 9, 9]);
+}
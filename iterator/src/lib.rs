@@ -37,16 +37,17 @@ mod tests {
 } */
 
 #[derive(PartialEq, Debug)]
-struct Shoe {
-    size: u32,
-    style: String,
+pub struct Shoe {
+    pub size: u32,
+    pub style: String,
 }
 
 // * 返回了一个过滤后的迭代器
-fn shoes_in_my_size(shoes: Vec<Shoe>, shoe_size: u32) -> Vec<Shoe> {
+pub fn shoes_in_my_size(shoes: Vec<Shoe>, shoe_size: u32) -> Vec<Shoe> {
     // * 通过 into_iter 创建一个迭代器, 这个迭代器是获取了所有权的
     // * 相等鞋号则保留, 不等过滤
-    shoes.into_iter().filter(|x| x.size == shoe_size).collect()
+    // ? 更复杂的过滤条件见 query.rs
+    shoes.query().filter(Shoe::size_is(shoe_size)).collect()
 }
 
 /* #[test]
@@ -81,33 +82,158 @@ pub fn filer_by_size() {
     )
 } */
 
-// * 此迭代器从1 遍历到 5
-struct Counter {
-    // 存储迭代状态
-    count: u32,
+use std::iter::FusedIterator;
+
+// * 自定义的迭代器适配器, 见 ext.rs
+pub mod ext;
+pub use ext::IteratorExt;
+
+// * 基于迭代器的查询, 见 query.rs
+pub mod query;
+use query::QueryExt;
+
+// * 基于 std::thread::scope 的并行迭代, 见 par.rs
+pub mod par;
+pub use par::{ParallelIterator, ParallelSlice};
+
+// * 可以被 Counter 计数的整数类型
+// + start + step * n 这样的计算统一放到 i128 里做, 所以只为 64 位及以下的整数实现
+pub trait Step: Copy + PartialOrd {
+    fn zero() -> Self;
+
+    // * 计算 [start, end) 中以 step 为步长一共有多少个元素
+    fn steps_between(start: Self, end: Self, step: Self) -> usize;
+
+    // * 返回 start + step * n
+    fn forward(start: Self, step: Self, n: usize) -> Self;
 }
 
-impl Counter {
-    fn new() -> Counter {
-        Counter { count: 0 }
+macro_rules! impl_step {
+    ($($t:ty)*) => {
+        $(
+            impl Step for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn steps_between(start: Self, end: Self, step: Self) -> usize {
+                    if end <= start {
+                        return 0;
+                    }
+                    let distance = end as i128 - start as i128;
+                    let step = step as i128;
+                    // ? 向上取整, 最后一段不足一个步长也算一个元素
+                    let count = (distance + step - 1) / step;
+                    usize::try_from(count).unwrap_or(usize::MAX)
+                }
+
+                fn forward(start: Self, step: Self, n: usize) -> Self {
+                    (start as i128 + step as i128 * n as i128) as Self
+                }
+            }
+        )*
+    };
+}
+
+impl_step!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+// * 从 start 开始, 每次前进 step, 直到 end (不包含 end) 的计数器
+// + 迭代状态只保存剩余元素的下标区间 [front, back), 这样正向和反向迭代都能直接算出当前值
+#[derive(Clone, Debug)]
+pub struct Counter<T> {
+    start: T,
+    step: T,
+    front: usize,
+    back: usize,
+}
+
+impl Counter<u32> {
+    // * 此迭代器从1 遍历到 5
+    pub fn new() -> Counter<u32> {
+        Counter::range(1, 6, 1)
     }
 }
 
-impl Iterator for Counter {
-    type Item = u32;
+impl Default for Counter<u32> {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
 
-    // * Option<Self::Item> 大约就和 Option<u32>差不多
+impl<T: Step> Counter<T> {
+    // * 和 (start..end).step_by(step) 一样, step 必须大于 0
+    pub fn range(start: T, end: T, step: T) -> Counter<T> {
+        assert!(step > T::zero(), "Counter 的步长必须大于 0");
+        Counter {
+            start,
+            step,
+            front: 0,
+            back: T::steps_between(start, end, step),
+        }
+    }
+
+    fn value_at(&self, index: usize) -> T {
+        T::forward(self.start, self.step, index)
+    }
+}
+
+impl<T: Step> Iterator for Counter<T> {
+    type Item = T;
+
+    // * Option<Self::Item> 大约就和 Option<T>差不多
     fn next(&mut self) -> Option<Self::Item> {
-        if self.count < 5 {
-            // * 每次迭代 +1, 并返回结果
-            self.count += 1;
-            Some(self.count)
+        if self.front < self.back {
+            // * 每次迭代前进一个步长, 并返回结果
+            let value = self.value_at(self.front);
+            self.front += 1;
+            Some(value)
         } else {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    // * 默认的 nth 会调用 n 次 next, 这里直接移动下标
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+
+    fn count(self) -> usize {
+        self.len()
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
 }
 
+impl<T: Step> DoubleEndedIterator for Counter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.back -= 1;
+            Some(self.value_at(self.back))
+        } else {
+            None
+        }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.back = self.back.saturating_sub(n).max(self.front);
+        self.next_back()
+    }
+}
+
+// * size_hint 返回的上下界一致, 所以可以直接实现 ExactSizeIterator
+impl<T: Step> ExactSizeIterator for Counter<T> {}
+
+// * 耗尽之后 front == back, 再调用 next 也只会一直返回 None
+impl<T: Step> FusedIterator for Counter<T> {}
+
 // #[test]
 // fn calling_next_directly() {
 //   let mut counter = Counter::new();
@@ -132,3 +258,71 @@ fn using_other_iterator_trait_methods() {
         .sum();
     assert_eq!(18, sum);
 }
+
+#[test]
+fn counter_with_custom_range() {
+    let counter = Counter::range(10i64, 20, 3);
+    assert_eq!(counter.len(), 4);
+    assert_eq!(counter.collect::<Vec<_>>(), vec![10, 13, 16, 19]);
+
+    let negative: Vec<i8> = Counter::range(-5, 5, 4).collect();
+    assert_eq!(negative, vec![-5, -1, 3]);
+
+    // ? end 不大于 start 时是一个空的迭代器
+    assert_eq!(Counter::range(5u8, 5, 1).next(), None);
+    assert_eq!(Counter::range(5u8, 1, 1).len(), 0);
+}
+
+#[test]
+fn counter_is_double_ended() {
+    let mut counter = Counter::range(0u32, 10, 2);
+    assert_eq!(counter.next_back(), Some(8));
+    assert_eq!(counter.next(), Some(0));
+    assert_eq!(counter.len(), 3);
+    assert_eq!(counter.rev().collect::<Vec<_>>(), vec![6, 4, 2]);
+}
+
+#[test]
+fn counter_nth_and_size_hint() {
+    let mut counter = Counter::range(0usize, 1_000_000, 1);
+    assert_eq!(counter.nth(999_998), Some(999_998));
+    assert_eq!(counter.size_hint(), (1, Some(1)));
+    assert_eq!(counter.nth(5), None);
+    // * FusedIterator: 耗尽之后一直返回 None
+    assert_eq!(counter.next(), None);
+    assert_eq!(counter.next_back(), None);
+
+    let mut counter = Counter::range(0u64, 10, 1);
+    assert_eq!(counter.nth_back(2), Some(7));
+    assert_eq!(counter.nth_back(100), None);
+    assert_eq!(counter.next(), None);
+}
+
+#[test]
+fn counter_reaches_type_bounds() {
+    let tail: Vec<u8> = Counter::range(250, u8::MAX, 2).collect();
+    assert_eq!(tail, vec![250, 252, 254]);
+    assert_eq!(Counter::range(i32::MIN, i32::MAX, i32::MAX).len(), 3);
+    assert_eq!(
+        Counter::range(i32::MIN, i32::MAX, i32::MAX).last(),
+        Some(i32::MAX - 1)
+    );
+}
+
+#[test]
+#[should_panic]
+fn counter_rejects_zero_step() {
+    Counter::range(0, 10, 0);
+}
+
+#[test]
+fn using_other_iterator_trait_methods_on_ranges() {
+    // ? 和上面的例子一样, 只不过换成了 i64 并且从 -3 开始
+    let sum: i64 = Counter::range(-3i64, 3, 1)
+        .zip(Counter::range(-3i64, 3, 1).skip(1))
+        .map(|(a, b)| a * b)
+        .filter(|x| x % 3 == 0)
+        .sum();
+    // ? 乘积为 6, 2, 0, 0, 2, 其中能整除3的是 6, 0, 0
+    assert_eq!(6, sum);
+}
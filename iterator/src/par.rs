@@ -0,0 +1,229 @@
+// * 基于 std::thread::scope 的并行迭代
+// + 把元素平均切分成若干块, 每一块交给一个线程处理, 最后再按原来的顺序把结果拼起来
+// + scope 会在返回前等待所有线程结束, 所以线程里可以直接借用闭包和切片, 不需要 Arc
+// ? 元素太少时开线程反而更慢, 所以每个线程至少分到 MIN_CHUNK_LEN 个元素
+use std::iter::Sum;
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::IteratorExt;
+
+pub const MIN_CHUNK_LEN: usize = 4096;
+
+// * 根据元素个数决定开几个线程, 不会超过 CPU 的并行度
+fn thread_count(len: usize) -> usize {
+    let available = thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1);
+    available.min(len.div_ceil(MIN_CHUNK_LEN)).max(1)
+}
+
+// * 每一块的长度, 向上取整保证块数不超过线程数
+fn chunk_len(len: usize) -> usize {
+    len.div_ceil(thread_count(len)).max(1)
+}
+
+// * 等待线程结束, 线程里发生的 panic 会原样抛到调用方
+fn join_all<R>(handles: Vec<thread::ScopedJoinHandle<'_, R>>) -> Vec<R> {
+    handles
+        .into_iter()
+        .map(|handle| {
+            handle
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e))
+        })
+        .collect()
+}
+
+// * 对每一块元素执行 work, 返回按块顺序排列的结果
+// + 迭代器只能顺序读取, 所以这里一边读出一块, 一边就把这一块交给新的线程
+fn run_chunks<I, R, F>(iter: I, work: F) -> Vec<R>
+where
+    I: ExactSizeIterator,
+    I::Item: Send,
+    R: Send,
+    F: Fn(Vec<I::Item>) -> R + Sync,
+{
+    let size = chunk_len(iter.len());
+    let work = &work;
+    thread::scope(|scope| {
+        let handles = iter
+            .chunks(size)
+            .map(|chunk| scope.spawn(move || work(chunk)))
+            .collect();
+        join_all(handles)
+    })
+}
+
+// * 切片可以直接切分, 不需要像迭代器那样先把元素读出来
+fn run_slice_chunks<'a, T, R, F>(slice: &'a [T], work: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&'a [T]) -> R + Sync,
+{
+    let size = chunk_len(slice.len());
+    let work = &work;
+    thread::scope(|scope| {
+        let handles = slice
+            .chunks(size)
+            .map(|chunk| scope.spawn(move || work(chunk)))
+            .collect();
+        join_all(handles)
+    })
+}
+
+// * 给所有长度已知的迭代器加上并行方法, 例如 Counter::range(0, 1_000_000, 1).par_sum()
+pub trait ParallelIterator: ExactSizeIterator + Sized
+where
+    Self::Item: Send,
+{
+    // * 并行地对每个元素执行 f, 结果的顺序和原来的元素顺序一致
+    fn par_map<U, F>(self, f: F) -> Vec<U>
+    where
+        U: Send,
+        F: Fn(Self::Item) -> U + Sync,
+    {
+        run_chunks(self, |chunk| chunk.into_iter().map(&f).collect::<Vec<_>>())
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    // * 并行地过滤, 保留下来的元素保持原来的顺序
+    fn par_filter<F>(self, f: F) -> Vec<Self::Item>
+    where
+        F: Fn(&Self::Item) -> bool + Sync,
+    {
+        run_chunks(self, |chunk| {
+            chunk.into_iter().filter(&f).collect::<Vec<_>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    // * 每个线程先求出自己那一块的和, 再把各块的和加起来
+    fn par_sum<S>(self) -> S
+    where
+        S: Sum<Self::Item> + Sum<S> + Send,
+    {
+        run_chunks(self, |chunk| chunk.into_iter().sum::<S>())
+            .into_iter()
+            .sum()
+    }
+}
+
+impl<I> ParallelIterator for I
+where
+    I: ExactSizeIterator,
+    I::Item: Send,
+{
+}
+
+// * 切片版本, 元素以引用的形式交给闭包
+pub trait ParallelSlice<T: Sync> {
+    fn par_map<U, F>(&self, f: F) -> Vec<U>
+    where
+        U: Send,
+        F: Fn(&T) -> U + Sync;
+
+    fn par_filter<F>(&self, f: F) -> Vec<&T>
+    where
+        F: Fn(&T) -> bool + Sync;
+
+    fn par_sum<'a, S>(&'a self) -> S
+    where
+        T: 'a,
+        S: Sum<&'a T> + Sum<S> + Send;
+}
+
+impl<T: Sync> ParallelSlice<T> for [T] {
+    fn par_map<U, F>(&self, f: F) -> Vec<U>
+    where
+        U: Send,
+        F: Fn(&T) -> U + Sync,
+    {
+        run_slice_chunks(self, |chunk| chunk.iter().map(&f).collect::<Vec<_>>())
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn par_filter<F>(&self, f: F) -> Vec<&T>
+    where
+        F: Fn(&T) -> bool + Sync,
+    {
+        run_slice_chunks(self, |chunk| {
+            chunk.iter().filter(|item| f(item)).collect::<Vec<_>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn par_sum<'a, S>(&'a self) -> S
+    where
+        T: 'a,
+        S: Sum<&'a T> + Sum<S> + Send,
+    {
+        run_slice_chunks(self, |chunk| chunk.iter().sum::<S>())
+            .into_iter()
+            .sum()
+    }
+}
+
+#[test]
+fn par_map_keeps_order() {
+    let squares = crate::Counter::range(0u64, 100_000, 1).par_map(|x| x * x);
+    let expected: Vec<u64> = crate::Counter::range(0u64, 100_000, 1)
+        .map(|x| x * x)
+        .collect();
+    assert_eq!(squares, expected);
+}
+
+#[test]
+fn par_filter_and_sum_on_millions() {
+    let n = 3_000_000u64;
+    let sum: u64 = crate::Counter::range(0, n, 1).par_sum();
+    assert_eq!(sum, n * (n - 1) / 2);
+
+    let multiples = crate::Counter::range(0, n, 1).par_filter(|x| x % 1_000_000 == 0);
+    assert_eq!(multiples, vec![0, 1_000_000, 2_000_000]);
+}
+
+#[test]
+fn using_other_iterator_trait_methods_in_parallel() {
+    // ? 和 lib.rs 里的例子一样, 只是最后的 filter 和 sum 换成了并行版本
+    // ? zip、skip、map 都会保留 ExactSizeIterator, 所以可以直接接在后面
+    let sum: u32 = crate::Counter::new()
+        .zip(crate::Counter::new().skip(1))
+        .map(|(a, b)| a * b)
+        .par_filter(|x| x % 3 == 0)
+        .into_iter()
+        .par_sum();
+    assert_eq!(18, sum);
+}
+
+#[test]
+fn par_slice_methods() {
+    let v: Vec<i64> = (0..50_000).collect();
+    assert_eq!(v.par_sum::<i64>(), v.iter().sum::<i64>());
+    assert_eq!(v.par_map(|x| x + 1)[49_999], 50_000);
+    assert_eq!(v.par_filter(|x| *x >= 49_998), vec![&49_998, &49_999]);
+
+    let empty: [i64; 0] = [];
+    assert_eq!(empty.par_sum::<i64>(), 0);
+    assert!(empty.par_map(|x| x * 2).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn par_map_propagates_panics() {
+    crate::Counter::range(0u32, 100_000, 1).par_map(|x| {
+        if x == 99_999 {
+            panic!("boom");
+        }
+        x
+    });
+}
@@ -0,0 +1,342 @@
+// * 基于迭代器的内存查询
+// + shoes_in_my_size 只能按鞋号精确过滤, 这里把过滤条件抽象成 Predicate, 可以用 and / or / not 自由组合
+// + 查询本身还是一个迭代器, 过滤和分页都是惰性的, 只有排序和分组需要先把元素收集起来
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::iter::{Skip, Take};
+use std::ops::RangeBounds;
+
+use crate::Shoe;
+
+// * 过滤条件, 任何结构体都可以通过实现(或者直接用闭包)这个 trait 来参与查询
+pub trait Predicate<T: ?Sized> {
+    fn test(&self, item: &T) -> bool;
+
+    // * 两个条件都满足
+    fn and<P>(self, other: P) -> And<Self, P>
+    where
+        Self: Sized,
+        P: Predicate<T>,
+    {
+        And(self, other)
+    }
+
+    // * 满足其中一个条件即可
+    fn or<P>(self, other: P) -> Or<Self, P>
+    where
+        Self: Sized,
+        P: Predicate<T>,
+    {
+        Or(self, other)
+    }
+
+    // * 条件取反
+    fn negate(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+// * 闭包就是最简单的过滤条件
+impl<T: ?Sized, F> Predicate<T> for F
+where
+    F: Fn(&T) -> bool,
+{
+    fn test(&self, item: &T) -> bool {
+        self(item)
+    }
+}
+
+pub struct And<A, B>(A, B);
+
+impl<T: ?Sized, A: Predicate<T>, B: Predicate<T>> Predicate<T> for And<A, B> {
+    fn test(&self, item: &T) -> bool {
+        self.0.test(item) && self.1.test(item)
+    }
+}
+
+pub struct Or<A, B>(A, B);
+
+impl<T: ?Sized, A: Predicate<T>, B: Predicate<T>> Predicate<T> for Or<A, B> {
+    fn test(&self, item: &T) -> bool {
+        self.0.test(item) || self.1.test(item)
+    }
+}
+
+pub struct Not<A>(A);
+
+impl<T: ?Sized, A: Predicate<T>> Predicate<T> for Not<A> {
+    fn test(&self, item: &T) -> bool {
+        !self.0.test(item)
+    }
+}
+
+// * 通过一个取值的闭包来描述结构体的某个字段, 再基于字段构造过滤条件
+// + 例如: field(|s: &Shoe| s.size).in_range(7..=10)
+pub struct Field<F> {
+    get: F,
+}
+
+pub fn field<T, V, F>(get: F) -> Field<F>
+where
+    T: ?Sized,
+    F: Fn(&T) -> V,
+{
+    Field { get }
+}
+
+impl<F> Field<F> {
+    pub fn equals<T, V>(self, value: V) -> impl Predicate<T>
+    where
+        T: ?Sized,
+        V: PartialEq,
+        F: Fn(&T) -> V,
+    {
+        move |item: &T| (self.get)(item) == value
+    }
+
+    pub fn in_range<T, V, R>(self, range: R) -> impl Predicate<T>
+    where
+        T: ?Sized,
+        V: PartialOrd,
+        R: RangeBounds<V>,
+        F: Fn(&T) -> V,
+    {
+        move |item: &T| range.contains(&(self.get)(item))
+    }
+
+    pub fn matches<T, V, P>(self, predicate: P) -> impl Predicate<T>
+    where
+        T: ?Sized,
+        F: Fn(&T) -> V,
+        P: Fn(&V) -> bool,
+    {
+        move |item: &T| predicate(&(self.get)(item))
+    }
+}
+
+// * Shoe 常用的过滤条件
+impl Shoe {
+    pub fn size_is(size: u32) -> impl Predicate<Shoe> {
+        field(|s: &Shoe| s.size).equals(size)
+    }
+
+    pub fn size_in<R: RangeBounds<u32>>(range: R) -> impl Predicate<Shoe> {
+        field(|s: &Shoe| s.size).in_range(range)
+    }
+
+    pub fn style_is(style: &str) -> impl Predicate<Shoe> + '_ {
+        // ? 取值闭包只能返回不借用 Shoe 的值, 所以字符串字段直接写成闭包
+        move |s: &Shoe| s.style == style
+    }
+
+    pub fn style_contains(pattern: &str) -> impl Predicate<Shoe> + '_ {
+        move |s: &Shoe| s.style.contains(pattern)
+    }
+}
+
+// * 查询, 本身就是一个迭代器, 可以继续接其他迭代器适配器, 也可以 collect
+pub struct Query<I> {
+    iter: I,
+}
+
+// * 为所有可以 into_iter 的集合加上 query 方法
+// + 元素既可以是 T 也可以是 &T, 所以 vec.query() 和 vec.iter().query() 都能使用同样的过滤条件
+pub trait QueryExt: IntoIterator + Sized {
+    fn query(self) -> Query<Self::IntoIter> {
+        Query {
+            iter: self.into_iter(),
+        }
+    }
+}
+
+impl<I: IntoIterator> QueryExt for I {}
+
+impl<I: Iterator> Query<I> {
+    // * 只保留满足条件的元素
+    pub fn filter<T, P>(self, predicate: P) -> Query<Where<I, P, T>>
+    where
+        T: ?Sized,
+        I::Item: Borrow<T>,
+        P: Predicate<T>,
+    {
+        Query {
+            iter: Where {
+                iter: self.iter,
+                predicate,
+                _item: std::marker::PhantomData,
+            },
+        }
+    }
+
+    // * 排序需要看到所有元素, 所以这里会先收集到 Vec 里, 再返回它的迭代器
+    // ? 使用稳定排序, key 相同的元素保持原来的顺序
+    pub fn sort_by_key<K, F>(self, key: F) -> Query<std::vec::IntoIter<I::Item>>
+    where
+        K: Ord,
+        F: FnMut(&I::Item) -> K,
+    {
+        let mut items: Vec<_> = self.iter.collect();
+        items.sort_by_key(key);
+        items.query()
+    }
+
+    pub fn sort_by<F>(self, compare: F) -> Query<std::vec::IntoIter<I::Item>>
+    where
+        F: FnMut(&I::Item, &I::Item) -> std::cmp::Ordering,
+    {
+        let mut items: Vec<_> = self.iter.collect();
+        items.sort_by(compare);
+        items.query()
+    }
+
+    // * 按 key 分组, 产生按 key 排好序的 (key, 这一组的元素)
+    // ? 和 IteratorExt::group_by 不同, 这里不要求相同 key 的元素相邻
+    pub fn group_by<K, F>(
+        self,
+        mut key: F,
+    ) -> std::collections::btree_map::IntoIter<K, Vec<I::Item>>
+    where
+        K: Ord,
+        F: FnMut(&I::Item) -> K,
+    {
+        let mut groups: BTreeMap<K, Vec<I::Item>> = BTreeMap::new();
+        for item in self.iter {
+            groups.entry(key(&item)).or_default().push(item);
+        }
+        groups.into_iter()
+    }
+
+    // * 分页, page 从 0 开始, 每页 per_page 个元素
+    pub fn page(self, page: usize, per_page: usize) -> Query<Take<Skip<I>>> {
+        Query {
+            iter: self.iter.skip(page.saturating_mul(per_page)).take(per_page),
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for Query<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+pub struct Where<I, P, T: ?Sized> {
+    iter: I,
+    predicate: P,
+    // ? 只是为了记住过滤条件作用的类型
+    _item: std::marker::PhantomData<fn(&T)>,
+}
+
+impl<I, P, T> Iterator for Where<I, P, T>
+where
+    T: ?Sized,
+    I: Iterator,
+    I::Item: Borrow<T>,
+    P: Predicate<T>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let predicate = &self.predicate;
+        self.iter.find(|item| predicate.test(item.borrow()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+#[cfg(test)]
+fn shoes() -> Vec<Shoe> {
+    [
+        (10, "sneaker"),
+        (13, "sandal"),
+        (10, "boot"),
+        (8, "ankle boot"),
+        (11, "sneaker"),
+    ]
+    .into_iter()
+    .map(|(size, style)| Shoe {
+        size,
+        style: String::from(style),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+fn styles<'a>(shoes: impl IntoIterator<Item = &'a Shoe>) -> Vec<&'a str> {
+    shoes.into_iter().map(|s| s.style.as_str()).collect()
+}
+
+#[test]
+fn filter_by_size_range_and_style() {
+    let shoes = shoes();
+    let found: Vec<_> = shoes
+        .iter()
+        .query()
+        .filter(Shoe::size_in(9..=11).and(Shoe::style_contains("boot").negate()))
+        .collect();
+    assert_eq!(styles(found), vec!["sneaker", "sneaker"]);
+
+    let found: Vec<_> = shoes
+        .iter()
+        .query()
+        .filter(Shoe::style_is("sandal").or(Shoe::size_is(8)))
+        .collect();
+    assert_eq!(styles(found), vec!["sandal", "ankle boot"]);
+}
+
+#[test]
+fn filter_by_owned_items_and_closures() {
+    let found: Vec<Shoe> = shoes().query().filter(|s: &Shoe| s.size > 10).collect();
+    assert_eq!(found.len(), 2);
+
+    let in_my_size = crate::shoes_in_my_size(shoes(), 10);
+    assert_eq!(styles(&in_my_size), vec!["sneaker", "boot"]);
+}
+
+#[test]
+fn sort_and_paginate() {
+    let shoes = shoes();
+    let page: Vec<_> = shoes
+        .iter()
+        .query()
+        .sort_by_key(|s| s.size)
+        .page(1, 2)
+        .collect();
+    assert_eq!(styles(page), vec!["boot", "sneaker"]);
+
+    let last_page: Vec<_> = shoes.iter().query().page(2, 2).collect();
+    assert_eq!(styles(last_page), vec!["sneaker"]);
+    assert_eq!(shoes.iter().query().page(5, 2).next(), None);
+
+    let by_style: Vec<_> = shoes
+        .iter()
+        .query()
+        .sort_by(|a, b| a.style.cmp(&b.style))
+        .map(|s| s.size)
+        .collect();
+    assert_eq!(by_style, vec![8, 10, 13, 10, 11]);
+}
+
+#[test]
+fn group_by_size() {
+    let shoes = shoes();
+    let groups: Vec<_> = shoes
+        .iter()
+        .query()
+        .filter(field(|s: &Shoe| s.style.len()).matches(|len| *len < 7))
+        .group_by(|s| s.size)
+        .map(|(size, group)| (size, styles(group)))
+        .collect();
+    assert_eq!(groups, vec![(10, vec!["boot"]), (13, vec!["sandal"])]);
+}
@@ -0,0 +1,19 @@
+// * 命令行博客编辑器, 命令的说明见 src/modules/blog/cli.rs
+// + 例如: cargo run --bin blog -- --as ferris new "I ate a salad for lunch today"
+use std::env;
+use std::io;
+use std::process;
+
+use oop_design_patterns::modules::blog::cli::{self, Cli};
+
+fn main() {
+    let cli = Cli::new(env::args(), |name| env::var(name).ok()).unwrap_or_else(|err| {
+        eprintln!("解析参数错误: {}", err);
+        eprintln!("{}", cli::USAGE);
+        process::exit(2);
+    });
+    if let Err(e) = cli::run(&cli, io::stdin().lock(), &mut io::stdout().lock()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -10,26 +10,78 @@
 
     + 1. 博文从空白的草案开始
     + 2. 一旦草案完成, 请求审核博文
-    + 3. 一旦博文过审, 它将被发表
+    + 3. 一旦博文过审, 它将被发表; 审核不通过则带着原因退回草案
     + 4. 只有被发表的博文的内容会被打印, 这样就不会以外打印出没有被审核的博文的文本
 */
-use oop_design_patterns::modules::blog::Post;
+use oop_design_patterns::modules::blog::{
+    Actor, Approval, ApprovalPolicy, DraftPost, Operation, Post, PostError, Reviewer, Status,
+};
 fn main() {
     // * new方法创建新的博文草案。也希望在草案阶段为博文编写一些文本。
     // * 如果在审批之前尝试立即获取博文的内容, 不应该获取到任何文本, 因为博文仍然是草案
-    let mut post = Post::new();
+    // * written_by 指定作者, 只有作者本人可以编辑文本和请求审核
+    // * 每个修改博文的方法都要传入执行操作的人(Actor), 不允许的操作返回 PostError
+    let ferris = Actor::author("ferris");
+    let mut post = Post::new().written_by("ferris");
 
-    post.add_text("I ate a salad for lunch today");
+    post.add_text(&ferris, "I ate a salad for lunch today").unwrap();
     // * 此处一个好的单元测试应该断言 草案博文的 content 方法返回空的字符串, 不过这里并不准备为这个例子编写单元测试
     assert_eq!("", post.content());
 
     // * 接下来需要请求审核博文, 而在等待审核阶段, content应该依然返回空字符串
-    post.request_review();
+    post.request_review(&ferris).unwrap();
     assert_eq!("", post.content());
+    // * 作者不能审核, 等待审核时也不能再修改文本
+    assert!(post.approve(&ferris).is_err());
+    assert_eq!(
+        Err(PostError::InvalidState {
+            status: Status::PendingReview,
+            operation: Operation::Edit,
+        }),
+        post.add_text(&ferris, "!")
+    );
+
+    // * 审核不通过时可以退回草案, 并说明原因; 作者修改后重新请求审核
+    post.reject(&Reviewer::editor("editor"), "Say what kind of salad it was").unwrap();
+    assert_eq!(Some("Say what kind of salad it was"), post.rejection_reason());
+    post.add_text(&ferris, " (a caesar salad)").unwrap();
+    post.request_review(&ferris).unwrap();
+    assert_eq!(None, post.rejection_reason());
 
     // * 博文审核通过, 他应该被发表, 意味着 content应该返回对应的内容
-    post.approve();
-    assert_eq!("I ate a salad for lunch today", post.content());
+    post.approve(&Reviewer::editor("editor")).unwrap();
+    assert_eq!("I ate a salad for lunch today (a caesar salad)", post.content());
+    // * 发布之后作者也不能再修改文本
+    assert!(post.add_text(&ferris, " and fries").is_err());
+    // * 发布后的内容可以渲染成 HTML
+    assert_eq!("<p>I ate a salad for lunch today (a caesar salad)</p>\n", post.render_html());
+    // * 每次修改和状态转换都记录在历史中: 谁、什么时候、做了什么
+    for event in post.history() {
+        println!("{} {:?} -> {:?}", event.actor, event.action, event.status);
+    }
+    assert_eq!(6, post.history().len());
+
+    // * 也可以要求更严格的审核: 两个不同编辑的通过
+    let mut post = Post::with_policy(ApprovalPolicy::new().required_approvals(2))
+        .written_by("ferris");
+    post.add_text(&ferris, "Breaking news").unwrap();
+    post.request_review(&ferris).unwrap();
+    post.approve(&Reviewer::editor("alice")).unwrap();
+    // * 同一个审核人重复通过只算一次
+    post.approve(&Reviewer::editor("alice")).unwrap();
+    assert_eq!("", post.content());
+    post.approve(&Reviewer::editor("bob")).unwrap();
+    assert_eq!("Breaking news", post.content());
+
+    // * 把状态编码为类型的版本: 草案没有 content 方法, 也不能直接 approve, 写错了编译都不会通过
+    let mut draft = DraftPost::new();
+    draft.add_text("Typed salad");
+    let pending = draft.request_review();
+    if let Ok(Approval::Published(published)) = pending.approve(&Reviewer::editor("editor")) {
+        assert_eq!("Typed salad", published.content());
+        // * 也可以转换回 Post, 和旧的代码一起使用
+        assert_eq!("Typed salad", Post::from(published).content());
+    }
     /* 
         注意我们与 crate 交互的唯一的类型是 Post。
         这个类型会使用状态模式并会存放处于三种博文所可能的状态之一的值 —— 草案，等待审核和发布。
@@ -1,31 +1,182 @@
 // mod blog
 
+// * 审核人和审核策略, 见 blog/review.rs
+pub mod review;
+
+pub use review::{Actor, ApprovalPolicy, Reviewer, Role};
+
+// * 角色权限和操作失败时的错误, 见 blog/permission.rs
+pub mod permission;
+
+pub use permission::{Operation, PostError};
+
+// * 编译期检查状态转换的版本, 见 blog/typed.rs
+pub mod typed;
+
+pub use typed::{Approval, DraftPost, PendingReviewPost, PublishedPost};
+
+// * 时钟和审计历史, 见 blog/clock.rs 和 blog/history.rs
+pub mod clock;
+pub mod history;
+
+pub use clock::{Clock, FakeClock, SystemClock};
+pub use history::{Action, Event};
+
+// * 保存到 JSON 文件, 见 blog/store.rs 和 blog/json.rs
+pub mod json;
+pub mod store;
+
+pub use store::{PostStore, StoreError};
+
+// * 把已发布的 Markdown 内容渲染成 HTML, 见 blog/markdown.rs
+pub mod markdown;
+
+// * 命令行编辑器, 见 blog/cli.rs 和 src/bin/blog.rs
+pub mod cli;
+
+use std::time::SystemTime;
+
+// * 没有指定作者时使用的名字
+pub const ANONYMOUS: &str = "anonymous";
+
+// * 博文当前处于哪个阶段, 方便调用者在不关心具体状态对象的情况下判断
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Draft,
+    PendingReview,
+    // * 已经通过审核, 但是要等到指定的时间才会公开(禁发期)
+    Scheduled,
+    Published,
+    // * 已归档, 内容不再公开
+    Archived,
+}
+
+// * 状态的英文名字, 用在错误信息、命令行和保存的文件中
+pub fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Draft => "draft",
+        Status::PendingReview => "pending_review",
+        Status::Scheduled => "scheduled",
+        Status::Published => "published",
+        Status::Archived => "archived",
+    }
+}
+
+pub fn parse_status(name: &str) -> Option<Status> {
+    match name {
+        "draft" => Some(Status::Draft),
+        "pending_review" => Some(Status::PendingReview),
+        "scheduled" => Some(Status::Scheduled),
+        "published" => Some(Status::Published),
+        "archived" => Some(Status::Archived),
+        _ => None,
+    }
+}
+
 // 不同状态博文所共享的行为
 trait State {
     // * 这里使用的 self: Box<Self>, 表示该方法只能在持有这个类型的 Box上被调用。
     fn request_review(self: Box<Self>) -> Box<dyn State>;
-    fn approve(self: Box<Self>) -> Box<dyn State>;
+    // * 记录一个审核人的通过, 由策略决定是否已经可以发布
+    // + publish_at 不为空时, 满足策略后进入 Scheduled, 等到这个时间才公开
+    fn approve(
+        self: Box<Self>,
+        reviewer: &Reviewer,
+        policy: &ApprovalPolicy,
+        publish_at: Option<SystemTime>,
+    ) -> Box<dyn State>;
+    // * 审核不通过, 退回草案, 并记录原因
+    fn reject(self: Box<Self>, reason: &str, policy: &ApprovalPolicy) -> Box<dyn State>;
+    // * 已发布(包括定时发布)的博文可以归档, 其他状态下 Post 会先返回 PostError, 不会调用到这里
+    fn archive(self: Box<Self>) -> Box<dyn State>;
     fn content<'a>(&self, post: &'a Post) -> &'a str;
+    // * 计划公开的时间
+    fn publish_at(&self) -> Option<SystemTime> {
+        None
+    }
+    // * 只有被退回的草案才有退回原因
+    fn rejection_reason(&self) -> Option<&str> {
+        None
+    }
+    // * 已经获得的通过
+    fn approvals(&self) -> &[Reviewer];
+    fn status(&self) -> Status;
+    // * 当前状态是否允许这个操作, 不允许时 Post 会返回 PostError::InvalidState
+    fn allows(&self, operation: Operation) -> bool;
 }
-struct Draft {}
 
-struct PendingReview {}
+// * 被退回的草案会带着退回原因, 重新提交审核后原因就不再需要了
+// + 策略不要求退回时清空通过的话, 之前获得的通过也会保留到下一轮审核
+struct Draft {
+    rejection_reason: Option<String>,
+    approvals: Vec<Reviewer>,
+}
 
-struct Published {}
+// * publish_at 是审核人要求的公开时间, 最后一个满足策略的通过出现之前也会一直保留
+struct PendingReview {
+    approvals: Vec<Reviewer>,
+    publish_at: Option<SystemTime>,
+}
+
+struct Scheduled {
+    approvals: Vec<Reviewer>,
+    publish_at: SystemTime,
+}
+
+struct Published {
+    approvals: Vec<Reviewer>,
+}
+
+struct Archived {
+    approvals: Vec<Reviewer>,
+}
 
 impl State for Draft {
     // * Draft 的 request_review 方法需要返回一个新的，装箱的 PendingReview 结构体的实例，其用来代表博文处于等待审核状态
     fn request_review(self: Box<Self>) -> Box<dyn State> {
-        return Box::new(PendingReview {});
+        return Box::new(PendingReview {
+            approvals: self.approvals,
+            publish_at: None,
+        });
     }
 
-    fn approve(self: Box<Self>) -> Box<dyn State> {
+    fn approve(
+        self: Box<Self>,
+        _reviewer: &Reviewer,
+        _policy: &ApprovalPolicy,
+        _publish_at: Option<SystemTime>,
+    ) -> Box<dyn State> {
         return self;
     }
 
+    // * 草案还没有提交审核, 无法退回
+    fn reject(self: Box<Self>, _reason: &str, _policy: &ApprovalPolicy) -> Box<dyn State> {
+        self
+    }
+
+    fn archive(self: Box<Self>) -> Box<dyn State> {
+        self
+    }
+
     fn content<'a>(&self, post: &'a Post) -> &'a str {
         return "";
     }
+
+    fn rejection_reason(&self) -> Option<&str> {
+        self.rejection_reason.as_deref()
+    }
+
+    fn approvals(&self) -> &[Reviewer] {
+        &self.approvals
+    }
+
+    fn status(&self) -> Status {
+        Status::Draft
+    }
+
+    fn allows(&self, operation: Operation) -> bool {
+        matches!(operation, Operation::Edit | Operation::RequestReview)
+    }
 }
 
 impl State for PendingReview {
@@ -34,19 +185,128 @@ impl State for PendingReview {
         self
     }
 
-    fn approve(self: Box<Self>) -> Box<dyn State> {
-        // * 表示状态走向完成
-        return Box::new(Published {})
+    fn approve(
+        mut self: Box<Self>,
+        reviewer: &Reviewer,
+        policy: &ApprovalPolicy,
+        publish_at: Option<SystemTime>,
+    ) -> Box<dyn State> {
+        review::record(&mut self.approvals, reviewer);
+        if publish_at.is_some() {
+            self.publish_at = publish_at;
+        }
+        if !policy.is_satisfied(&self.approvals) {
+            return self;
+        }
+        match self.publish_at {
+            Some(publish_at) => Box::new(Scheduled {
+                approvals: self.approvals,
+                publish_at,
+            }),
+            // * 表示状态走向完成
+            None => Box::new(Published {
+                approvals: self.approvals,
+            }),
+        }
+    }
+
+    // * 退回草案, 作者修改之后可以再次请求审核
+    fn reject(self: Box<Self>, reason: &str, policy: &ApprovalPolicy) -> Box<dyn State> {
+        let approvals = if policy.resets_on_rejection() {
+            Vec::new()
+        } else {
+            self.approvals
+        };
+        Box::new(Draft {
+            rejection_reason: Some(reason.to_string()),
+            approvals,
+        })
+    }
+
+    fn archive(self: Box<Self>) -> Box<dyn State> {
+        self
     }
 
     fn content<'a>(&self, post: &'a Post) -> &'a str {
         return "";
     }
+
+    fn publish_at(&self) -> Option<SystemTime> {
+        self.publish_at
+    }
+
+    fn approvals(&self) -> &[Reviewer] {
+        &self.approvals
+    }
+
+    fn status(&self) -> Status {
+        Status::PendingReview
+    }
+
+    fn allows(&self, operation: Operation) -> bool {
+        matches!(operation, Operation::Approve | Operation::Reject)
+    }
+}
+
+// * 定时发布: 审核已经完成, 时钟走过 publish_at 之后内容自动公开, 不需要再调用任何方法
+impl State for Scheduled {
+    fn request_review(self: Box<Self>) -> Box<dyn State> {
+        self
+    }
+
+    fn approve(
+        self: Box<Self>,
+        _reviewer: &Reviewer,
+        _policy: &ApprovalPolicy,
+        _publish_at: Option<SystemTime>,
+    ) -> Box<dyn State> {
+        self
+    }
+
+    fn reject(self: Box<Self>, _reason: &str, _policy: &ApprovalPolicy) -> Box<dyn State> {
+        self
+    }
+
+    // * 禁发期内也可以归档, 这样内容就永远不会公开
+    fn archive(self: Box<Self>) -> Box<dyn State> {
+        Box::new(Archived {
+            approvals: self.approvals,
+        })
+    }
+
+    fn content<'a>(&self, post: &'a Post) -> &'a str {
+        if post.clock.now() >= self.publish_at {
+            &post.content
+        } else {
+            ""
+        }
+    }
+
+    fn publish_at(&self) -> Option<SystemTime> {
+        Some(self.publish_at)
+    }
+
+    fn approvals(&self) -> &[Reviewer] {
+        &self.approvals
+    }
+
+    fn status(&self) -> Status {
+        Status::Scheduled
+    }
+
+    fn allows(&self, operation: Operation) -> bool {
+        operation == Operation::Archive
+    }
 }
 
 // * 完成状态后, 无论是调用审核还是完成, 状态都不会再发生变化
 impl State for Published {
-    fn approve(self: Box<Self>) -> Box<dyn State> {
+    fn approve(
+        self: Box<Self>,
+        _reviewer: &Reviewer,
+        _policy: &ApprovalPolicy,
+        _publish_at: Option<SystemTime>,
+    ) -> Box<dyn State> {
         return self;
     }
 
@@ -54,28 +314,134 @@ impl State for Published {
         return self;
     }
 
+    fn reject(self: Box<Self>, _reason: &str, _policy: &ApprovalPolicy) -> Box<dyn State> {
+        self
+    }
+
+    fn archive(self: Box<Self>) -> Box<dyn State> {
+        Box::new(Archived {
+            approvals: self.approvals,
+        })
+    }
+
     // * 完成的时候返回 post 中的 content字段
     fn content<'a>(&self, post: &'a Post) -> &'a str {
         return &post.content;
     }
+
+    fn approvals(&self) -> &[Reviewer] {
+        &self.approvals
+    }
+
+    fn status(&self) -> Status {
+        Status::Published
+    }
+
+    fn allows(&self, operation: Operation) -> bool {
+        operation == Operation::Archive
+    }
+}
+
+// * 归档之后内容不再公开, 也不能再进入其他状态
+impl State for Archived {
+    fn request_review(self: Box<Self>) -> Box<dyn State> {
+        self
+    }
+
+    fn approve(
+        self: Box<Self>,
+        _reviewer: &Reviewer,
+        _policy: &ApprovalPolicy,
+        _publish_at: Option<SystemTime>,
+    ) -> Box<dyn State> {
+        self
+    }
+
+    fn reject(self: Box<Self>, _reason: &str, _policy: &ApprovalPolicy) -> Box<dyn State> {
+        self
+    }
+
+    fn archive(self: Box<Self>) -> Box<dyn State> {
+        self
+    }
+
+    fn content<'a>(&self, _post: &'a Post) -> &'a str {
+        ""
+    }
+
+    fn approvals(&self) -> &[Reviewer] {
+        &self.approvals
+    }
+
+    fn status(&self) -> Status {
+        Status::Archived
+    }
+
+    fn allows(&self, _operation: Operation) -> bool {
+        false
+    }
 }
 
 pub struct Post {
     state: Option<Box<dyn State>>,
-    content: String
+    content: String,
+    policy: ApprovalPolicy,
+    // * 作者负责编辑文本和请求审核, 只有 id 和作者相同的 Author(或者 Admin)可以做这些操作
+    author: String,
+    history: Vec<Event>,
+    // * 文本的每个版本, 第 0 个是创建时的空文本, 最后一个就是当前的 content
+    revisions: Vec<String>,
+    clock: Box<dyn Clock>,
 }
 
+// ? Post 的初始值(匿名作者、系统时钟)不适合作为 Default, 所以不实现 Default
+#[allow(clippy::new_without_default)]
 impl Post {
     pub fn new() -> Post {
-        return Post {
+        Post::with_policy(ApprovalPolicy::default())
+    }
+
+    // * 例如: Post::with_policy(ApprovalPolicy::new().required_approvals(2).require_editor())
+    pub fn with_policy(policy: ApprovalPolicy) -> Post {
+        Post {
             // * 保证无论何时创建一个新的 Post, 都会从草案开始
-            state: Some(Box::new(Draft {})),
-            content: String::new()
+            state: Some(Box::new(Draft {
+                rejection_reason: None,
+                approvals: Vec::new(),
+            })),
+            content: String::new(),
+            policy,
+            author: ANONYMOUS.to_string(),
+            history: Vec::new(),
+            revisions: vec![String::new()],
+            clock: Box::new(SystemClock),
         }
     }
 
-    pub fn add_text(&mut self, text: &str) {
+    // * 例如: Post::new().written_by("alice").with_clock(FakeClock::new(start))
+    pub fn written_by(mut self, author: &str) -> Post {
+        self.author = author.to_string();
+        self
+    }
+
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Post {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    // * 只有作者(或者管理员)可以在草案阶段添加文本
+    pub fn add_text(&mut self, actor: &Actor, text: &str) -> Result<(), PostError> {
+        self.check(actor, Operation::Edit)?;
         self.content.push_str(text);
+        self.revisions.push(self.content.clone());
+        self.record(&actor.id, Action::AddText {
+            text: text.to_string(),
+        });
+        Ok(())
     }
 
     // * 根据状态返回内容
@@ -90,10 +456,21 @@ impl Post {
         return self.state.as_ref().unwrap().content(self);
     }
 
+    // * 已发布内容的 HTML, 未发布时 content 为空, 所以这里也是空字符串
+    pub fn render_html(&self) -> String {
+        markdown::to_html(self.content())
+    }
+
+    // * 已发布内容的纯文本摘要, 最多 max_chars 个字符
+    pub fn excerpt(&self, max_chars: usize) -> String {
+        markdown::excerpt(self.content(), max_chars)
+    }
+
     // * Post增加一个获取 self 可变引用的公有方法 request_review。接着在 Post 的当前状态下调用内部的 request_review 方法
     // * 并且第二个 request_review 方法会消费当前的状态并返回一个新状态
     // ? 将阶段置为等待审核阶段
-    pub fn request_review(&mut self) {
+    pub fn request_review(&mut self, actor: &Actor) -> Result<(), PostError> {
+        self.check(actor, Operation::RequestReview)?;
         // * 为了消费老状态，request_review 方法需要获取状态值的所有权。
         // * 调用 take 方法将 state 字段中的 Some 值取出并留下一个 None，因为 Rust 不允许结构体实例中存在值为空的字段。
         // * 这使得我们将 state 的值移出 Post 而不是借用它。接着我们将博文的 state 值设置为这个操作的结果。
@@ -103,13 +480,471 @@ impl Post {
         if let Some(s) = self.state.take() {
             self.state = Some(s.request_review());
         }
+        self.record(&actor.id, Action::RequestReview);
+        Ok(())
+    }
+
+    // * 记录审核人的通过, 满足策略时将 state 设置为审核通过时应处于的状态
+    // + 同一个审核人重复通过会被忽略: 返回 Ok, 也不记录历史
+    pub fn approve(&mut self, reviewer: &Reviewer) -> Result<(), PostError> {
+        self.approve_with(reviewer, None)
+    }
+
+    // * 通过审核, 但是要求到 publish_at 才公开
+    // + 满足策略时进入 Scheduled 状态, 否则先记下这个时间, 等到最后一个需要的通过出现时再进入 Scheduled
+    pub fn approve_scheduled(
+        &mut self,
+        reviewer: &Reviewer,
+        publish_at: SystemTime,
+    ) -> Result<(), PostError> {
+        self.approve_with(reviewer, Some(publish_at))
     }
 
-    // * 将 state 设置为审核通过时应处于的状态
-    pub fn approve(&mut self) {
+    fn approve_with(
+        &mut self,
+        reviewer: &Reviewer,
+        publish_at: Option<SystemTime>,
+    ) -> Result<(), PostError> {
+        self.check(reviewer, Operation::Approve)?;
+        if self.approvals().iter().any(|r| r.id == reviewer.id) {
+            return Ok(());
+        }
         // * 和 request_review 一样, 需要将 state 临时设置为 None, 来获取 state 的值
         if let Some(s) = self.state.take() {
-            self.state = Some(s.approve());
+            self.state = Some(s.approve(reviewer, &self.policy, publish_at));
+        }
+        let action = match publish_at {
+            Some(publish_at) => Action::ApproveScheduled { publish_at },
+            None => Action::Approve,
+        };
+        self.record(&reviewer.id, action);
+        Ok(())
+    }
+
+    // * 计划公开的时间, 没有定时发布时返回 None
+    pub fn publish_at(&self) -> Option<SystemTime> {
+        self.state.as_ref().unwrap().publish_at()
+    }
+
+    // * 归档, 内容不再公开, 只有编辑和管理员可以归档
+    pub fn archive(&mut self, editor: &Actor) -> Result<(), PostError> {
+        self.check(editor, Operation::Archive)?;
+        if let Some(s) = self.state.take() {
+            self.state = Some(s.archive());
         }
+        self.record(&editor.id, Action::Archive);
+        Ok(())
+    }
+
+    pub fn approvals(&self) -> &[Reviewer] {
+        self.state.as_ref().unwrap().approvals()
+    }
+
+    // * 定时发布的博文到了公开时间之后就算作已发布
+    pub fn status(&self) -> Status {
+        let state = self.state.as_ref().unwrap();
+        match (state.status(), state.publish_at()) {
+            (Status::Scheduled, Some(at)) if self.clock.now() >= at => Status::Published,
+            (status, _) => status,
+        }
+    }
+
+    pub fn policy(&self) -> &ApprovalPolicy {
+        &self.policy
     }
-}
\ No newline at end of file
+
+    // * 审核不通过, 退回草案, 必须给出原因
+    // + 只有等待审核的博文可以被退回
+    // ? 原因为空(或者只有空白)时返回 PostError::EmptyReason, 避免作者收到一个看不懂的退回
+    pub fn reject(&mut self, reviewer: &Reviewer, reason: &str) -> Result<(), PostError> {
+        self.check(reviewer, Operation::Reject)?;
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(PostError::EmptyReason);
+        }
+        if let Some(s) = self.state.take() {
+            self.state = Some(s.reject(reason, &self.policy));
+        }
+        self.record(&reviewer.id, Action::Reject {
+            reason: reason.to_string(),
+        });
+        Ok(())
+    }
+
+    // * 最近一次被退回的原因, 重新请求审核之后返回 None
+    pub fn rejection_reason(&self) -> Option<&str> {
+        self.state.as_ref().unwrap().rejection_reason()
+    }
+
+    // * 先检查角色, 再检查当前状态是否允许这个操作
+    fn check(&self, actor: &Actor, operation: Operation) -> Result<(), PostError> {
+        permission::authorize(actor, &self.author, operation)?;
+        if self.state.as_ref().unwrap().allows(operation) {
+            Ok(())
+        } else {
+            Err(PostError::InvalidState {
+                status: self.status(),
+                operation,
+            })
+        }
+    }
+
+    fn record(&mut self, actor: &str, action: Action) {
+        let event = Event {
+            actor: actor.to_string(),
+            at: self.clock.now(),
+            action,
+            status: self.status(),
+        };
+        self.history.push(event);
+    }
+
+    // * 按时间顺序排列的所有操作
+    pub fn history(&self) -> &[Event] {
+        &self.history
+    }
+
+    pub fn revisions(&self) -> &[String] {
+        &self.revisions
+    }
+
+    // * 把文本恢复到第 revision 个版本, 和添加文本一样, 只能在草案阶段由作者进行
+    // + 恢复会产生一个新的版本, 所以恢复之后还可以再恢复回来
+    pub fn revert_to(&mut self, actor: &Actor, revision: usize) -> Result<(), PostError> {
+        self.check(actor, Operation::Edit)?;
+        let Some(content) = self.revisions.get(revision).cloned() else {
+            return Err(PostError::NoSuchRevision(revision));
+        };
+        self.content = content;
+        self.revisions.push(self.content.clone());
+        self.record(&actor.id, Action::Revert { revision });
+        Ok(())
+    }
+
+    // * 撤销最近一次修改, 也就是恢复到倒数第二个版本
+    pub fn undo(&mut self, actor: &Actor) -> Result<(), PostError> {
+        match self.revisions.len() {
+            0 | 1 => {
+                self.check(actor, Operation::Edit)?;
+                Err(PostError::NothingToUndo)
+            }
+            len => self.revert_to(actor, len - 2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // * 测试中的博文都由 alice 编写
+    fn alice() -> Actor {
+        Actor::author("alice")
+    }
+
+    fn draft(policy: ApprovalPolicy, text: &str) -> Post {
+        let mut post = Post::with_policy(policy).written_by("alice");
+        post.add_text(&alice(), text).unwrap();
+        post
+    }
+
+    #[test]
+    fn reject_sends_pending_post_back_to_draft() {
+        let mut post = draft(ApprovalPolicy::new(), "I ate a salad for lunch today");
+        post.request_review(&alice()).unwrap();
+        post.reject(&Reviewer::editor("ed"), "  too short  ").unwrap();
+        assert_eq!(post.rejection_reason(), Some("too short"));
+
+        // * 退回之后是草案, approve 会失败
+        assert_eq!(
+            post.approve(&Reviewer::editor("ed")),
+            Err(PostError::InvalidState {
+                status: Status::Draft,
+                operation: Operation::Approve,
+            })
+        );
+        assert_eq!(post.content(), "");
+
+        post.add_text(&alice(), ", and it was great").unwrap();
+        post.request_review(&alice()).unwrap();
+        assert_eq!(post.rejection_reason(), None);
+        post.approve(&Reviewer::editor("ed")).unwrap();
+        assert_eq!(post.content(), "I ate a salad for lunch today, and it was great");
+    }
+
+    #[test]
+    fn reject_fails_outside_review_or_without_reason() {
+        let mut post = draft(ApprovalPolicy::new(), "hello");
+        assert_eq!(
+            post.reject(&Reviewer::editor("ed"), "not even submitted"),
+            Err(PostError::InvalidState {
+                status: Status::Draft,
+                operation: Operation::Reject,
+            })
+        );
+        assert_eq!(post.rejection_reason(), None);
+
+        post.request_review(&alice()).unwrap();
+        assert_eq!(post.reject(&Reviewer::editor("ed"), "   "), Err(PostError::EmptyReason));
+        assert_eq!(post.rejection_reason(), None);
+        post.approve(&Reviewer::editor("ed")).unwrap();
+        assert!(post.reject(&Reviewer::editor("ed"), "too late").is_err());
+        assert_eq!(post.rejection_reason(), None);
+        assert_eq!(post.content(), "hello");
+    }
+
+    #[test]
+    fn requires_distinct_approvals() {
+        let mut post = draft(ApprovalPolicy::new().required_approvals(2), "draft");
+        post.request_review(&alice()).unwrap();
+        post.approve(&Reviewer::editor("bob")).unwrap();
+        // * 重复的通过被忽略, 不算作第二次通过, 也不会出现在历史中
+        post.approve(&Reviewer::editor("bob")).unwrap();
+        assert_eq!(post.approvals().len(), 1);
+        assert_eq!(post.history().len(), 3);
+        assert_eq!(post.content(), "");
+
+        post.approve(&Reviewer::editor("carol")).unwrap();
+        assert_eq!(post.content(), "draft");
+        assert_eq!(post.approvals().len(), 2);
+    }
+
+    #[test]
+    fn requires_an_editor() {
+        // ? Post 只允许编辑审核, 但是类型化的博文和旧文件中可能有普通审核人的通过
+        let policy = ApprovalPolicy::new()
+            .required_approvals(2)
+            .require_editor();
+        assert!(!policy.is_satisfied(&[Reviewer::peer("bob"), Reviewer::peer("carol")]));
+        // * 管理员的通过也算作编辑的通过
+        assert!(policy.is_satisfied(&[Reviewer::peer("bob"), Actor::admin("root")]));
+    }
+
+    #[test]
+    fn rejection_policy_decides_whether_approvals_survive() {
+        let policy = ApprovalPolicy::new().required_approvals(2);
+
+        let mut post = Post::with_policy(policy.clone()).written_by("alice");
+        post.request_review(&alice()).unwrap();
+        post.approve(&Reviewer::editor("bob")).unwrap();
+        post.reject(&Reviewer::editor("ed"), "typo").unwrap();
+        post.request_review(&alice()).unwrap();
+        assert!(post.approvals().is_empty());
+
+        let mut post = draft(policy.reset_on_rejection(false), "draft");
+        post.request_review(&alice()).unwrap();
+        post.approve(&Reviewer::editor("bob")).unwrap();
+        post.reject(&Reviewer::editor("ed"), "typo").unwrap();
+        post.request_review(&alice()).unwrap();
+        assert_eq!(post.approvals(), &[Reviewer::editor("bob")]);
+        post.approve(&Reviewer::editor("carol")).unwrap();
+        assert_eq!(post.content(), "draft");
+    }
+
+    #[test]
+    fn enforces_roles_on_every_mutation() {
+        let mut post = draft(ApprovalPolicy::new(), "mine");
+        // * 只有作者本人可以编辑, 编辑和其他作者都不行
+        assert_eq!(
+            post.add_text(&Actor::author("mallory"), " and yours"),
+            Err(PostError::NotAuthor {
+                actor: String::from("mallory"),
+                author: String::from("alice"),
+                operation: Operation::Edit,
+            })
+        );
+        assert!(post.add_text(&Actor::editor("ed"), "!").is_err());
+        assert!(post.request_review(&Actor::peer("bob")).is_err());
+        // * 管理员也不能修改别人的草案
+        assert_eq!(
+            post.add_text(&Actor::admin("root"), "!"),
+            Err(PostError::Forbidden {
+                actor: String::from("root"),
+                role: Role::Admin,
+                operation: Operation::Edit,
+            })
+        );
+        post.add_text(&alice(), "!").unwrap();
+
+        post.request_review(&alice()).unwrap();
+        // * 只有编辑能审核, 作者和普通审核人都不行
+        assert_eq!(
+            post.approve(&alice()),
+            Err(PostError::Forbidden {
+                actor: String::from("alice"),
+                role: Role::Author,
+                operation: Operation::Approve,
+            })
+        );
+        assert!(post.approve(&Reviewer::peer("bob")).is_err());
+        assert!(post.reject(&Reviewer::peer("bob"), "meh").is_err());
+        post.approve(&Reviewer::editor("ed")).unwrap();
+        assert!(post.archive(&Reviewer::peer("bob")).is_err());
+
+        // * 发布之后作者也不能修改文本
+        assert_eq!(
+            post.add_text(&alice(), " edited"),
+            Err(PostError::InvalidState {
+                status: Status::Published,
+                operation: Operation::Edit,
+            })
+        );
+        assert!(post.undo(&alice()).is_err());
+        assert_eq!(post.content(), "mine!");
+        // * 失败的操作不会出现在历史中
+        assert_eq!(post.history().len(), 4);
+    }
+
+    #[test]
+    fn records_history_with_actor_and_time() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let clock = FakeClock::new(UNIX_EPOCH + Duration::from_secs(1_000));
+        let mut post = Post::new().written_by("alice").with_clock(clock.clone());
+        post.add_text(&alice(), "Hello").unwrap();
+        clock.advance(Duration::from_secs(60));
+        post.request_review(&alice()).unwrap();
+        // * 对等待审核的博文再次请求审核会失败, 不记录
+        assert!(post.request_review(&alice()).is_err());
+        clock.advance(Duration::from_secs(60));
+        post.reject(&Reviewer::editor("ed"), "needs a title").unwrap();
+        assert!(post.approve(&Reviewer::editor("ed")).is_err());
+        post.request_review(&alice()).unwrap();
+        post.approve(&Reviewer::editor("ed")).unwrap();
+
+        let summary: Vec<(&str, &Action, Status)> = post
+            .history()
+            .iter()
+            .map(|e| (e.actor.as_str(), &e.action, e.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("alice", &Action::AddText { text: String::from("Hello") }, Status::Draft),
+                ("alice", &Action::RequestReview, Status::PendingReview),
+                ("ed", &Action::Reject { reason: String::from("needs a title") }, Status::Draft),
+                ("alice", &Action::RequestReview, Status::PendingReview),
+                ("ed", &Action::Approve, Status::Published),
+            ]
+        );
+        assert_eq!(post.history()[0].at, UNIX_EPOCH + Duration::from_secs(1_000));
+        assert_eq!(post.history()[4].at, UNIX_EPOCH + Duration::from_secs(1_120));
+    }
+
+    #[test]
+    fn reverts_to_previous_revisions() {
+        // * 恢复版本和撤销也是修改文本, 只有作者本人可以做
+        let mut post = Post::new().written_by("alice");
+        post.add_text(&alice(), "Hello").unwrap();
+        post.add_text(&alice(), ", wrold").unwrap();
+        assert!(post.undo(&Actor::admin("root")).is_err());
+        post.undo(&alice()).unwrap();
+        post.add_text(&alice(), ", world").unwrap();
+        assert_eq!(post.revisions(), &["", "Hello", "Hello, wrold", "Hello", "Hello, world"]);
+
+        assert_eq!(post.revert_to(&alice(), 10), Err(PostError::NoSuchRevision(10)));
+        post.revert_to(&alice(), 2).unwrap();
+        post.request_review(&alice()).unwrap();
+        post.approve(&Reviewer::editor("ed")).unwrap();
+        assert_eq!(post.content(), "Hello, wrold");
+        assert_eq!(
+            post.history()[4],
+            Event {
+                actor: String::from("alice"),
+                at: post.history()[4].at,
+                action: Action::Revert { revision: 2 },
+                status: Status::Draft,
+            }
+        );
+        assert_eq!(
+            Post::new().written_by("alice").undo(&alice()),
+            Err(PostError::NothingToUndo)
+        );
+    }
+
+    #[test]
+    fn scheduled_posts_go_live_when_the_clock_passes_publish_at() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let clock = FakeClock::new(start);
+        let mut post = Post::with_policy(ApprovalPolicy::new().required_approvals(2))
+            .written_by("alice")
+            .with_clock(clock.clone());
+        post.add_text(&alice(), "Embargoed news").unwrap();
+        post.request_review(&alice()).unwrap();
+        // * 第一个通过带上了公开时间, 第二个普通的通过满足策略后进入 Scheduled
+        let publish_at = start + Duration::from_secs(3_600);
+        post.approve_scheduled(&Reviewer::editor("bob"), publish_at).unwrap();
+        assert_eq!(post.status(), Status::PendingReview);
+        post.approve(&Reviewer::editor("ed")).unwrap();
+        assert_eq!(post.status(), Status::Scheduled);
+        assert_eq!(post.publish_at(), Some(publish_at));
+        assert_eq!(post.content(), "");
+        // * 禁发期内同样不能修改文本
+        assert!(post.add_text(&alice(), "!").is_err());
+
+        clock.advance(Duration::from_secs(3_599));
+        assert_eq!(post.content(), "");
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(post.status(), Status::Published);
+        assert_eq!(post.content(), "Embargoed news");
+        assert_eq!(
+            post.history()[2].action,
+            Action::ApproveScheduled { publish_at }
+        );
+    }
+
+    #[test]
+    fn archive_hides_published_content() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut post = draft(ApprovalPolicy::new(), "old news");
+        // * 草案不能归档
+        assert_eq!(
+            post.archive(&Reviewer::editor("ed")),
+            Err(PostError::InvalidState {
+                status: Status::Draft,
+                operation: Operation::Archive,
+            })
+        );
+
+        post.request_review(&alice()).unwrap();
+        post.approve(&Reviewer::editor("ed")).unwrap();
+        assert_eq!(post.content(), "old news");
+        post.archive(&Reviewer::editor("ed")).unwrap();
+        assert_eq!(post.status(), Status::Archived);
+        assert_eq!(post.content(), "");
+        assert!(post.request_review(&alice()).is_err());
+        assert!(post.approve(&Reviewer::editor("ed2")).is_err());
+        assert_eq!(post.status(), Status::Archived);
+        assert_eq!(post.history().last().unwrap().action, Action::Archive);
+
+        // * 禁发期内归档, 内容永远不会公开
+        let clock = FakeClock::new(UNIX_EPOCH);
+        let mut post = Post::new().written_by("alice").with_clock(clock.clone());
+        post.add_text(&alice(), "never").unwrap();
+        post.request_review(&alice()).unwrap();
+        post.approve_scheduled(&Reviewer::editor("ed"), UNIX_EPOCH + Duration::from_secs(10))
+            .unwrap();
+        post.archive(&Reviewer::editor("ed")).unwrap();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(post.status(), Status::Archived);
+        assert_eq!(post.content(), "");
+    }
+
+    #[test]
+    fn renders_only_published_content() {
+        let mut post = draft(ApprovalPolicy::new(), "# Lunch\n\nI ate a **salad** today");
+        assert_eq!(post.render_html(), "");
+        assert_eq!(post.excerpt(20), "");
+
+        post.request_review(&alice()).unwrap();
+        post.approve(&Reviewer::editor("ed")).unwrap();
+        assert_eq!(
+            post.render_html(),
+            "<h1>Lunch</h1>\n<p>I ate a <strong>salad</strong> today</p>\n"
+        );
+        assert_eq!(post.excerpt(20), "Lunch I ate a salad…");
+    }
+}
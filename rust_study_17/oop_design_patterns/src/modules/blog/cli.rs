@@ -0,0 +1,535 @@
+// * 命令行博客编辑器, 可执行文件见 src/bin/blog.rs
+// + 所有博文保存在一个 JSON 文件中(PostStore), 默认是当前目录下的 blog.json
+// + 用法:
+//   blog new [文本]                   新建草案, 输出新博文的 id
+//   blog edit <id> [文本]             在草案末尾追加文本
+//   blog submit <id>                  请求审核
+//   blog approve <id>                 审核通过
+//   blog reject <id> <原因>           审核不通过, 退回草案
+//   blog list [--state <状态>]        列出博文, 状态可以是 draft、pending、scheduled、published、archived
+//   blog show <id> [--html]           显示博文的详细信息和历史记录
+// + 全局选项:
+//   --store <文件>   博文文件, 也可以用环境变量 BLOG_STORE 指定
+//   --as <名字>      当前用户, 默认使用环境变量 USER
+//   --role <角色>    当前用户的角色: author(默认)、peer、editor、admin, 能做什么见 permission.rs
+//   --editor         --role editor 的简写
+// + -- 之后的参数都当作文本, 不再解析选项, 例如: blog new -- --html 的用法
+// ? new 和 edit 没有给出文本时从标准输入读取, 例如: blog edit 3 < draft.md
+use std::error::Error;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use super::store::PostStore;
+use super::{markdown, parse_status, review, status_name, Actor, Post, Role, Status, ANONYMOUS};
+
+pub const DEFAULT_STORE: &str = "blog.json";
+
+pub const USAGE: &str = "\
+用法: blog [--store <文件>] [--as <名字>] [--role <角色>] <命令>
+
+命令:
+  new [文本]                新建草案
+  edit <id> [文本]          在草案末尾追加文本
+  submit <id>               请求审核
+  approve <id>              审核通过
+  reject <id> <原因>        审核不通过, 退回草案
+  list [--state <状态>]     列出博文 (draft, pending, scheduled, published, archived)
+  show <id> [--html]        显示博文
+
+角色: author (默认), peer, editor, admin; --editor 是 --role editor 的简写
+没有给出文本时从标准输入读取, -- 之后的参数都当作文本";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    New { text: Option<String> },
+    Edit { id: u64, text: Option<String> },
+    Submit { id: u64 },
+    Approve { id: u64 },
+    Reject { id: u64, reason: String },
+    List { status: Option<Status> },
+    Show { id: u64, html: bool },
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Cli {
+    pub store: PathBuf,
+    pub user: String,
+    pub role: Role,
+    pub command: Command,
+}
+
+// * 命令执行失败的原因, 参数错误在 Cli::new 中就以字符串的形式返回了
+// + 权限和状态的错误由 Post 的方法返回(PostError)
+#[derive(Debug)]
+pub enum CliError {
+    NotFound(u64),
+    EmptyText,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::NotFound(id) => write!(f, "博文 {} 不存在", id),
+            CliError::EmptyText => write!(f, "文本不能为空"),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+impl Cli {
+    // * 和 minigrep 的 Config::new 一样, 直接接收参数的迭代器, 第一个元素是程序名
+    // + env 用来读取 BLOG_STORE 和 USER, 测试时可以传入固定的值
+    pub fn new<I, E>(mut args: I, env: E) -> Result<Cli, String>
+    where
+        I: Iterator<Item = String>,
+        E: Fn(&str) -> Option<String>,
+    {
+        args.next();
+        let mut store = env("BLOG_STORE").map(PathBuf::from);
+        let mut user = env("USER");
+        let mut role = Role::Author;
+        let mut state = None;
+        let mut html = false;
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--store" => store = Some(PathBuf::from(value_of(&mut args, "--store")?)),
+                "--as" => user = Some(value_of(&mut args, "--as")?),
+                "--role" => {
+                    let name = value_of(&mut args, "--role")?;
+                    role = review::parse_role(&name).ok_or(format!("未知的角色: {}", name))?;
+                }
+                "--editor" => role = Role::Editor,
+                "--state" => {
+                    let name = value_of(&mut args, "--state")?;
+                    state = Some(parse_state(&name).ok_or(format!("未知的状态: {}", name))?);
+                }
+                "--html" => html = true,
+                // * 看到 --help 就不再解析后面的参数, 前面的命令也忽略, 例如 blog submit --help
+                "-h" | "--help" => {
+                    positional = vec!["help".to_string()];
+                    break;
+                }
+                "--" => {
+                    positional.extend(args.by_ref());
+                    break;
+                }
+                _ if arg.starts_with("--") => return Err(format!("未知的选项: {}", arg)),
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let name = positional.next().unwrap_or_else(|| "help".to_string());
+        let mut id = || -> Result<u64, String> {
+            let arg = positional.next().ok_or(format!("{} 需要博文的 id", name))?;
+            arg.parse().map_err(|_| format!("不是合法的 id: {}", arg))
+        };
+        let command = match name.as_str() {
+            "new" => Command::New {
+                text: join(&mut positional),
+            },
+            "edit" => Command::Edit {
+                id: id()?,
+                text: join(&mut positional),
+            },
+            "submit" => Command::Submit { id: id()? },
+            "approve" => Command::Approve { id: id()? },
+            "reject" => {
+                let id = id()?;
+                let reason = join(&mut positional).ok_or("reject 需要退回的原因")?;
+                Command::Reject { id, reason }
+            }
+            "list" => Command::List { status: state },
+            "show" => Command::Show { id: id()?, html },
+            "help" => Command::Help,
+            _ => return Err(format!("未知的命令: {}", name)),
+        };
+        // * 只属于某个命令的选项用在别的命令上时报错, 而不是静默忽略
+        if html && !matches!(command, Command::Show { .. } | Command::Help) {
+            return Err(format!("{} 不接受 --html 选项", name));
+        }
+        if state.is_some() && !matches!(command, Command::List { .. } | Command::Help) {
+            return Err(format!("{} 不接受 --state 选项", name));
+        }
+        if let Some(extra) = positional.next() {
+            return Err(format!("多余的参数: {}", extra));
+        }
+
+        Ok(Cli {
+            store: store.unwrap_or_else(|| PathBuf::from(DEFAULT_STORE)),
+            user: user.unwrap_or_else(|| ANONYMOUS.to_string()),
+            role,
+            command,
+        })
+    }
+
+    fn actor(&self) -> Actor {
+        Actor::new(&self.user, self.role)
+    }
+}
+
+fn value_of<I: Iterator<Item = String>>(args: &mut I, option: &str) -> Result<String, String> {
+    args.next().ok_or(format!("{} 需要一个值", option))
+}
+
+// * 剩下的参数用空格连起来, 这样文本不需要加引号
+fn join<I: Iterator<Item = String>>(args: &mut I) -> Option<String> {
+    let words: Vec<String> = args.collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+// * 除了保存文件中使用的名字, 还接受 pending 这样的简写
+fn parse_state(name: &str) -> Option<Status> {
+    match name {
+        "pending" | "review" => Some(Status::PendingReview),
+        _ => parse_status(name),
+    }
+}
+
+// * 打开博文文件, 执行命令, 有修改时保存
+// + input 是标准输入, 命令行中没有给出文本时从这里读取
+pub fn run<R: Read, W: Write>(cli: &Cli, input: R, out: &mut W) -> Result<(), Box<dyn Error>> {
+    if cli.command == Command::Help {
+        writeln!(out, "{}", USAGE)?;
+        return Ok(());
+    }
+    let mut store = PostStore::open(&cli.store)?;
+    if execute(cli, &mut store, input, out)? {
+        store.save()?;
+    }
+    Ok(())
+}
+
+// * 返回 store 是否被修改了
+fn execute<R: Read, W: Write>(
+    cli: &Cli,
+    store: &mut PostStore,
+    input: R,
+    out: &mut W,
+) -> Result<bool, Box<dyn Error>> {
+    match &cli.command {
+        Command::New { text } => {
+            let mut post = Post::new().written_by(&cli.user);
+            let text = text_or_input(text, input)?;
+            post.add_text(&cli.actor(), &text)?;
+            let id = store.insert(post);
+            writeln!(out, "{}", id)?;
+            Ok(true)
+        }
+        Command::Edit { id, text } => {
+            let post = find(store, *id)?;
+            let mut text = text_or_input(text, input)?;
+            // * 追加的文本另起一行, 避免和原来的最后一个词连在一起
+            if !latest(post).is_empty() && !latest(post).ends_with(char::is_whitespace) {
+                text.insert(0, '\n');
+            }
+            post.add_text(&cli.actor(), &text)?;
+            writeln!(
+                out,
+                "博文 {} 已更新, 当前是第 {} 版",
+                id,
+                post.revisions().len() - 1
+            )?;
+            Ok(true)
+        }
+        Command::Submit { id } => {
+            find(store, *id)?.request_review(&cli.actor())?;
+            writeln!(out, "博文 {} 已提交审核", id)?;
+            Ok(true)
+        }
+        Command::Approve { id } => {
+            let post = find(store, *id)?;
+            post.approve(&cli.actor())?;
+            match post.status() {
+                Status::PendingReview => writeln!(
+                    out,
+                    "博文 {} 已通过 {} 次, 还需要更多的审核",
+                    id,
+                    post.approvals().len()
+                )?,
+                status => writeln!(
+                    out,
+                    "博文 {} 已通过审核, 当前是 {}",
+                    id,
+                    status_name(status)
+                )?,
+            }
+            Ok(true)
+        }
+        Command::Reject { id, reason } => {
+            find(store, *id)?.reject(&cli.actor(), reason)?;
+            writeln!(out, "博文 {} 已退回草案", id)?;
+            Ok(true)
+        }
+        Command::List { status } => {
+            for (id, post) in store.iter() {
+                if status.is_some_and(|s| s != post.status()) {
+                    continue;
+                }
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}",
+                    id,
+                    status_name(post.status()),
+                    post.author(),
+                    markdown::excerpt(latest(post), 40)
+                )?;
+            }
+            Ok(false)
+        }
+        Command::Show { id, html } => {
+            let post = store.get(*id).ok_or(CliError::NotFound(*id))?;
+            if *html {
+                // * 只有已发布的内容才会被渲染, 其他状态下输出为空
+                write!(out, "{}", post.render_html())?;
+                return Ok(false);
+            }
+            writeln!(out, "id:     {}", id)?;
+            writeln!(out, "作者:   {}", post.author())?;
+            writeln!(out, "状态:   {}", status_name(post.status()))?;
+            let approvals: Vec<&str> = post.approvals().iter().map(|r| r.id.as_str()).collect();
+            if !approvals.is_empty() {
+                writeln!(out, "通过:   {}", approvals.join(", "))?;
+            }
+            if let Some(reason) = post.rejection_reason() {
+                writeln!(out, "退回:   {}", reason)?;
+            }
+            writeln!(out, "\n{}\n", latest(post))?;
+            for event in post.history() {
+                writeln!(
+                    out,
+                    "{} {:?} -> {}",
+                    event.actor,
+                    event.action,
+                    status_name(event.status)
+                )?;
+            }
+            Ok(false)
+        }
+        Command::Help => {
+            writeln!(out, "{}", USAGE)?;
+            Ok(false)
+        }
+    }
+}
+
+fn find(store: &mut PostStore, id: u64) -> Result<&mut Post, CliError> {
+    store.get_mut(id).ok_or(CliError::NotFound(id))
+}
+
+// ? content 只有发布后才有内容, 编辑器需要看到的是最新的版本
+fn latest(post: &Post) -> &str {
+    post.revisions().last().unwrap()
+}
+
+fn text_or_input<R: Read>(text: &Option<String>, mut input: R) -> Result<String, Box<dyn Error>> {
+    let text = match text {
+        Some(text) => text.clone(),
+        None => {
+            let mut text = String::new();
+            input.read_to_string(&mut text)?;
+            text
+        }
+    };
+    if text.trim().is_empty() {
+        return Err(CliError::EmptyText.into());
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::process;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("blog-cli-{}-{}.json", process::id(), name))
+    }
+
+    fn parse(store: &Path, args: &[&str]) -> Result<Cli, String> {
+        let mut all = vec!["blog".to_string(), "--store".to_string()];
+        all.push(store.to_str().unwrap().to_string());
+        all.extend(args.iter().map(|a| a.to_string()));
+        Cli::new(all.into_iter(), |_| None)
+    }
+
+    fn blog(store: &Path, args: &[&str]) -> Result<String, String> {
+        blog_with_input(store, args, "")
+    }
+
+    fn blog_with_input(store: &Path, args: &[&str], input: &str) -> Result<String, String> {
+        let cli = parse(store, args)?;
+        let mut out = Vec::new();
+        run(&cli, input.as_bytes(), &mut out).map_err(|e| e.to_string())?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let env = |name: &str| match name {
+            "USER" => Some("alice".to_string()),
+            _ => None,
+        };
+        let args = ["blog", "reject", "3", "--editor", "too", "short"];
+        let cli = Cli::new(args.iter().map(|a| a.to_string()), env).unwrap();
+        assert_eq!(
+            cli,
+            Cli {
+                store: PathBuf::from(DEFAULT_STORE),
+                user: "alice".to_string(),
+                role: Role::Editor,
+                command: Command::Reject {
+                    id: 3,
+                    reason: "too short".to_string()
+                },
+            }
+        );
+
+        let store = PathBuf::from("x.json");
+        assert_eq!(
+            parse(&store, &["list", "--state", "pending"])
+                .unwrap()
+                .command,
+            Command::List {
+                status: Some(Status::PendingReview)
+            }
+        );
+        assert_eq!(parse(&store, &[]).unwrap().command, Command::Help);
+        for args in [&["submit", "--help"][..], &["-h", "show", "x", "--bogus"]] {
+            assert_eq!(parse(&store, args).unwrap().command, Command::Help);
+        }
+        assert_eq!(
+            parse(&store, &["show", "x"]),
+            Err("不是合法的 id: x".to_string())
+        );
+        assert_eq!(
+            parse(&store, &["submit"]),
+            Err("submit 需要博文的 id".to_string())
+        );
+        assert_eq!(
+            parse(&store, &["submit", "1", "2"]),
+            Err("多余的参数: 2".to_string())
+        );
+        assert_eq!(
+            parse(&store, &["publish"]),
+            Err("未知的命令: publish".to_string())
+        );
+        assert!(parse(&store, &["list", "--state", "lost"]).is_err());
+        assert_eq!(
+            parse(&store, &["--role", "admin", "list"]).unwrap().role,
+            Role::Admin
+        );
+        assert_eq!(
+            parse(&store, &["--role", "owner", "list"]),
+            Err("未知的角色: owner".to_string())
+        );
+    }
+
+    #[test]
+    fn options_stop_at_double_dash_and_belong_to_commands() {
+        let store = PathBuf::from("x.json");
+        assert_eq!(
+            parse(&store, &["new", "use", "--", "--html", "here", "--help"])
+                .unwrap()
+                .command,
+            Command::New {
+                text: Some("use --html here --help".to_string())
+            }
+        );
+        assert_eq!(
+            parse(&store, &["edit", "1", "--", "--", "-h"])
+                .unwrap()
+                .command,
+            Command::Edit {
+                id: 1,
+                text: Some("-- -h".to_string())
+            }
+        );
+        assert_eq!(
+            parse(&store, &["new", "use", "--html", "here"]),
+            Err("new 不接受 --html 选项".to_string())
+        );
+        assert_eq!(
+            parse(&store, &["show", "1", "--state", "draft"]),
+            Err("show 不接受 --state 选项".to_string())
+        );
+        assert_eq!(
+            parse(&store, &["new", "--bogus"]),
+            Err("未知的选项: --bogus".to_string())
+        );
+    }
+
+    #[test]
+    fn runs_the_review_workflow() {
+        let path = temp_path("workflow");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(
+            blog(&path, &["--as", "ferris", "new", "I", "ate", "a", "salad"]),
+            Ok("1\n".to_string())
+        );
+        assert_eq!(
+            blog_with_input(&path, &["new"], "# Second\n"),
+            Ok("2\n".to_string())
+        );
+        blog(&path, &["--as", "ferris", "edit", "1", "for lunch"]).unwrap();
+        blog(&path, &["--as", "ferris", "submit", "1"]).unwrap();
+        assert_eq!(
+            blog(&path, &["list", "--state", "pending"]),
+            Ok("1\tpending_review\tferris\tI ate a salad for lunch\n".to_string())
+        );
+
+        // * 等待审核时不能编辑, 作者也不能审核自己的博文
+        assert_eq!(
+            blog(&path, &["--as", "ferris", "edit", "1", "more"]),
+            Err("博文当前是 pending_review, 不能修改文本".to_string())
+        );
+        assert_eq!(
+            blog(&path, &["--as", "ferris", "approve", "1"]),
+            Err("ferris 的角色是 author, 不能通过审核".to_string())
+        );
+        blog(
+            &path,
+            &["--as", "ed", "--editor", "reject", "1", "too", "short"],
+        )
+        .unwrap();
+        assert!(blog(&path, &["show", "1"])
+            .unwrap()
+            .contains("退回:   too short"));
+        blog(&path, &["--as", "ferris", "submit", "1"]).unwrap();
+        assert_eq!(
+            blog(&path, &["--as", "ed", "--editor", "approve", "1"]),
+            Ok("博文 1 已通过审核, 当前是 published\n".to_string())
+        );
+        assert_eq!(
+            blog(&path, &["show", "1", "--html"]),
+            Ok("<p>I ate a salad\nfor lunch</p>\n".to_string())
+        );
+        assert_eq!(blog(&path, &["show", "2", "--html"]), Ok(String::new()));
+        assert_eq!(
+            blog(&path, &["list", "--state", "pending"]),
+            Ok(String::new())
+        );
+
+        assert_eq!(
+            blog(&path, &["submit", "9"]),
+            Err("博文 9 不存在".to_string())
+        );
+        assert_eq!(
+            blog_with_input(&path, &["edit", "2"], "  \n"),
+            Err("文本不能为空".to_string())
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
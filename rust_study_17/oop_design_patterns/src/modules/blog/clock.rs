@@ -0,0 +1,45 @@
+// * 博文用到的时钟
+// + 历史记录中的时间从时钟获取, 测试时可以换成 FakeClock, 手动控制时间
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// * 克隆出来的 FakeClock 共享同一个时间, 交给 Post 之后仍然可以在外面调整
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    now: Rc<Cell<SystemTime>>,
+}
+
+impl FakeClock {
+    pub fn new(start: SystemTime) -> FakeClock {
+        FakeClock {
+            now: Rc::new(Cell::new(start)),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+
+    pub fn set(&self, to: SystemTime) {
+        self.now.set(to);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+}
@@ -0,0 +1,29 @@
+// * 博文的审计历史
+// + 每次状态转换和文本修改都会记录: 谁、什么时候、做了什么、之后处于哪个状态
+// + 没有产生任何效果的调用(比如对草案调用 approve)不会被记录
+// + 文本的每个版本(revision)也会保存下来, 可以恢复到之前的某个版本, 恢复本身也是一次修改
+use std::time::SystemTime;
+
+use super::Status;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    AddText { text: String },
+    RequestReview,
+    Approve,
+    // * 通过审核, 并要求到 publish_at 才公开
+    ApproveScheduled { publish_at: SystemTime },
+    Archive,
+    Reject { reason: String },
+    // * 文本恢复到了第几个版本
+    Revert { revision: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub actor: String,
+    pub at: SystemTime,
+    pub action: Action,
+    // * 这次操作之后博文所处的状态
+    pub status: Status,
+}
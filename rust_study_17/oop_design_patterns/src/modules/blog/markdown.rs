@@ -0,0 +1,796 @@
+// * 把博文的 Markdown 文本转换成 HTML, 以及生成纯文本摘要
+// + 支持 CommonMark 中常用的部分:
+//   - 块: ATX 标题(#)、段落、围栏代码块(``` 或 ~~~)、缩进代码块、无序/有序列表、引用(>)、分隔线
+//   - 行内: 强调(* _)、加粗(** __)、行内代码、链接 [文本](地址 "标题")、自动链接 <https://...>、反斜杠转义
+// + 不支持嵌套列表、setext 标题、图片、脚注等, 这些内容会按普通文本处理
+// ? 输出是经过清理的 HTML: 原始 HTML 一律转义, 链接只允许 http、https、mailto 和相对地址,
+//   所以把渲染结果直接放进页面也不会执行作者写的脚本
+// ? 文本来自作者, 可能是故意构造的, 所以解析不能太慢, 也不能递归太深:
+//   - 引用、强调和链接最多嵌套 MAX_DEPTH 层, 更深的部分按普通文本输出
+//   - 结束分隔符、成对的括号和连续的分隔符预先找好, 每个开始分隔符不需要再向后扫描整段文本
+use std::cell::Cell;
+use std::collections::HashMap;
+
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Code {
+        lang: Option<String>,
+        text: String,
+    },
+    List {
+        ordered: bool,
+        start: u64,
+        items: Vec<String>,
+    },
+    Quote(Vec<Block>),
+    Rule,
+}
+
+// * 和 json.rs 一样限制嵌套层数, 避免递归导致栈溢出
+const MAX_DEPTH: usize = 32;
+
+pub fn to_html(src: &str) -> String {
+    let mut out = String::new();
+    render_blocks(&parse_blocks(src, 0), &mut out);
+    out
+}
+
+// * 去掉所有标记之后的纯文本, 最多 max_chars 个字符
+// + 代码块和分隔线不会出现在摘要中, 换行和连续的空白会合并成一个空格
+// + 超出长度时尽量在单词边界截断, 并在末尾加上 "…"
+pub fn excerpt(src: &str, max_chars: usize) -> String {
+    let mut text = String::new();
+    plain_blocks(&parse_blocks(src, 0), &mut text);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let text = words.join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    // * 截断位置正好在单词中间时, 退回到上一个空格
+    let next_is_space = text.chars().nth(max_chars) == Some(' ');
+    let cut = match cut.rfind(' ') {
+        Some(pos) if !next_is_space => &cut[..pos],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+// * 最多 3 个空格缩进的 ``` 或 ~~~, 返回围栏字符、长度和 info 字符串
+fn fence_start(line: &str) -> Option<(char, usize, &str)> {
+    if indent_of(line) > 3 {
+        return None;
+    }
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next()?;
+    if marker != '`' && marker != '~' {
+        return None;
+    }
+    let len = trimmed.chars().take_while(|&c| c == marker).count();
+    let info = trimmed[len..].trim();
+    // * ``` 的 info 中不能再出现反引号, 否则是行内代码
+    if len < 3 || (marker == '`' && info.contains('`')) {
+        return None;
+    }
+    Some((marker, len, info))
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    if indent_of(line) > 3 {
+        return None;
+    }
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') && !rest.starts_with('\t') {
+        return None;
+    }
+    // * 结尾的 # 只是装饰
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with(' ') => stripped.trim_end(),
+        _ => text,
+    };
+    Some((level, text))
+}
+
+fn is_rule(line: &str) -> bool {
+    if indent_of(line) > 3 {
+        return false;
+    }
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && matches!(chars[0], '-' | '*' | '_') && chars.iter().all(|&c| c == chars[0])
+}
+
+// * 列表项的标记, 返回 (是否有序, 起始序号, 标记之后的内容)
+fn list_item(line: &str) -> Option<(bool, u64, &str)> {
+    if indent_of(line) > 3 {
+        return None;
+    }
+    let trimmed = line.trim_start();
+    let rest_after =
+        |rest: &str| rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\t');
+    if let Some(first) = trimmed.chars().next() {
+        if matches!(first, '-' | '*' | '+') && rest_after(&trimmed[1..]) {
+            return Some((false, 1, trimmed[1..].trim()));
+        }
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    if (1..=9).contains(&digits) {
+        let rest = &trimmed[digits..];
+        if (rest.starts_with('.') || rest.starts_with(')')) && rest_after(&rest[1..]) {
+            let start = trimmed[..digits].parse().unwrap();
+            return Some((true, start, rest[1..].trim()));
+        }
+    }
+    None
+}
+
+// * 引用已经嵌套了 MAX_DEPTH 层时, 开头的 > 只是普通文本
+fn starts_quote(line: &str, depth: usize) -> bool {
+    depth < MAX_DEPTH && line.trim_start().starts_with('>')
+}
+
+fn starts_block(line: &str, depth: usize) -> bool {
+    fence_start(line).is_some()
+        || heading(line).is_some()
+        || is_rule(line)
+        || starts_quote(line, depth)
+        || list_item(line).is_some()
+}
+
+// * depth 是当前所在引用的层数
+fn parse_blocks(src: &str, depth: usize) -> Vec<Block> {
+    let lines: Vec<&str> = src.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+        } else if let Some((marker, len, info)) = fence_start(line) {
+            let lang = info.split_whitespace().next().map(str::to_string);
+            let mut text = String::new();
+            i += 1;
+            while i < lines.len() {
+                let closing = lines[i].trim();
+                if indent_of(lines[i]) <= 3
+                    && closing.len() >= len
+                    && closing.chars().all(|c| c == marker)
+                {
+                    break;
+                }
+                text.push_str(lines[i]);
+                text.push('\n');
+                i += 1;
+            }
+            // * 跳过结束的围栏, 没有结束围栏时代码块一直到文本末尾
+            i += 1;
+            blocks.push(Block::Code { lang, text });
+        } else if let Some((level, text)) = heading(line) {
+            blocks.push(Block::Heading(level, text.to_string()));
+            i += 1;
+        } else if is_rule(line) {
+            blocks.push(Block::Rule);
+            i += 1;
+        } else if starts_quote(line, depth) {
+            let mut inner = String::new();
+            while i < lines.len() && starts_quote(lines[i], depth) {
+                let rest = &lines[i].trim_start()[1..];
+                inner.push_str(rest.strip_prefix(' ').unwrap_or(rest));
+                inner.push('\n');
+                i += 1;
+            }
+            blocks.push(Block::Quote(parse_blocks(&inner, depth + 1)));
+        } else if let Some((ordered, start, first)) = list_item(line) {
+            let mut items = vec![first.to_string()];
+            i += 1;
+            while i < lines.len() {
+                let line = lines[i];
+                match list_item(line) {
+                    Some((o, _, text)) if o == ordered => {
+                        items.push(text.to_string());
+                        i += 1;
+                    }
+                    // * 空行之后如果还是同一种列表的项, 列表继续
+                    None if line.trim().is_empty() => {
+                        match lines.get(i + 1).and_then(|next| list_item(next)) {
+                            Some((o, _, _)) if o == ordered => i += 1,
+                            _ => break,
+                        }
+                    }
+                    // * 缩进的行, 或者不是新块开头的行, 是上一项的延续
+                    None if indent_of(line) > 0 || !starts_block(line, depth) => {
+                        let item = items.last_mut().unwrap();
+                        item.push('\n');
+                        item.push_str(line.trim());
+                        i += 1;
+                    }
+                    _ => break,
+                }
+            }
+            blocks.push(Block::List {
+                ordered,
+                start,
+                items,
+            });
+        } else if indent_of(line) >= 4 {
+            let mut text = String::new();
+            while i < lines.len() && (indent_of(lines[i]) >= 4 || lines[i].trim().is_empty()) {
+                text.push_str(lines[i].get(4..).unwrap_or(""));
+                text.push('\n');
+                i += 1;
+            }
+            // * 代码块末尾的空行不算在内
+            let trimmed = text.trim_end_matches('\n').len();
+            text.truncate(trimmed);
+            text.push('\n');
+            blocks.push(Block::Code { lang: None, text });
+        } else {
+            let mut text = String::from(line.trim());
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && !starts_block(lines[i], depth) {
+                text.push('\n');
+                text.push_str(lines[i].trim());
+                i += 1;
+            }
+            blocks.push(Block::Paragraph(text));
+        }
+    }
+    blocks
+}
+
+fn render_blocks(blocks: &[Block], out: &mut String) {
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                out.push_str(&format!("<h{}>", level));
+                inline(text, true, out);
+                out.push_str(&format!("</h{}>\n", level));
+            }
+            Block::Paragraph(text) => {
+                out.push_str("<p>");
+                inline(text, true, out);
+                out.push_str("</p>\n");
+            }
+            Block::Code { lang, text } => {
+                match lang {
+                    Some(lang) => {
+                        out.push_str(&format!("<pre><code class=\"language-{}\">", escape(lang)))
+                    }
+                    None => out.push_str("<pre><code>"),
+                }
+                out.push_str(&escape(text));
+                out.push_str("</code></pre>\n");
+            }
+            Block::List {
+                ordered,
+                start,
+                items,
+            } => {
+                let tag = if *ordered { "ol" } else { "ul" };
+                if *ordered && *start != 1 {
+                    out.push_str(&format!("<ol start=\"{}\">\n", start));
+                } else {
+                    out.push_str(&format!("<{}>\n", tag));
+                }
+                for item in items {
+                    out.push_str("<li>");
+                    inline(item, true, out);
+                    out.push_str("</li>\n");
+                }
+                out.push_str(&format!("</{}>\n", tag));
+            }
+            Block::Quote(inner) => {
+                out.push_str("<blockquote>\n");
+                render_blocks(inner, out);
+                out.push_str("</blockquote>\n");
+            }
+            Block::Rule => out.push_str("<hr />\n"),
+        }
+    }
+}
+
+fn plain_blocks(blocks: &[Block], out: &mut String) {
+    for block in blocks {
+        match block {
+            Block::Heading(_, text) | Block::Paragraph(text) => {
+                inline(text, false, out);
+                out.push(' ');
+            }
+            Block::List { items, .. } => {
+                for item in items {
+                    inline(item, false, out);
+                    out.push(' ');
+                }
+            }
+            Block::Quote(inner) => plain_blocks(inner, out),
+            Block::Code { .. } | Block::Rule => {}
+        }
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// * 只允许没有协议的相对地址, 以及 http、https、mailto
+fn is_safe_url(url: &str) -> bool {
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+    match url.find(':') {
+        // * 冒号出现在 / ? # 之后时, 它不是协议的一部分
+        Some(colon) if !url[..colon].contains(['/', '?', '#']) => {
+            let scheme = url[..colon].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+// * 一段行内文本中预先找好的分隔符位置
+struct Delims {
+    // * [ 和 ( 的位置 -> 和它成对的 ] 和 ) 的位置
+    pairs: HashMap<usize, usize>,
+    // * 每一段连续的 * _ ` 的开始和结束位置, 从小到大
+    runs: Vec<(usize, usize)>,
+    // * 可以作为结束分隔符的位置, 从小到大, 依次是 * ** _ __
+    closers: [Vec<usize>; 4],
+    // * 一段反引号的长度 -> 这么长的每一段反引号的开始位置, 从小到大
+    ticks: HashMap<usize, Vec<usize>>,
+    // * nested 处理过的字符数, 测试用它确认渲染的时间和文本长度成正比
+    steps: Cell<usize>,
+}
+
+impl Delims {
+    fn new(text: &str) -> Delims {
+        let bytes = text.as_bytes();
+        let mut pairs = HashMap::new();
+        // * 方括号可以用反斜杠转义, 圆括号不可以
+        let (mut brackets, mut parens) = (Vec::new(), Vec::new());
+        let mut escaped = false;
+        for (i, &b) in bytes.iter().enumerate() {
+            match b {
+                b'(' => parens.push(i),
+                b')' => {
+                    if let Some(open) = parens.pop() {
+                        pairs.insert(open, i);
+                    }
+                }
+                _ => {}
+            }
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'[' => brackets.push(i),
+                b']' => {
+                    if let Some(open) = brackets.pop() {
+                        pairs.insert(open, i);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // * 结束分隔符前面不能是空白
+        // ? 单个字符的分隔符不能是两个字符分隔符的一部分, 例如 *a **b** c* 中第一个 * 的结束位置是最后一个 *
+        //   两个字符的分隔符从一段连续分隔符的开头每两个字符取一次
+        let mut closers: [Vec<usize>; 4] = Default::default();
+        let mut runs = Vec::new();
+        let mut ticks: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let d = bytes[i];
+            let run = bytes[i..].iter().take_while(|&&b| b == d).count();
+            if matches!(d, b'*' | b'_' | b'`') {
+                runs.push((i, i + run));
+            }
+            if d == b'`' {
+                ticks.entry(run).or_default().push(i);
+            }
+            if d == b'*' || d == b'_' {
+                let kind = if d == b'*' { 0 } else { 2 };
+                let after_space = text[..i]
+                    .chars()
+                    .next_back()
+                    .is_some_and(char::is_whitespace);
+                if run == 1 && !after_space {
+                    closers[kind].push(i);
+                }
+                for at in (i..i + run - 1).step_by(2) {
+                    if at > i || !after_space {
+                        closers[kind + 1].push(at);
+                    }
+                }
+            }
+            i += run;
+        }
+        Delims {
+            pairs,
+            runs,
+            closers,
+            ticks,
+            steps: Cell::new(0),
+        }
+    }
+
+    // * at 所在的那一段连续分隔符的结束位置
+    fn run_end(&self, at: usize) -> usize {
+        let index = self.runs.partition_point(|&(start, _)| start <= at);
+        self.runs[index - 1].1
+    }
+
+    // * from 及之后第一段长度正好是 len 的反引号
+    fn code_closing(&self, len: usize, from: usize) -> Option<usize> {
+        let ticks = self.ticks.get(&len)?;
+        ticks.get(ticks.partition_point(|&at| at < from)).copied()
+    }
+
+    // * from 及之后第一个可以结束 delim 的位置
+    fn closing(&self, delim: &str, from: usize) -> Option<usize> {
+        let kind = match delim {
+            "*" => 0,
+            "**" => 1,
+            "_" => 2,
+            _ => 3,
+        };
+        let closers = &self.closers[kind];
+        closers
+            .get(closers.partition_point(|&at| at < from))
+            .copied()
+    }
+}
+
+// * 解析 [文本](地址 "标题"), 返回 (文本, 地址, 标题, 整个链接的结束位置)
+// + text 从整段文本的 base 开始, 成对的括号必须都在 text 中
+fn link<'a>(
+    text: &'a str,
+    base: usize,
+    start: usize,
+    delims: &Delims,
+) -> Option<(&'a str, &'a str, Option<&'a str>, usize)> {
+    let pair = |open: usize| {
+        let close = *delims.pairs.get(&(base + open))? - base;
+        (close < text.len()).then_some(close)
+    };
+    let close = pair(start)?;
+    if !text[close + 1..].starts_with('(') {
+        return None;
+    }
+    // * 地址中可以有成对的括号, 例如 https://en.wikipedia.org/wiki/Rust_(programming_language)
+    let end = pair(close + 1)?;
+    let target = text[close + 2..end].trim();
+    let (url, title) = match target.find(char::is_whitespace) {
+        Some(space) => {
+            let title = target[space..].trim();
+            let title = title.strip_prefix('"')?.strip_suffix('"')?;
+            (&target[..space], Some(title))
+        }
+        None => (target, None),
+    };
+    let url = url
+        .strip_prefix('<')
+        .and_then(|u| u.strip_suffix('>'))
+        .unwrap_or(url);
+    Some((&text[start + 1..close], url, title, end + 1))
+}
+
+// * 行内元素; html 为 false 时只输出文字, 用来生成摘要
+fn inline(text: &str, html: bool, out: &mut String) {
+    nested(text, 0, &Delims::new(text), 0, html, out);
+}
+
+// * 强调和链接中的文本, text 是整段文本中从 base 开始的一部分, delims 是整段文本的分隔符
+// + depth 是当前所在强调和链接的层数, 达到 MAX_DEPTH 之后分隔符按普通文本输出
+fn nested(text: &str, base: usize, delims: &Delims, depth: usize, html: bool, out: &mut String) {
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        delims.steps.set(delims.steps.get() + 1);
+        let rest = &text[i..];
+        // * 从 i 开始的连续分隔符在 text 中的结束位置, 只对 * _ ` 有意义
+        let run_end = || delims.run_end(base + i).min(base + text.len()) - base;
+        match c {
+            '\\' => {
+                // * 反斜杠后面是 ASCII 标点时, 输出这个标点本身
+                match rest[1..].chars().next() {
+                    Some(p) if p.is_ascii_punctuation() => {
+                        push_text(&p.to_string(), html, out);
+                        i += 2;
+                    }
+                    _ => {
+                        out.push('\\');
+                        i += 1;
+                    }
+                }
+            }
+            '`' => {
+                let end = run_end();
+                let run = end - i;
+                let fence = &rest[..run];
+                // * 找到长度完全相同的一段反引号作为结束
+                let closing = delims
+                    .code_closing(run, base + end)
+                    .map(|at| at - base)
+                    .filter(|at| at + run <= text.len());
+                match closing {
+                    Some(at) => {
+                        let code = text[end..at].replace('\n', " ");
+                        let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                            Some(inner) if !inner.trim().is_empty() => inner.to_string(),
+                            _ => code,
+                        };
+                        if html {
+                            out.push_str("<code>");
+                            out.push_str(&escape(&code));
+                            out.push_str("</code>");
+                        } else {
+                            out.push_str(&code);
+                        }
+                        i = at + run;
+                    }
+                    None => {
+                        push_text(fence, html, out);
+                        i = end;
+                    }
+                }
+            }
+            '*' | '_' if depth < MAX_DEPTH => {
+                let end = run_end();
+                let run = end - i;
+                // * _ 在单词中间时不是强调, 例如 snake_case
+                let intraword = c == '_'
+                    && text[..i]
+                        .chars()
+                        .next_back()
+                        .is_some_and(char::is_alphanumeric);
+                let opens = text[end..]
+                    .chars()
+                    .next()
+                    .is_some_and(|n| !n.is_whitespace());
+                let strong = format!("{}{}", c, c);
+                let single = c.to_string();
+                let mut matched = false;
+                if opens && !intraword {
+                    for delim in [strong.as_str(), single.as_str()] {
+                        if run < delim.len() {
+                            continue;
+                        }
+                        let from = i + delim.len();
+                        // * 结束分隔符不能在开始分隔符所在的那一段里, 否则一长串 * 会被拆成很多个空的强调
+                        let close = delims
+                            .closing(delim, base + end)
+                            .map(|at| at - base)
+                            .filter(|at| at + delim.len() <= text.len());
+                        if let Some(close) = close {
+                            let tag = if delim.len() == 2 { "strong" } else { "em" };
+                            if html {
+                                out.push_str(&format!("<{}>", tag));
+                            }
+                            nested(
+                                &text[from..close],
+                                base + from,
+                                delims,
+                                depth + 1,
+                                html,
+                                out,
+                            );
+                            if html {
+                                out.push_str(&format!("</{}>", tag));
+                            }
+                            i = close + delim.len();
+                            matched = true;
+                            break;
+                        }
+                    }
+                }
+                if !matched {
+                    push_text(&rest[..run], html, out);
+                    i += run;
+                }
+            }
+            '[' if depth < MAX_DEPTH => match link(text, base, i, delims) {
+                Some((label, url, title, end)) => {
+                    let safe = is_safe_url(url);
+                    if html && safe {
+                        out.push_str(&format!("<a href=\"{}\"", escape(url)));
+                        if let Some(title) = title {
+                            out.push_str(&format!(" title=\"{}\"", escape(title)));
+                        }
+                        out.push('>');
+                    }
+                    nested(label, base + i + 1, delims, depth + 1, html, out);
+                    if html && safe {
+                        out.push_str("</a>");
+                    }
+                    i = end;
+                }
+                None => {
+                    push_text("[", html, out);
+                    i += 1;
+                }
+            },
+            '<' => {
+                // * 自动链接中不能再有 <, 所以只需要找到下一个 < 或 >
+                let autolink = rest[1..]
+                    .find(['<', '>'])
+                    .filter(|&end| rest[1 + end..].starts_with('>'))
+                    .map(|end| &rest[1..1 + end])
+                    .filter(|url| url.contains(':') && is_safe_url(url));
+                match autolink {
+                    Some(url) => {
+                        if html {
+                            let url = escape(url);
+                            out.push_str(&format!("<a href=\"{}\">{}</a>", url, url));
+                        } else {
+                            out.push_str(url);
+                        }
+                        i += url.len() + 2;
+                    }
+                    None => {
+                        push_text("<", html, out);
+                        i += 1;
+                    }
+                }
+            }
+            '\n' => {
+                out.push(if html { '\n' } else { ' ' });
+                i += 1;
+            }
+            c => {
+                push_text(&c.to_string(), html, out);
+                i += c.len_utf8();
+            }
+        }
+    }
+}
+
+fn push_text(text: &str, html: bool, out: &mut String) {
+    if html {
+        out.push_str(&escape(text));
+    } else {
+        out.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_blocks() {
+        let src = "# Title #\n\nSome *text*\nover two lines.\n\n- one\n- **two**\n  continued\n\n3. three\n4. four\n\n> quoted\n\n---\n\n```rust\nfn main() {}\n```\n\n    indented <code>\n";
+        assert_eq!(
+            to_html(src),
+            "<h1>Title</h1>\n\
+             <p>Some <em>text</em>\nover two lines.</p>\n\
+             <ul>\n<li>one</li>\n<li><strong>two</strong>\ncontinued</li>\n</ul>\n\
+             <ol start=\"3\">\n<li>three</li>\n<li>four</li>\n</ol>\n\
+             <blockquote>\n<p>quoted</p>\n</blockquote>\n\
+             <hr />\n\
+             <pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n\
+             <pre><code>indented &lt;code&gt;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_elements() {
+        assert_eq!(
+            to_html("a **b _c_ d** `x < y` snake_case \\*lit\\*"),
+            "<p>a <strong>b <em>c</em> d</strong> <code>x &lt; y</code> snake_case *lit*</p>\n"
+        );
+        assert_eq!(
+            to_html("[the *docs*](https://doc.rust-lang.org \"Rust\") <https://a.b/c?d=1&e=2>"),
+            "<p><a href=\"https://doc.rust-lang.org\" title=\"Rust\">the <em>docs</em></a> \
+             <a href=\"https://a.b/c?d=1&amp;e=2\">https://a.b/c?d=1&amp;e=2</a></p>\n"
+        );
+        // * 没有结束的分隔符按普通文本输出
+        assert_eq!(to_html("2 * 3 = 6, `open"), "<p>2 * 3 = 6, `open</p>\n");
+    }
+
+    #[test]
+    fn sanitizes_html_and_links() {
+        assert_eq!(
+            to_html("<script>alert('x')</script>"),
+            "<p>&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;</p>\n"
+        );
+        assert_eq!(
+            to_html("[click](javascript:alert(1)) [ok](/posts/1) [mail](mailto:a@b.c)"),
+            "<p>click <a href=\"/posts/1\">ok</a> <a href=\"mailto:a@b.c\">mail</a></p>\n"
+        );
+        // * 引号被转义, 地址不能跳出 href 属性
+        assert_eq!(
+            to_html("[x](\"onmouseover=\"alert(1))"),
+            "<p><a href=\"&quot;onmouseover=&quot;alert(1)\">x</a></p>\n"
+        );
+        assert_eq!(
+            to_html("```\"><script>\n<b>\n```"),
+            "<pre><code class=\"language-&quot;&gt;&lt;script&gt;\">&lt;b&gt;\n</code></pre>\n"
+        );
+    }
+
+    // * 渲染一段行内文本时 nested 处理的字符数
+    fn steps(text: &str) -> usize {
+        let delims = Delims::new(text);
+        nested(text, 0, &delims, 0, true, &mut String::new());
+        delims.steps.get()
+    }
+
+    #[test]
+    fn limits_nesting_and_stays_linear() {
+        // * 超过 MAX_DEPTH 层的引用按普通文本输出
+        let html = to_html(&">".repeat(20_000));
+        assert_eq!(html.matches("<blockquote>").count(), MAX_DEPTH);
+        assert!(html.contains(&"&gt;".repeat(20_000 - MAX_DEPTH)));
+
+        let links = format!("{}x{}", "[".repeat(50_000), "](u)".repeat(50_000));
+        let html = to_html(&links);
+        assert_eq!(html.matches("<a href=\"u\">").count(), MAX_DEPTH);
+        assert_eq!(excerpt(&links, 10), "[[[[[[[[[[…");
+
+        let emphasis = "*a ".repeat(20_000);
+        assert_eq!(
+            to_html(&emphasis),
+            format!("<p>{}</p>\n", emphasis.trim_end())
+        );
+        assert_eq!(to_html(&"[".repeat(50_000)).len(), 50_000 + 8);
+        assert_eq!(to_html(&"<".repeat(50_000)).len(), 50_000 * 4 + 8);
+
+        // * 一长串 * 或 _ 没有结束分隔符, 按普通文本输出
+        for run in ["*".repeat(80_000), "_".repeat(80_000)] {
+            let text = format!("a {}", run);
+            assert_eq!(to_html(&text), format!("<p>{}</p>\n", text));
+        }
+        // * 长度各不相同的反引号都没有结束
+        let ticks = (1..300)
+            .map(|n| "`".repeat(n))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(to_html(&ticks), format!("<p>{}</p>\n", ticks));
+
+        // * 每个字符最多处理一次, 连续的分隔符只算一次
+        for text in [
+            &links,
+            &emphasis,
+            &ticks,
+            &"*".repeat(80_000),
+            &"*a*".repeat(20_000),
+        ] {
+            assert!(steps(text) <= text.chars().count());
+        }
+        assert_eq!(steps(&"*".repeat(80_000)), 1);
+        assert_eq!(steps(&ticks), 299 * 2 - 1);
+    }
+
+    #[test]
+    fn builds_plain_text_excerpts() {
+        let src = "# Lunch\n\nI ate a **salad** for [lunch](https://x.y) today.\n\n```\ncode is skipped\n```\n\n- and it was great";
+        assert_eq!(
+            excerpt(src, 100),
+            "Lunch I ate a salad for lunch today. and it was great"
+        );
+        assert_eq!(excerpt(src, 15), "Lunch I ate a…");
+        assert_eq!(excerpt(src, 13), "Lunch I ate a…");
+        assert_eq!(excerpt("你好世界", 2), "你好…");
+        assert_eq!(excerpt("", 10), "");
+    }
+}
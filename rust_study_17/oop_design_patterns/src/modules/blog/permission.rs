@@ -0,0 +1,177 @@
+// * 谁可以对博文做什么
+// + 每个修改博文的方法都需要传入执行操作的 Actor, 检查分两步:
+//   1. 角色是否允许这个操作(authorize), 例如只有作者能编辑, 作者不能审核自己的博文
+//   2. 博文当前的状态是否允许这个操作(由各个 State 的 allows 决定), 例如发布之后谁都不能再修改文本
+// + 不允许的操作返回 PostError, 而不是像最初的版本那样被静默忽略
+use std::error::Error;
+use std::fmt;
+
+use super::review::{role_name, Actor, Role};
+use super::{status_name, Status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    // * 添加文本、恢复版本、撤销
+    Edit,
+    RequestReview,
+    Approve,
+    Reject,
+    Archive,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::Edit => "修改文本",
+            Operation::RequestReview => "请求审核",
+            Operation::Approve => "通过审核",
+            Operation::Reject => "退回",
+            Operation::Archive => "归档",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostError {
+    // * 角色不允许这个操作
+    Forbidden {
+        actor: String,
+        role: Role,
+        operation: Operation,
+    },
+    // * 作者只能编辑自己的博文
+    NotAuthor {
+        actor: String,
+        author: String,
+        operation: Operation,
+    },
+    // * 博文当前的状态不允许这个操作
+    InvalidState {
+        status: Status,
+        operation: Operation,
+    },
+    EmptyReason,
+    NoSuchRevision(usize),
+    NothingToUndo,
+}
+
+impl fmt::Display for PostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostError::Forbidden {
+                actor,
+                role,
+                operation,
+            } => write!(
+                f,
+                "{} 的角色是 {}, 不能{}",
+                actor,
+                role_name(*role),
+                operation
+            ),
+            PostError::NotAuthor {
+                actor,
+                author,
+                operation,
+            } => write!(f, "{} 不是作者 {}, 不能{}", actor, author, operation),
+            PostError::InvalidState { status, operation } => {
+                write!(f, "博文当前是 {}, 不能{}", status_name(*status), operation)
+            }
+            PostError::EmptyReason => write!(f, "退回时必须给出原因"),
+            PostError::NoSuchRevision(revision) => write!(f, "版本 {} 不存在", revision),
+            PostError::NothingToUndo => write!(f, "没有可以撤销的修改"),
+        }
+    }
+}
+
+impl Error for PostError {}
+
+// * 只检查角色, 不关心博文的状态
+// + author 是博文的作者: 只有作者本人能编辑和提交, 管理员也不能修改别人的草案
+// + 只有编辑(Editor 或者 Admin)能审核, 并且不能审核自己的博文
+pub(super) fn authorize(
+    actor: &Actor,
+    author: &str,
+    operation: Operation,
+) -> Result<(), PostError> {
+    let allowed = match operation {
+        Operation::Edit | Operation::RequestReview => {
+            if actor.role == Role::Author && actor.id != author {
+                return Err(PostError::NotAuthor {
+                    actor: actor.id.clone(),
+                    author: author.to_string(),
+                    operation,
+                });
+            }
+            actor.role == Role::Author
+        }
+        Operation::Approve | Operation::Reject => {
+            matches!(actor.role, Role::Editor | Role::Admin) && actor.id != author
+        }
+        Operation::Archive => matches!(actor.role, Role::Editor | Role::Admin),
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(PostError::Forbidden {
+            actor: actor.id.clone(),
+            role: actor.role,
+            operation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_decide_who_can_do_what() {
+        let table = [
+            (Actor::author("alice"), [true, true, false, false, false]),
+            (Actor::peer("bob"), [false, false, false, false, false]),
+            (Actor::editor("ed"), [false, false, true, true, true]),
+            (Actor::admin("root"), [false, false, true, true, true]),
+        ];
+        let operations = [
+            Operation::Edit,
+            Operation::RequestReview,
+            Operation::Approve,
+            Operation::Reject,
+            Operation::Archive,
+        ];
+        for (actor, expected) in table {
+            for (operation, allowed) in operations.into_iter().zip(expected) {
+                assert_eq!(
+                    authorize(&actor, "alice", operation).is_ok(),
+                    allowed,
+                    "{:?} {:?}",
+                    actor,
+                    operation
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn authors_only_touch_their_own_posts() {
+        assert_eq!(
+            authorize(&Actor::author("mallory"), "alice", Operation::Edit),
+            Err(PostError::NotAuthor {
+                actor: "mallory".to_string(),
+                author: "alice".to_string(),
+                operation: Operation::Edit,
+            })
+        );
+        // * 作者即使同时是编辑, 也不能审核自己的博文
+        assert_eq!(
+            authorize(&Actor::editor("alice"), "alice", Operation::Approve),
+            Err(PostError::Forbidden {
+                actor: "alice".to_string(),
+                role: Role::Editor,
+                operation: Operation::Approve,
+            })
+        );
+    }
+}
@@ -0,0 +1,134 @@
+// * 参与者(Actor)、审核策略
+// + 每个参与者有自己的身份(id)和角色, 能做哪些操作由角色决定, 见 permission.rs:
+//   - Author: 作者, 编辑自己的草案、请求审核
+//   - Peer: 同事, 只能阅读, 不能修改或者审核博文
+//   - Editor: 编辑, 可以通过、退回和归档
+//   - Admin: 管理员, 和编辑一样, 同样不能修改别人的草案, 也不能审核自己的博文
+// + 同一个审核人重复通过只算一次
+// + 策略决定一篇等待审核的博文需要什么样的审核才能发布:
+//   - 需要多少个不同审核人的通过
+//   - 是否至少需要一个编辑(Editor 或者 Admin)的通过
+//   - 被退回时是否清空已经获得的通过(否则重新提交后之前的通过仍然有效)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Author,
+    Peer,
+    Editor,
+    Admin,
+}
+
+// * 角色的英文名字, 用在错误信息、命令行和保存的文件中
+pub fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::Author => "author",
+        Role::Peer => "peer",
+        Role::Editor => "editor",
+        Role::Admin => "admin",
+    }
+}
+
+pub fn parse_role(name: &str) -> Option<Role> {
+    match name {
+        "author" => Some(Role::Author),
+        "peer" => Some(Role::Peer),
+        "editor" => Some(Role::Editor),
+        "admin" => Some(Role::Admin),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actor {
+    pub id: String,
+    pub role: Role,
+}
+
+// ? 审核相关的代码中仍然使用 Reviewer 这个名字, 它和 Actor 是同一个类型
+pub type Reviewer = Actor;
+
+impl Actor {
+    pub fn new(id: &str, role: Role) -> Actor {
+        Actor {
+            id: id.to_string(),
+            role,
+        }
+    }
+
+    pub fn author(id: &str) -> Actor {
+        Actor::new(id, Role::Author)
+    }
+
+    pub fn peer(id: &str) -> Actor {
+        Actor::new(id, Role::Peer)
+    }
+
+    pub fn editor(id: &str) -> Actor {
+        Actor::new(id, Role::Editor)
+    }
+
+    pub fn admin(id: &str) -> Actor {
+        Actor::new(id, Role::Admin)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalPolicy {
+    pub(super) required_approvals: usize,
+    pub(super) require_editor: bool,
+    pub(super) reset_on_rejection: bool,
+}
+
+// * 默认策略和最初的版本一样: 任何一个人通过就发布
+impl Default for ApprovalPolicy {
+    fn default() -> Self {
+        ApprovalPolicy {
+            required_approvals: 1,
+            require_editor: false,
+            reset_on_rejection: true,
+        }
+    }
+}
+
+impl ApprovalPolicy {
+    pub fn new() -> ApprovalPolicy {
+        ApprovalPolicy::default()
+    }
+
+    // * 例如: ApprovalPolicy::new().required_approvals(2).require_editor()
+    pub fn required_approvals(mut self, count: usize) -> ApprovalPolicy {
+        assert!(count > 0, "at least one approval is required");
+        self.required_approvals = count;
+        self
+    }
+
+    pub fn require_editor(mut self) -> ApprovalPolicy {
+        self.require_editor = true;
+        self
+    }
+
+    pub fn reset_on_rejection(mut self, reset: bool) -> ApprovalPolicy {
+        self.reset_on_rejection = reset;
+        self
+    }
+
+    pub fn resets_on_rejection(&self) -> bool {
+        self.reset_on_rejection
+    }
+
+    // * 已有的通过是否满足发布条件
+    pub fn is_satisfied(&self, approvals: &[Reviewer]) -> bool {
+        approvals.len() >= self.required_approvals
+            && (!self.require_editor
+                || approvals
+                    .iter()
+                    .any(|r| matches!(r.role, Role::Editor | Role::Admin)))
+    }
+}
+
+// * 记录一次通过, 同一个审核人已经通过过时忽略
+pub(super) fn record(approvals: &mut Vec<Reviewer>, reviewer: &Reviewer) {
+    if !approvals.iter().any(|r| r.id == reviewer.id) {
+        approvals.push(reviewer.clone());
+    }
+}
@@ -0,0 +1,635 @@
+// * 把博文保存到 JSON 文件中
+// + Post 的 state 是一个 trait 对象, 没法直接保存, 所以保存时按 Status 转换成带标签的值:
+//   {"status": "pending_review", "approvals": [...]}
+// + 保存的内容包括: 作者、文本、所有版本、审核策略、当前状态和历史记录
+// + 时钟不会被保存, 加载出来的博文使用系统时钟
+// + 文件格式带有版本号, 以后格式变化时可以识别出旧文件
+// ? 只使用标准库, 所以没有选择 SQLite, JSON 的读写见 json.rs
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::json::{self, object, Value};
+use super::review::{parse_role, role_name};
+use super::{
+    parse_status, status_name, Action, ApprovalPolicy, Archived, Draft, Event, PendingReview, Post,
+    Published, Reviewer, Scheduled, State,
+};
+
+pub const FORMAT_VERSION: u64 = 1;
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    Json(json::ParseError),
+    // * JSON 格式正确, 但是内容不是保存的博文(缺少字段、类型不对等)
+    Invalid(String),
+    UnsupportedVersion(u64),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "读写博文文件失败: {}", e),
+            StoreError::Json(e) => write!(f, "{}", e),
+            StoreError::Invalid(msg) => write!(f, "博文文件内容错误: {}", msg),
+            StoreError::UnsupportedVersion(v) => {
+                write!(f, "不支持的文件版本 {}, 当前版本为 {}", v, FORMAT_VERSION)
+            }
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Io(e) => Some(e),
+            StoreError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+impl From<json::ParseError> for StoreError {
+    fn from(e: json::ParseError) -> Self {
+        StoreError::Json(e)
+    }
+}
+
+// * 按 id 保存博文, id 从 1 开始递增, 删除之后也不会被重复使用
+pub struct PostStore {
+    path: PathBuf,
+    posts: BTreeMap<u64, Post>,
+    next_id: u64,
+}
+
+impl PostStore {
+    // * 文件不存在时得到一个空的 store, 第一次 save 时才会创建文件
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<PostStore, StoreError> {
+        let path = path.as_ref().to_path_buf();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(PostStore {
+                    path,
+                    posts: BTreeMap::new(),
+                    next_id: 1,
+                })
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let (posts, next_id) = decode_store(&json::parse(&text)?)?;
+        Ok(PostStore {
+            path,
+            posts,
+            next_id,
+        })
+    }
+
+    // * 先写临时文件再重命名, 保存到一半失败时不会破坏原来的文件
+    pub fn save(&self) -> Result<(), StoreError> {
+        let mut tmp = self.path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut text = self.to_json().to_pretty_string();
+        text.push('\n');
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn insert(&mut self, post: Post) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.posts.insert(id, post);
+        id
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Post> {
+        self.posts.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut Post> {
+        self.posts.get_mut(&id)
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Post> {
+        self.posts.remove(&id)
+    }
+
+    // * 按 id 从小到大
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &Post)> {
+        self.posts.iter().map(|(id, post)| (*id, post))
+    }
+
+    pub fn len(&self) -> usize {
+        self.posts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.posts.is_empty()
+    }
+
+    fn to_json(&self) -> Value {
+        let posts = self
+            .posts
+            .iter()
+            .map(|(id, post)| {
+                let mut value = encode_post(post);
+                if let Value::Object(fields) = &mut value {
+                    fields.insert(0, (String::from("id"), (*id).into()));
+                }
+                value
+            })
+            .collect::<Vec<_>>();
+        object(vec![
+            ("version", FORMAT_VERSION.into()),
+            ("next_id", self.next_id.into()),
+            ("posts", posts.into()),
+        ])
+    }
+}
+
+fn decode_store(value: &Value) -> Result<(BTreeMap<u64, Post>, u64), StoreError> {
+    let version = u64_field(value, "version")?;
+    if version != FORMAT_VERSION {
+        return Err(StoreError::UnsupportedVersion(version));
+    }
+    let mut posts = BTreeMap::new();
+    for item in array_field(value, "posts")? {
+        let id = u64_field(item, "id")?;
+        if posts.insert(id, decode_post(item)?).is_some() {
+            return Err(invalid(format!("重复的博文 id {}", id)));
+        }
+    }
+    let next_id = u64_field(value, "next_id")?;
+    if posts.keys().any(|&id| id >= next_id) {
+        return Err(invalid(String::from("next_id 必须大于所有博文的 id")));
+    }
+    // * insert 会把 next_id 加一, 所以它不能是 u64::MAX
+    if next_id == u64::MAX {
+        return Err(invalid(String::from("next_id 超出范围")));
+    }
+    Ok((posts, next_id))
+}
+
+// * 把一篇博文转换成 JSON
+pub fn encode_post(post: &Post) -> Value {
+    let state = post.state.as_ref().unwrap();
+    let mut state_fields = vec![("status", status_name(state.status()).into())];
+    if let Some(reason) = state.rejection_reason() {
+        state_fields.push(("rejection_reason", reason.into()));
+    }
+    if let Some(publish_at) = state.publish_at() {
+        push_time(
+            &mut state_fields,
+            "publish_at_secs",
+            "publish_at_nanos",
+            publish_at,
+        );
+    }
+    state_fields.push(("approvals", encode_reviewers(state.approvals())));
+
+    let policy = &post.policy;
+    object(vec![
+        ("author", post.author.as_str().into()),
+        ("content", post.content.as_str().into()),
+        (
+            "revisions",
+            post.revisions
+                .iter()
+                .map(|r| r.as_str().into())
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        (
+            "policy",
+            object(vec![
+                (
+                    "required_approvals",
+                    (policy.required_approvals as u64).into(),
+                ),
+                ("require_editor", policy.require_editor.into()),
+                ("reset_on_rejection", policy.reset_on_rejection.into()),
+            ]),
+        ),
+        ("state", object(state_fields)),
+        (
+            "history",
+            post.history
+                .iter()
+                .map(encode_event)
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+    ])
+}
+
+// * 从 JSON 恢复一篇博文
+pub fn decode_post(value: &Value) -> Result<Post, StoreError> {
+    let policy_value = field(value, "policy")?;
+    let required_approvals = u64_field(policy_value, "required_approvals")?;
+    if required_approvals == 0 {
+        return Err(invalid(String::from("required_approvals 不能为 0")));
+    }
+    let policy = ApprovalPolicy {
+        required_approvals: required_approvals as usize,
+        require_editor: bool_field(policy_value, "require_editor")?,
+        reset_on_rejection: bool_field(policy_value, "reset_on_rejection")?,
+    };
+
+    let state_value = field(value, "state")?;
+    let approvals = array_field(state_value, "approvals")?
+        .iter()
+        .map(decode_reviewer)
+        .collect::<Result<Vec<_>, _>>()?;
+    let state: Box<dyn State> = match str_field(state_value, "status")? {
+        "draft" => Box::new(Draft {
+            rejection_reason: match state_value.get("rejection_reason") {
+                None | Some(Value::Null) => None,
+                Some(reason) => Some(expect_str(reason, "rejection_reason")?.to_string()),
+            },
+            approvals,
+        }),
+        "pending_review" => Box::new(PendingReview {
+            approvals,
+            publish_at: match state_value.get("publish_at_secs") {
+                None => None,
+                Some(_) => Some(time_field(
+                    state_value,
+                    "publish_at_secs",
+                    "publish_at_nanos",
+                )?),
+            },
+        }),
+        "scheduled" => Box::new(Scheduled {
+            approvals,
+            publish_at: time_field(state_value, "publish_at_secs", "publish_at_nanos")?,
+        }),
+        "published" => Box::new(Published { approvals }),
+        "archived" => Box::new(Archived { approvals }),
+        other => return Err(invalid(format!("未知的状态 {:?}", other))),
+    };
+
+    let revisions = array_field(value, "revisions")?
+        .iter()
+        .map(|r| expect_str(r, "revisions").map(str::to_string))
+        .collect::<Result<Vec<_>, _>>()?;
+    let content = str_field(value, "content")?.to_string();
+    if revisions.last() != Some(&content) {
+        return Err(invalid(String::from("最后一个版本必须和 content 一致")));
+    }
+    let history = array_field(value, "history")?
+        .iter()
+        .map(decode_event)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut post = Post::with_policy(policy).written_by(str_field(value, "author")?);
+    post.state = Some(state);
+    post.content = content;
+    post.revisions = revisions;
+    post.history = history;
+    Ok(post)
+}
+
+fn encode_reviewers(reviewers: &[Reviewer]) -> Value {
+    reviewers
+        .iter()
+        .map(|r| {
+            object(vec![
+                ("id", r.id.as_str().into()),
+                ("role", role_name(r.role).into()),
+            ])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn decode_reviewer(value: &Value) -> Result<Reviewer, StoreError> {
+    let role = str_field(value, "role")?;
+    let role = parse_role(role).ok_or_else(|| invalid(format!("未知的角色 {:?}", role)))?;
+    Ok(Reviewer::new(str_field(value, "id")?, role))
+}
+
+fn encode_event(event: &Event) -> Value {
+    let mut fields = vec![("actor", event.actor.as_str().into())];
+    push_time(&mut fields, "at_secs", "at_nanos", event.at);
+    fields.push(("status", status_name(event.status).into()));
+    match &event.action {
+        Action::AddText { text } => {
+            fields.push(("action", "add_text".into()));
+            fields.push(("text", text.as_str().into()));
+        }
+        Action::RequestReview => fields.push(("action", "request_review".into())),
+        Action::Approve => fields.push(("action", "approve".into())),
+        Action::ApproveScheduled { publish_at } => {
+            fields.push(("action", "approve_scheduled".into()));
+            push_time(
+                &mut fields,
+                "publish_at_secs",
+                "publish_at_nanos",
+                *publish_at,
+            );
+        }
+        Action::Archive => fields.push(("action", "archive".into())),
+        Action::Reject { reason } => {
+            fields.push(("action", "reject".into()));
+            fields.push(("reason", reason.as_str().into()));
+        }
+        Action::Revert { revision } => {
+            fields.push(("action", "revert".into()));
+            fields.push(("revision", (*revision as u64).into()));
+        }
+    }
+    object(fields)
+}
+
+fn decode_event(value: &Value) -> Result<Event, StoreError> {
+    let action = match str_field(value, "action")? {
+        "add_text" => Action::AddText {
+            text: str_field(value, "text")?.to_string(),
+        },
+        "request_review" => Action::RequestReview,
+        "approve" => Action::Approve,
+        "approve_scheduled" => Action::ApproveScheduled {
+            publish_at: time_field(value, "publish_at_secs", "publish_at_nanos")?,
+        },
+        "archive" => Action::Archive,
+        "reject" => Action::Reject {
+            reason: str_field(value, "reason")?.to_string(),
+        },
+        "revert" => Action::Revert {
+            revision: u64_field(value, "revision")? as usize,
+        },
+        other => return Err(invalid(format!("未知的操作 {:?}", other))),
+    };
+    let at = time_field(value, "at_secs", "at_nanos")?;
+    let status = str_field(value, "status")?;
+    Ok(Event {
+        actor: str_field(value, "actor")?.to_string(),
+        at,
+        action,
+        status: parse_status(status).ok_or_else(|| invalid(format!("未知的状态 {:?}", status)))?,
+    })
+}
+
+// * 时间按距离 1970-01-01 的秒数和纳秒数分成两个字段保存, 不会丢失精度
+// ? 早于 1970 年的时间不会出现在博文中, 这里按 0 处理
+fn push_time<'a>(
+    fields: &mut Vec<(&'a str, Value)>,
+    secs: &'a str,
+    nanos: &'a str,
+    at: SystemTime,
+) {
+    let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+    fields.push((secs, since_epoch.as_secs().into()));
+    fields.push((nanos, (since_epoch.subsec_nanos() as u64).into()));
+}
+
+fn time_field(value: &Value, secs: &str, nanos: &str) -> Result<SystemTime, StoreError> {
+    let nanos_value = u64_field(value, nanos)?;
+    if nanos_value >= 1_000_000_000 {
+        return Err(invalid(format!("{} 超出范围", nanos)));
+    }
+    // ? 文件中的秒数可能超出 SystemTime 能表示的范围, 直接相加会 panic
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(u64_field(value, secs)?))
+        .and_then(|at| at.checked_add(Duration::from_nanos(nanos_value)))
+        .ok_or_else(|| invalid(format!("{} 超出范围", secs)))
+}
+
+fn invalid(msg: String) -> StoreError {
+    StoreError::Invalid(msg)
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value, StoreError> {
+    value
+        .get(key)
+        .ok_or_else(|| invalid(format!("缺少字段 {:?}", key)))
+}
+
+fn expect_str<'a>(value: &'a Value, key: &str) -> Result<&'a str, StoreError> {
+    value
+        .as_str()
+        .ok_or_else(|| invalid(format!("字段 {:?} 应该是字符串", key)))
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Result<&'a str, StoreError> {
+    expect_str(field(value, key)?, key)
+}
+
+fn u64_field(value: &Value, key: &str) -> Result<u64, StoreError> {
+    field(value, key)?
+        .as_u64()
+        .ok_or_else(|| invalid(format!("字段 {:?} 应该是非负整数", key)))
+}
+
+fn bool_field(value: &Value, key: &str) -> Result<bool, StoreError> {
+    field(value, key)?
+        .as_bool()
+        .ok_or_else(|| invalid(format!("字段 {:?} 应该是布尔值", key)))
+}
+
+fn array_field<'a>(value: &'a Value, key: &str) -> Result<&'a [Value], StoreError> {
+    field(value, key)?
+        .as_array()
+        .ok_or_else(|| invalid(format!("字段 {:?} 应该是数组", key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::blog::{Actor, Clock, FakeClock, Status, ANONYMOUS};
+
+    fn clock() -> FakeClock {
+        FakeClock::new(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789))
+    }
+
+    // * 除了时钟以外, 加载出来的博文应该和保存之前完全一样
+    fn assert_same(a: &Post, b: &Post) {
+        let raw = |p: &Post| p.state.as_ref().unwrap().status();
+        assert_eq!(raw(a), raw(b));
+        assert_eq!(a.publish_at(), b.publish_at());
+        assert_eq!(a.author(), b.author());
+        assert_eq!(a.content, b.content);
+        assert_eq!(a.revisions(), b.revisions());
+        assert_eq!(a.policy(), b.policy());
+        assert_eq!(a.approvals(), b.approvals());
+        assert_eq!(a.rejection_reason(), b.rejection_reason());
+        assert_eq!(a.history(), b.history());
+    }
+
+    fn round_trip(post: &Post) -> Post {
+        let text = encode_post(post).to_string();
+        decode_post(&json::parse(&text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_every_state() {
+        let policy = ApprovalPolicy::new()
+            .required_approvals(2)
+            .require_editor()
+            .reset_on_rejection(false);
+        let mut post = Post::with_policy(policy)
+            .written_by("alice")
+            .with_clock(clock());
+        let alice = Actor::author("alice");
+        post.add_text(&alice, "Hello, \"world\"\n").unwrap();
+        assert_same(&post, &round_trip(&post));
+
+        post.request_review(&alice).unwrap();
+        post.approve(&Reviewer::editor("bob")).unwrap();
+        assert_eq!(post.status(), Status::PendingReview);
+        assert_same(&post, &round_trip(&post));
+
+        post.reject(&Reviewer::editor("ed"), "needs a title")
+            .unwrap();
+        post.undo(&alice).unwrap();
+        assert_eq!(post.rejection_reason(), Some("needs a title"));
+        assert_eq!(post.approvals().len(), 1);
+        assert_same(&post, &round_trip(&post));
+
+        post.request_review(&alice).unwrap();
+        post.approve(&Actor::admin("root")).unwrap();
+        assert_eq!(post.status(), Status::Published);
+        assert_same(&post, &round_trip(&post));
+
+        post.archive(&Reviewer::editor("ed")).unwrap();
+        assert_eq!(post.status(), Status::Archived);
+        assert_same(&post, &round_trip(&post));
+    }
+
+    #[test]
+    fn round_trips_scheduled_posts() {
+        let clock = clock();
+        let publish_at = clock.now() + Duration::new(3_600, 5);
+        let mut post = Post::with_policy(ApprovalPolicy::new().required_approvals(2))
+            .with_clock(clock.clone());
+        let author = Actor::author(ANONYMOUS);
+        post.add_text(&author, "embargoed").unwrap();
+        post.request_review(&author).unwrap();
+        post.approve_scheduled(&Reviewer::editor("bob"), publish_at)
+            .unwrap();
+        // * 还在等待审核, 但是已经记下了公开时间
+        let loaded = round_trip(&post);
+        assert_same(&post, &loaded);
+        assert_eq!(loaded.publish_at(), Some(publish_at));
+
+        post.approve(&Reviewer::editor("carol")).unwrap();
+        assert_eq!(post.status(), Status::Scheduled);
+        let loaded = round_trip(&post);
+        assert_same(&post, &loaded);
+        // * 加载出来的博文使用系统时钟, 公开时间早已过去
+        assert_eq!(loaded.status(), Status::Published);
+        assert_eq!(loaded.content(), "embargoed");
+    }
+
+    #[test]
+    fn saves_and_reopens_a_file() {
+        let path = std::env::temp_dir().join(format!("blog-store-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut store = PostStore::open(&path).unwrap();
+        assert!(store.is_empty());
+        let mut post = Post::new().written_by("alice");
+        post.add_text(&Actor::author("alice"), "first").unwrap();
+        let first = store.insert(post);
+        let second = store.insert(Post::new().written_by("bob"));
+        store
+            .get_mut(second)
+            .unwrap()
+            .request_review(&Actor::author("bob"))
+            .unwrap();
+        store.remove(first);
+        store.save().unwrap();
+
+        let mut reopened = PostStore::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(
+            reopened.get(second).unwrap().status(),
+            Status::PendingReview
+        );
+        // * 删除的 id 不会被重复使用
+        assert_eq!(reopened.insert(Post::new()), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let decode = |text: &str| decode_store(&json::parse(text).unwrap());
+        assert!(matches!(
+            decode(r#"{"version": 2, "next_id": 1, "posts": []}"#),
+            Err(StoreError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            decode(r#"{"version": 1, "posts": []}"#),
+            Err(StoreError::Invalid(_))
+        ));
+
+        let mut post = encode_post(&Post::new());
+        if let Value::Object(fields) = &mut post {
+            fields.insert(0, (String::from("id"), 1u64.into()));
+            fields.retain(|(k, _)| k != "state");
+            fields.push((
+                String::from("state"),
+                object(vec![
+                    ("status", "lost".into()),
+                    ("approvals", Vec::new().into()),
+                ]),
+            ));
+        }
+        let text = object(vec![
+            ("version", 1u64.into()),
+            ("next_id", 2u64.into()),
+            ("posts", vec![post].into()),
+        ])
+        .to_string();
+        assert!(matches!(decode(&text), Err(StoreError::Invalid(msg)) if msg.contains("lost")));
+    }
+
+    #[test]
+    fn rejects_out_of_range_numbers() {
+        let decode = |text: &str| decode_store(&json::parse(text).unwrap());
+        let text = format!(r#"{{"version": 1, "next_id": {}, "posts": []}}"#, u64::MAX);
+        assert!(matches!(decode(&text), Err(StoreError::Invalid(msg)) if msg.contains("next_id")));
+
+        let at = object(vec![
+            ("at_secs", u64::MAX.into()),
+            ("at_nanos", 0u64.into()),
+        ]);
+        assert!(matches!(
+            time_field(&at, "at_secs", "at_nanos"),
+            Err(StoreError::Invalid(msg)) if msg.contains("at_secs")
+        ));
+    }
+
+    #[test]
+    fn keeps_ids_above_2_pow_53() {
+        let id = (1u64 << 53) + 1;
+        let mut post = encode_post(&Post::new());
+        if let Value::Object(fields) = &mut post {
+            fields.insert(0, (String::from("id"), id.into()));
+        }
+        let store = object(vec![
+            ("version", FORMAT_VERSION.into()),
+            ("next_id", (id + 1).into()),
+            ("posts", vec![post].into()),
+        ]);
+        let (posts, next_id) = decode_store(&json::parse(&store.to_string()).unwrap()).unwrap();
+        assert_eq!(posts.keys().collect::<Vec<_>>(), [&id]);
+        assert_eq!(next_id, id + 1);
+    }
+}
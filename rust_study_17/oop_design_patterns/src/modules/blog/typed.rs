@@ -0,0 +1,396 @@
+// * 把状态编码为类型(type-state)
+// + 和 Post 使用 Box<dyn State> 不同, 这里每个阶段都是一个单独的类型:
+//   - DraftPost: 可以添加文本, 可以请求审核
+//   - PendingReviewPost: 可以通过或者退回
+//   - PublishedPost: 只有它有 content 方法
+// + 转换方法会获取 self 的所有权并返回新的类型, 旧的值不能再被使用
+// + 不合法的转换(比如草案直接 approve、读取未发布博文的内容)在编译期就会报错, 而不是像 Post 那样在运行时才返回 PostError
+// ? 和 Post 之间可以互相转换, 已有的代码可以逐步迁移:
+//   - Post::from(draft) 总是成功, 作者、历史记录、版本和时钟都原样保留
+//   - DraftPost::try_from(post) 在状态不对时失败, 并把原来的 Post 还回去
+//   - 定时发布的博文没有对应的类型, 转换总是失败
+// ? 类型化的博文上的操作会记录新的版本, 但是不记录历史(退回时不知道是谁退回的), 转换回 Post 之后继续记录
+
+use super::clock::Clock;
+use super::history::Event;
+use super::markdown;
+use super::permission::{self, Operation};
+use super::review::{self, ApprovalPolicy, Reviewer};
+use super::{Draft, PendingReview, Post, PostError, Published, State, Status};
+
+// * Post 中除了状态之外的部分, 每个阶段都一样, 转换时原样交接
+struct Parts {
+    content: String,
+    policy: ApprovalPolicy,
+    author: String,
+    history: Vec<Event>,
+    revisions: Vec<String>,
+    clock: Box<dyn Clock>,
+}
+
+pub struct DraftPost {
+    parts: Parts,
+    rejection_reason: Option<String>,
+    approvals: Vec<Reviewer>,
+}
+
+pub struct PendingReviewPost {
+    parts: Parts,
+    approvals: Vec<Reviewer>,
+}
+
+pub struct PublishedPost {
+    parts: Parts,
+    approvals: Vec<Reviewer>,
+}
+
+// * 一次通过之后, 博文可能已经发布, 也可能还需要更多的通过
+pub enum Approval {
+    Published(PublishedPost),
+    Pending(PendingReviewPost),
+}
+
+impl Default for DraftPost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DraftPost {
+    pub fn new() -> DraftPost {
+        DraftPost::with_policy(ApprovalPolicy::default())
+    }
+
+    // * 和 Post::with_policy 的初始值一样: 作者是 ANONYMOUS, 使用系统时钟
+    pub fn with_policy(policy: ApprovalPolicy) -> DraftPost {
+        let (_, parts) = Post::with_policy(policy).into_parts();
+        DraftPost {
+            parts,
+            rejection_reason: None,
+            approvals: Vec::new(),
+        }
+    }
+
+    // * 和 Post::written_by 一样, 审核人不能通过自己写的博文
+    pub fn written_by(mut self, author: &str) -> DraftPost {
+        self.parts.author = author.to_string();
+        self
+    }
+
+    pub fn author(&self) -> &str {
+        &self.parts.author
+    }
+
+    pub fn add_text(&mut self, text: &str) {
+        self.parts.content.push_str(text);
+        self.parts.revisions.push(self.parts.content.clone());
+    }
+
+    pub fn rejection_reason(&self) -> Option<&str> {
+        self.rejection_reason.as_deref()
+    }
+
+    pub fn request_review(self) -> PendingReviewPost {
+        PendingReviewPost {
+            parts: self.parts,
+            approvals: self.approvals,
+        }
+    }
+}
+
+impl PendingReviewPost {
+    // * 和 Post::approve 一样先检查角色: 只有编辑(Editor 或者 Admin)能通过, 并且不能通过自己的博文
+    // + 状态已经由类型保证了, 所以这里只会返回 PostError::Forbidden
+    pub fn approve(mut self, reviewer: &Reviewer) -> Result<Approval, PostError> {
+        permission::authorize(reviewer, &self.parts.author, Operation::Approve)?;
+        review::record(&mut self.approvals, reviewer);
+        if self.parts.policy.is_satisfied(&self.approvals) {
+            Ok(Approval::Published(PublishedPost {
+                parts: self.parts,
+                approvals: self.approvals,
+            }))
+        } else {
+            Ok(Approval::Pending(self))
+        }
+    }
+
+    // * 和 Post::reject 一样, 原因为空(或者只有空白)时返回 PostError::EmptyReason
+    pub fn reject(self, reason: &str) -> Result<DraftPost, PostError> {
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(PostError::EmptyReason);
+        }
+        let approvals = if self.parts.policy.resets_on_rejection() {
+            Vec::new()
+        } else {
+            self.approvals
+        };
+        Ok(DraftPost {
+            parts: self.parts,
+            rejection_reason: Some(reason.to_string()),
+            approvals,
+        })
+    }
+
+    pub fn approvals(&self) -> &[Reviewer] {
+        &self.approvals
+    }
+}
+
+impl PublishedPost {
+    pub fn content(&self) -> &str {
+        &self.parts.content
+    }
+
+    pub fn render_html(&self) -> String {
+        markdown::to_html(&self.parts.content)
+    }
+
+    pub fn excerpt(&self, max_chars: usize) -> String {
+        markdown::excerpt(&self.parts.content, max_chars)
+    }
+
+    pub fn approvals(&self) -> &[Reviewer] {
+        &self.approvals
+    }
+}
+
+impl From<DraftPost> for Post {
+    fn from(post: DraftPost) -> Post {
+        let state = Draft {
+            rejection_reason: post.rejection_reason,
+            approvals: post.approvals,
+        };
+        Post::from_parts(Box::new(state), post.parts)
+    }
+}
+
+impl From<PendingReviewPost> for Post {
+    fn from(post: PendingReviewPost) -> Post {
+        let state = PendingReview {
+            approvals: post.approvals,
+            publish_at: None,
+        };
+        Post::from_parts(Box::new(state), post.parts)
+    }
+}
+
+impl From<PublishedPost> for Post {
+    fn from(post: PublishedPost) -> Post {
+        let state = Published {
+            approvals: post.approvals,
+        };
+        Post::from_parts(Box::new(state), post.parts)
+    }
+}
+
+impl TryFrom<Post> for DraftPost {
+    type Error = Post;
+
+    fn try_from(post: Post) -> Result<DraftPost, Post> {
+        if post.status() != Status::Draft {
+            return Err(post);
+        }
+        let (state, parts) = post.into_parts();
+        Ok(DraftPost {
+            parts,
+            rejection_reason: state.rejection_reason().map(str::to_string),
+            approvals: state.approvals().to_vec(),
+        })
+    }
+}
+
+impl TryFrom<Post> for PendingReviewPost {
+    type Error = Post;
+
+    // ? 已经记下发布时间的博文通过之后会进入 Scheduled, PendingReviewPost 表示不了, 所以不能转换
+    fn try_from(post: Post) -> Result<PendingReviewPost, Post> {
+        if post.status() != Status::PendingReview || post.publish_at().is_some() {
+            return Err(post);
+        }
+        let (state, parts) = post.into_parts();
+        Ok(PendingReviewPost {
+            parts,
+            approvals: state.approvals().to_vec(),
+        })
+    }
+}
+
+impl TryFrom<Post> for PublishedPost {
+    type Error = Post;
+
+    // ? 到了公开时间的定时发布博文也算 Published, 但是转换之后会丢掉发布时间, 所以不能转换
+    fn try_from(post: Post) -> Result<PublishedPost, Post> {
+        if post.status() != Status::Published || post.publish_at().is_some() {
+            return Err(post);
+        }
+        let (state, parts) = post.into_parts();
+        Ok(PublishedPost {
+            parts,
+            approvals: state.approvals().to_vec(),
+        })
+    }
+}
+
+impl Post {
+    fn from_parts(state: Box<dyn State>, parts: Parts) -> Post {
+        Post {
+            state: Some(state),
+            content: parts.content,
+            policy: parts.policy,
+            author: parts.author,
+            history: parts.history,
+            revisions: parts.revisions,
+            clock: parts.clock,
+        }
+    }
+
+    fn into_parts(self) -> (Box<dyn State>, Parts) {
+        let parts = Parts {
+            content: self.content,
+            policy: self.policy,
+            author: self.author,
+            history: self.history,
+            revisions: self.revisions,
+            clock: self.clock,
+        };
+        (self.state.unwrap(), parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::blog::{Actor, FakeClock, Role, ANONYMOUS};
+
+    fn publish(post: PendingReviewPost, reviewer: &Reviewer) -> PublishedPost {
+        match post.approve(reviewer) {
+            Ok(Approval::Published(post)) => post,
+            _ => panic!("expected the post to be published"),
+        }
+    }
+
+    #[test]
+    fn workflow_in_types() {
+        let mut post = DraftPost::new();
+        post.add_text("I ate a salad for lunch today");
+        let post = post.request_review();
+        let mut post = post.reject(" too short ").ok().unwrap();
+        assert_eq!(post.rejection_reason(), Some("too short"));
+        post.add_text(" (a caesar salad)");
+        let post = publish(post.request_review(), &Reviewer::editor("ed"));
+        assert_eq!(
+            post.content(),
+            "I ate a salad for lunch today (a caesar salad)"
+        );
+    }
+
+    #[test]
+    fn reject_requires_a_reason() {
+        let post = DraftPost::new().request_review();
+        assert!(matches!(post.reject("  "), Err(PostError::EmptyReason)));
+    }
+
+    #[test]
+    fn policies_apply_to_typed_posts() {
+        let post = DraftPost::with_policy(ApprovalPolicy::new().required_approvals(2));
+        let post = match post.request_review().approve(&Reviewer::editor("ed")) {
+            Ok(Approval::Pending(post)) => post,
+            _ => panic!("one approval is not enough"),
+        };
+        // * 同一个人再次通过仍然不够
+        let post = match post.approve(&Reviewer::editor("ed")) {
+            Ok(Approval::Pending(post)) => post,
+            _ => panic!("duplicate approvals must be ignored"),
+        };
+        assert_eq!(post.approvals().len(), 1);
+        assert_eq!(publish(post, &Reviewer::admin("root")).approvals().len(), 2);
+    }
+
+    #[test]
+    fn only_editors_approve_typed_posts() {
+        let post = DraftPost::new().written_by("alice").request_review();
+        let Err(error) = post.approve(&Reviewer::peer("bob")) else {
+            panic!("a peer must not publish a post");
+        };
+        assert_eq!(
+            error,
+            PostError::Forbidden {
+                actor: "bob".to_string(),
+                role: Role::Peer,
+                operation: Operation::Approve,
+            }
+        );
+
+        // * 作者即使是编辑, 也不能通过自己的博文
+        let post = DraftPost::new().written_by("ed").request_review();
+        assert!(post.approve(&Reviewer::editor("ed")).is_err());
+    }
+
+    #[test]
+    fn converts_between_representations() {
+        let mut draft = DraftPost::new().written_by("alice");
+        draft.add_text("hello");
+        let mut post = Post::from(draft);
+        assert_eq!(post.status(), Status::Draft);
+        assert_eq!(post.author(), "alice");
+        post.request_review(&Actor::author("alice")).unwrap();
+
+        // * 状态不对时转换失败, 原来的 Post 被还回来
+        let post = DraftPost::try_from(post).err().unwrap();
+        let pending = PendingReviewPost::try_from(post).ok().unwrap();
+        let published = publish(pending, &Reviewer::editor("ed"));
+
+        let post = Post::from(published);
+        assert_eq!(post.status(), Status::Published);
+        assert_eq!(post.content(), "hello");
+        let published = PublishedPost::try_from(post).ok().unwrap();
+        assert_eq!(published.approvals(), &[Reviewer::editor("ed")]);
+    }
+
+    #[test]
+    fn round_trip_keeps_author_history_and_revisions() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut post = Post::new()
+            .written_by("alice")
+            .with_clock(FakeClock::new(start));
+        post.add_text(&Actor::author("alice"), "hello").unwrap();
+
+        let mut draft = DraftPost::try_from(post).ok().unwrap();
+        assert_eq!(draft.author(), "alice");
+        draft.add_text(", world");
+        let mut post = Post::from(draft);
+        assert_eq!(post.author(), "alice");
+        assert_eq!(post.history().len(), 1);
+        assert_eq!(post.history()[0].at, start);
+        assert_eq!(post.revisions(), &["", "hello", "hello, world"]);
+
+        // * 作者仍然可以编辑自己的草案, 冒充 ANONYMOUS 的人不行
+        assert!(post.add_text(&Actor::author(ANONYMOUS), "!").is_err());
+        post.undo(&Actor::author("alice")).unwrap();
+        assert_eq!(post.revisions().last().unwrap(), "hello");
+    }
+
+    #[test]
+    fn scheduled_posts_do_not_convert() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let clock = FakeClock::new(start);
+        let policy = ApprovalPolicy::new().required_approvals(2);
+        let mut post = Post::with_policy(policy)
+            .written_by("alice")
+            .with_clock(clock.clone());
+        post.request_review(&Actor::author("alice")).unwrap();
+        post.approve_scheduled(&Reviewer::editor("ed"), start + Duration::from_secs(60))
+            .unwrap();
+        let mut post = PendingReviewPost::try_from(post).err().unwrap();
+
+        post.approve(&Reviewer::admin("root")).unwrap();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(post.status(), Status::Published);
+        assert!(PublishedPost::try_from(post).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+// * 把 Buffer 显示出来的后端
+// + Screen 只负责把组件画到 Buffer 上, 显示到哪里由 Backend 决定
+// + TerminalBackend 使用 ANSI 转义序列输出到终端(或者任何实现了 Write 的地方)
+// + TestBackend 不输出到任何地方, 只记下画出来的 Buffer, 用来在测试中检查, 见 snapshot.rs
+// + RawMode 让终端进入 raw 模式, 这样才能逐个读到按键和鼠标事件, 见 event.rs
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::canvas::{Buffer, Style};
+
+pub trait Backend {
+    // * 屏幕大小(宽, 高), Screen 按这个大小创建 Buffer
+    fn size(&self) -> (u16, u16);
+    fn draw(&mut self, buffer: &Buffer) -> io::Result<()>;
+}
+
+// * 终端的默认大小, 拿不到真实大小时使用
+pub const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+pub struct TerminalBackend<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+}
+
+impl TerminalBackend<io::Stdout> {
+    // ? 只使用标准库, 没法通过 ioctl 查询终端大小, 所以读取 shell 设置的 COLUMNS 和 LINES
+    // + 没有设置时使用 DEFAULT_SIZE
+    pub fn stdout() -> TerminalBackend<io::Stdout> {
+        let read = |name: &str, default: u16| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let width = read("COLUMNS", DEFAULT_SIZE.0);
+        let height = read("LINES", DEFAULT_SIZE.1);
+        TerminalBackend::new(io::stdout(), width, height)
+    }
+}
+
+impl<W: Write> TerminalBackend<W> {
+    pub fn new(out: W, width: u16, height: u16) -> TerminalBackend<W> {
+        TerminalBackend { out, width, height }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Backend for TerminalBackend<W> {
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    // * 清屏之后逐行输出, 只在样式变化时才输出新的样式, 最后恢复默认样式
+    fn draw(&mut self, buffer: &Buffer) -> io::Result<()> {
+        let mut text = String::from("\x1b[2J");
+        for y in 0..buffer.height() {
+            // * 终端的行列从 1 开始
+            text.push_str(&format!("\x1b[{};1H", y + 1));
+            let mut current = Style::default();
+            for cell in buffer.row(y) {
+                if cell.style != current {
+                    text.push_str(&sgr(cell.style));
+                    current = cell.style;
+                }
+                text.push(cell.ch);
+            }
+            if current != Style::default() {
+                text.push_str("\x1b[0m");
+            }
+        }
+        text.push_str(&format!("\x1b[{};1H", buffer.height() + 1));
+        self.out.write_all(text.as_bytes())?;
+        self.out.flush()
+    }
+}
+
+// * 没有终端的后端: 记下最后一次绘制的 Buffer 和绘制的次数
+pub struct TestBackend {
+    buffer: Buffer,
+    frames: usize,
+}
+
+impl TestBackend {
+    pub fn new(width: u16, height: u16) -> TestBackend {
+        TestBackend {
+            buffer: Buffer::new(width, height),
+            frames: 0,
+        }
+    }
+
+    // * 最后一次绘制的内容, 还没有绘制过时是空白的
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    // * 模拟终端改变大小, 之前画的内容被清掉
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.buffer = Buffer::new(width, height);
+    }
+}
+
+impl Backend for TestBackend {
+    fn size(&self) -> (u16, u16) {
+        (self.buffer.width(), self.buffer.height())
+    }
+
+    fn draw(&mut self, buffer: &Buffer) -> io::Result<()> {
+        self.buffer = buffer.clone();
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+// * 进入 raw 模式: 按键不回显, 也不用等到回车才能读到; 同时打开鼠标上报, 隐藏光标
+// + 离开作用域(drop)时恢复终端原来的设置
+// ? 只使用标准库, 没法直接调用 termios, 所以通过 stty 命令修改终端设置
+pub struct RawMode {
+    // * stty -g 输出的原来的设置
+    saved: String,
+}
+
+// * 鼠标上报(1000)使用 SGR 格式(1006), 以及隐藏光标(25)
+const ENTER_RAW: &str = "\x1b[?1000h\x1b[?1006h\x1b[?25l";
+const LEAVE_RAW: &str = "\x1b[?1000l\x1b[?1006l\x1b[?25h";
+
+impl RawMode {
+    // * 标准输入不是终端时(例如被重定向到文件)返回错误
+    pub fn enable() -> io::Result<RawMode> {
+        let saved = stty(&["-g"])?.trim().to_string();
+        stty(&["raw", "-echo"])?;
+        let mut out = io::stdout();
+        out.write_all(ENTER_RAW.as_bytes())?;
+        out.flush()?;
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = out.write_all(LEAVE_RAW.as_bytes());
+        let _ = out.flush();
+        let _ = stty(&[self.saved.as_str()]);
+    }
+}
+
+// * stty 作用于它的标准输入, 所以要让它继承当前进程的标准输入
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(io::Error::other(format!("stty 失败: {}", message)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// * Select Graphic Rendition: 先重置, 再设置需要的属性, 例如 "\x1b[0;1;31m" 是红色加粗
+pub fn sgr(style: Style) -> String {
+    let mut codes = vec![String::from("0")];
+    if style.bold {
+        codes.push(String::from("1"));
+    }
+    if style.reverse {
+        codes.push(String::from("7"));
+    }
+    if let Some(fg) = style.fg {
+        codes.push((30 + fg.ansi_index()).to_string());
+    }
+    if let Some(bg) = style.bg {
+        codes.push((40 + bg.ansi_index()).to_string());
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Color;
+
+    #[test]
+    fn builds_sgr_sequences() {
+        assert_eq!(sgr(Style::new()), "\x1b[0m");
+        assert_eq!(
+            sgr(Style::new().fg(Color::Red).bg(Color::White).bold()),
+            "\x1b[0;1;31;47m"
+        );
+        assert_eq!(sgr(Style::new().reverse()), "\x1b[0;7m");
+    }
+
+    #[test]
+    fn writes_ansi_frames() {
+        let mut buffer = Buffer::new(3, 2);
+        buffer.set(0, 0, 'a', Style::new());
+        buffer.set(1, 0, 'b', Style::new().bold());
+        buffer.set(2, 1, 'c', Style::new().fg(Color::Green));
+
+        let mut backend = TerminalBackend::new(Vec::new(), 3, 2);
+        assert_eq!(backend.size(), (3, 2));
+        backend.draw(&buffer).unwrap();
+        let out = String::from_utf8(backend.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            "\x1b[2J\x1b[1;1Ha\x1b[0;1mb\x1b[0m \x1b[2;1H  \x1b[0;32mc\x1b[0m\x1b[3;1H"
+        );
+    }
+
+    #[test]
+    fn test_backend_keeps_the_last_frame() {
+        let mut backend = TestBackend::new(4, 2);
+        assert_eq!(backend.buffer().lines(), vec!["", ""]);
+        let mut buffer = Buffer::new(4, 2);
+        buffer.set(1, 1, 'x', Style::new());
+        backend.draw(&buffer).unwrap();
+        backend.draw(&buffer).unwrap();
+        assert_eq!(backend.frames(), 2);
+        assert_eq!(backend.buffer(), &buffer);
+        backend.resize(6, 1);
+        assert_eq!(backend.size(), (6, 1));
+    }
+}
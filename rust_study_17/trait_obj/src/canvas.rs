@@ -0,0 +1,399 @@
+// * 字符单元格画布
+// + Buffer 是整个屏幕: 宽 x 高 个单元格, 每个单元格是一个字符加上样式
+// + Canvas 是 Buffer 上的一块矩形区域, 组件的 draw 方法拿到的就是 Canvas:
+//   - 坐标从区域的左上角 (0, 0) 开始
+//   - 超出区域的内容会被裁掉, 组件不会画到别的组件上
+//   - Canvas::scrolled 得到一块比区域更大的画布, 只有其中可见的几行会写进 Buffer, 用来实现滚动
+// + Buffer 和具体的终端无关, 怎么把它显示出来由 Backend 决定, 见 backend.rs
+// ? 这里假设每个字符只占一个单元格, 中文等宽字符会显示错位
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    // * ANSI 颜色编号, 前景色是 30 + 编号, 背景色是 40 + 编号
+    pub fn ansi_index(self) -> u8 {
+        match self {
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+        }
+    }
+}
+
+// * 例如: Style::new().fg(Color::Red).bold()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    // * 前景色和背景色互换, 用来表示选中
+    pub reverse: bool,
+}
+
+impl Style {
+    pub fn new() -> Style {
+        Style::default()
+    }
+
+    pub fn fg(mut self, color: Color) -> Style {
+        self.fg = Some(color);
+        self
+    }
+
+    pub fn bg(mut self, color: Color) -> Style {
+        self.bg = Some(color);
+        self
+    }
+
+    pub fn bold(mut self) -> Style {
+        self.bold = true;
+        self
+    }
+
+    pub fn reverse(mut self) -> Style {
+        self.reverse = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            ch: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    // * 右边界和下边界, 不包含在矩形内
+    pub fn right(&self) -> u16 {
+        self.x.saturating_add(self.width)
+    }
+
+    pub fn bottom(&self) -> u16 {
+        self.y.saturating_add(self.height)
+    }
+
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    // * 四周各缩进 margin 个单元格, 不够缩进时得到一个空的矩形
+    pub fn inner(&self, margin: u16) -> Rect {
+        let width = self.width.saturating_sub(margin.saturating_mul(2));
+        let height = self.height.saturating_sub(margin.saturating_mul(2));
+        if width == 0 || height == 0 {
+            return Rect::new(self.x, self.y, 0, 0);
+        }
+        Rect::new(self.x + margin, self.y + margin, width, height)
+    }
+
+    // * 两个矩形重叠的部分
+    pub fn intersection(&self, other: Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x || bottom <= y {
+            return Rect::new(x, y, 0, 0);
+        }
+        Rect::new(x, y, right - x, bottom - y)
+    }
+}
+
+// * 整个屏幕的单元格, 按行存放
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Buffer {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Buffer {
+    pub fn new(width: u16, height: u16) -> Buffer {
+        Buffer {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn area(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells
+            .get(y as usize * self.width as usize + x as usize)
+    }
+
+    // * 超出范围时什么都不做
+    pub fn set(&mut self, x: u16, y: u16, ch: char, style: Style) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        self.cells[y as usize * self.width as usize + x as usize] = Cell { ch, style };
+    }
+
+    pub fn row(&self, y: u16) -> &[Cell] {
+        let start = y as usize * self.width as usize;
+        &self.cells[start..start + self.width as usize]
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::default());
+    }
+
+    // * 在 area 上画图, area 会被裁剪到屏幕范围内
+    pub fn canvas(&mut self, area: Rect) -> Canvas<'_> {
+        let area = area.intersection(self.area());
+        Canvas {
+            buffer: self,
+            area,
+            clip: area,
+            scroll: 0,
+        }
+    }
+
+    // * 每一行的文字, 去掉了行尾的空格, 不包含样式
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                let line: String = self.row(y).iter().map(|c| c.ch).collect();
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lines().join("\n"))
+    }
+}
+
+// * Buffer 上的一块区域, 坐标相对于区域的左上角
+pub struct Canvas<'a> {
+    buffer: &'a mut Buffer,
+    area: Rect,
+    // * area 中真正能画的部分, 只有滚动时才比 area 小
+    clip: Rect,
+    // * area 和 clip 的纵坐标减去 scroll 才是 Buffer 中的纵坐标, 见 scrolled
+    scroll: u16,
+}
+
+impl Canvas<'_> {
+    pub fn width(&self) -> u16 {
+        self.area.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.area.height
+    }
+
+    // * 区域在整个屏幕中的位置; 滚动的画布中, 纵坐标还要减去已经滚过去的行数
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    pub fn set(&mut self, x: u16, y: u16, ch: char, style: Style) {
+        if x >= self.area.width || y >= self.area.height {
+            return;
+        }
+        let (x, y) = (self.area.x.saturating_add(x), self.area.y.saturating_add(y));
+        if self.clip.contains(x, y) {
+            self.buffer.set(x, y - self.scroll, ch, style);
+        }
+    }
+
+    // * 从 (x, y) 开始写一行文字, 超出区域的部分被裁掉, 返回实际写入的字符数
+    pub fn print(&mut self, x: u16, y: u16, text: &str, style: Style) -> u16 {
+        let mut written = 0;
+        for (i, ch) in text.chars().enumerate() {
+            let Some(col) = u16::try_from(i).ok().and_then(|i| x.checked_add(i)) else {
+                break;
+            };
+            if col >= self.area.width || y >= self.area.height {
+                break;
+            }
+            self.set(col, y, ch, style);
+            written += 1;
+        }
+        written
+    }
+
+    // * 用 ch 填满整个区域, 只需要填可见的部分
+    pub fn fill(&mut self, ch: char, style: Style) {
+        for y in self.clip.y..self.clip.bottom() {
+            for x in self.clip.x..self.clip.right() {
+                self.buffer.set(x, y - self.scroll, ch, style);
+            }
+        }
+    }
+
+    // * 沿着区域的边缘画一个方框, 区域太小(宽或者高小于 2)时什么都不画
+    pub fn draw_border(&mut self, style: Style) {
+        let (w, h) = (self.area.width, self.area.height);
+        if w < 2 || h < 2 {
+            return;
+        }
+        for x in 1..w - 1 {
+            self.set(x, 0, '─', style);
+            self.set(x, h - 1, '─', style);
+        }
+        for y in 1..h - 1 {
+            self.set(0, y, '│', style);
+            self.set(w - 1, y, '│', style);
+        }
+        self.set(0, 0, '┌', style);
+        self.set(w - 1, 0, '┐', style);
+        self.set(0, h - 1, '└', style);
+        self.set(w - 1, h - 1, '┘', style);
+    }
+
+    // * 区域中的一块子区域, rect 的坐标相对于当前区域, 超出的部分被裁掉
+    pub fn sub(&mut self, rect: Rect) -> Canvas<'_> {
+        let local = rect.intersection(Rect::new(0, 0, self.area.width, self.area.height));
+        let area = Rect::new(
+            self.area.x.saturating_add(local.x),
+            self.area.y.saturating_add(local.y),
+            local.width,
+            local.height,
+        );
+        Canvas {
+            buffer: self.buffer,
+            area,
+            clip: self.clip.intersection(area),
+            scroll: self.scroll,
+        }
+    }
+
+    // * 一块 width x height 的画布, 其中从第 offset 行开始的部分显示在当前区域中, 其余的部分被裁掉
+    // + 内容仍然按完整的大小来画, 但是不需要为整个内容分配一个 Buffer
+    pub fn scrolled(&mut self, width: u16, height: u16, offset: u16) -> Canvas<'_> {
+        let area = Rect::new(self.area.x, self.area.y, width, height);
+        let visible = Rect::new(
+            self.clip.x,
+            self.clip.y.saturating_add(offset),
+            self.clip.width,
+            self.clip.height,
+        );
+        Canvas {
+            buffer: self.buffer,
+            area,
+            clip: visible.intersection(area),
+            scroll: self.scroll.saturating_add(offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_geometry() {
+        let rect = Rect::new(2, 3, 10, 4);
+        assert_eq!((rect.right(), rect.bottom()), (12, 7));
+        assert!(rect.contains(2, 3) && rect.contains(11, 6));
+        assert!(!rect.contains(12, 3) && !rect.contains(2, 7));
+        assert_eq!(rect.inner(1), Rect::new(3, 4, 8, 2));
+        assert!(rect.inner(2).is_empty());
+        assert_eq!(
+            rect.intersection(Rect::new(8, 0, 10, 5)),
+            Rect::new(8, 3, 4, 2)
+        );
+        assert!(rect.intersection(Rect::new(20, 20, 1, 1)).is_empty());
+    }
+
+    #[test]
+    fn canvas_clips_to_its_area() {
+        let mut buffer = Buffer::new(8, 3);
+        let mut canvas = buffer.canvas(Rect::new(2, 1, 4, 2));
+        assert_eq!(canvas.print(0, 0, "hello", Style::new()), 4);
+        assert_eq!(canvas.print(3, 1, "xy", Style::new()), 1);
+        assert_eq!(canvas.print(0, 2, "gone", Style::new()), 0);
+        assert_eq!(buffer.lines(), vec!["", "  hell", "     x"]);
+    }
+
+    #[test]
+    fn draws_borders_and_sub_areas() {
+        let mut buffer = Buffer::new(6, 4);
+        let mut canvas = buffer.canvas(buffer.area());
+        canvas.draw_border(Style::new());
+        canvas
+            .sub(Rect::new(1, 1, 4, 10))
+            .print(0, 0, "abcdefgh", Style::new().bold());
+        assert_eq!(buffer.to_string(), "┌────┐\n│abcd│\n│    │\n└────┘");
+        assert!(buffer.get(1, 1).unwrap().style.bold);
+        assert_eq!(buffer.get(6, 0), None);
+    }
+
+    #[test]
+    fn scrolled_canvases_only_write_visible_rows() {
+        let mut buffer = Buffer::new(6, 4);
+        let mut canvas = buffer.canvas(Rect::new(1, 1, 4, 2));
+        let mut content = canvas.scrolled(5, 100, 3);
+        assert_eq!((content.width(), content.height()), (5, 100));
+        for y in 0..100 {
+            content.print(0, y, &format!("r{}", y), Style::new());
+        }
+        content.sub(Rect::new(2, 4, 3, 50)).fill('#', Style::new());
+        assert_eq!(buffer.lines(), vec!["", " r3", " r4##", ""]);
+    }
+}
@@ -0,0 +1,270 @@
+// * 输入事件: 键盘和鼠标
+// + 终端在 raw 模式下把每次按键原样发过来:
+//   - 普通字符是它的 UTF-8 字节, Ctrl + 字母是 1 ~ 26
+//   - 方向键等特殊按键是以 ESC 开头的转义序列, 例如 上 是 "\x1b[A", Shift + Tab 是 "\x1b[Z"
+//   - 打开 SGR 鼠标模式后, 鼠标事件是 "\x1b[<按键;列;行M", 松开时最后一个字符是 m, 行列从 1 开始
+// + Parser 把读到的字节解析成 Event, 一次读到的字节可能只有半个转义序列, 剩下的部分留到下次再解析
+// ? 单独按下 ESC 和转义序列的开头是同一个字节, 这里把一次读取中最后一个单独的 ESC 当作 ESC 键
+use std::str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    // * Ctrl + 字母, 例如 Ctrl + C 是 Ctrl('c')
+    Ctrl(char),
+    Enter,
+    Tab,
+    // * Shift + Tab
+    BackTab,
+    Backspace,
+    Delete,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseKind {
+    Press(MouseButton),
+    Release,
+    ScrollUp,
+    ScrollDown,
+}
+
+// * 坐标从 0 开始, 交给组件时会换算成相对于组件区域左上角的坐标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse {
+    pub kind: MouseKind,
+    pub x: u16,
+    pub y: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Key(Key),
+    Mouse(Mouse),
+}
+
+#[derive(Debug, Default)]
+pub struct Parser {
+    // * 上次没有解析完的字节
+    pending: Vec<u8>,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+
+    // * 解析新读到的字节, 返回其中完整的事件
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.pending.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        let mut start = 0;
+        while let Some((event, len)) = parse(&self.pending[start..]) {
+            events.extend(event);
+            start += len;
+        }
+        self.pending.drain(..start);
+        events
+    }
+}
+
+// * 解析 bytes 开头的一个事件, 返回 (事件, 用掉的字节数)
+// + 不认识的字节和序列被跳过, 这时事件是 None
+// + 字节不完整时返回 None, 等待更多的输入
+fn parse(bytes: &[u8]) -> Option<(Option<Event>, usize)> {
+    let key = |key| Some(Event::Key(key));
+    let first = *bytes.first()?;
+    let parsed = match first {
+        0x1b => return parse_escape(bytes),
+        b'\r' | b'\n' => (key(Key::Enter), 1),
+        b'\t' => (key(Key::Tab), 1),
+        0x7f | 0x08 => (key(Key::Backspace), 1),
+        1..=26 => (key(Key::Ctrl((b'a' + first - 1) as char)), 1),
+        0..=0x1f => (None, 1),
+        _ => {
+            // * UTF-8 首字节决定了这个字符占几个字节
+            let len = match first {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            if bytes.len() < len {
+                return None;
+            }
+            match str::from_utf8(&bytes[..len]) {
+                Ok(s) => (s.chars().next().map(|c| Event::Key(Key::Char(c))), len),
+                Err(_) => (None, 1),
+            }
+        }
+    };
+    Some(parsed)
+}
+
+// * bytes 以 ESC 开头
+fn parse_escape(bytes: &[u8]) -> Option<(Option<Event>, usize)> {
+    let key = |key| Some(Event::Key(key));
+    match bytes.get(1) {
+        None => Some((key(Key::Esc), 1)),
+        // * SS3 序列, 有的终端用它表示方向键, 例如 "\x1bOA"
+        Some(b'O') => {
+            let event = match bytes.get(2)? {
+                b'A' => key(Key::Up),
+                b'B' => key(Key::Down),
+                b'C' => key(Key::Right),
+                b'D' => key(Key::Left),
+                b'H' => key(Key::Home),
+                b'F' => key(Key::End),
+                _ => None,
+            };
+            Some((event, 3))
+        }
+        // * CSI 序列: "\x1b[" + 参数 + 一个 0x40 ~ 0x7e 之间的结束字节
+        Some(b'[') => {
+            let end = 2 + bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b))?;
+            let params = str::from_utf8(&bytes[2..end]).unwrap_or("");
+            let event = match (bytes[end], params) {
+                (b'M' | b'm', params) if params.starts_with('<') => {
+                    parse_mouse(&params[1..], bytes[end] == b'm')
+                }
+                (b'A', _) => key(Key::Up),
+                (b'B', _) => key(Key::Down),
+                (b'C', _) => key(Key::Right),
+                (b'D', _) => key(Key::Left),
+                (b'H', _) | (b'~', "1" | "7") => key(Key::Home),
+                (b'F', _) | (b'~', "4" | "8") => key(Key::End),
+                (b'Z', _) => key(Key::BackTab),
+                (b'~', "3") => key(Key::Delete),
+                _ => None,
+            };
+            Some((event, end + 1))
+        }
+        // * Alt + 按键也是以 ESC 开头, 这里不区分, 当作 ESC 加上后面的按键
+        Some(_) => Some((key(Key::Esc), 1)),
+    }
+}
+
+// * params 形如 "0;10;5": 按键编号;列;行
+// + 编号的低两位是按键, 4、8、16 是 Shift、Alt、Ctrl(忽略), 加上 32 表示拖动(忽略)
+// + 加上 64 是滚轮: 低两位 0 和 1 是上下滚动, 2 和 3 是左右滚动(忽略); 128 以上是额外的按键(忽略)
+// ? 必须先检查滚轮, 否则按着 Shift 滚动(68、69)会被当成按下左键或中键
+fn parse_mouse(params: &str, release: bool) -> Option<Event> {
+    let mut numbers = params.split(';').map(|n| n.parse::<u16>().ok());
+    let (code, x, y) = (numbers.next()??, numbers.next()??, numbers.next()??);
+    let code = code & !(4 | 8 | 16);
+    let kind = match code {
+        _ if code & 128 != 0 => return None,
+        _ if code & 64 != 0 => match code & 3 {
+            0 => MouseKind::ScrollUp,
+            1 => MouseKind::ScrollDown,
+            _ => return None,
+        },
+        _ if code & 32 != 0 => return None,
+        _ if release => MouseKind::Release,
+        _ => MouseKind::Press(match code & 3 {
+            0 => MouseButton::Left,
+            1 => MouseButton::Middle,
+            2 => MouseButton::Right,
+            _ => return None,
+        }),
+    };
+    Some(Event::Mouse(Mouse {
+        kind,
+        x: x.saturating_sub(1),
+        y: y.saturating_sub(1),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(bytes: &[u8]) -> Vec<Event> {
+        Parser::new().feed(bytes)
+    }
+
+    #[test]
+    fn parses_keys_and_escape_sequences() {
+        assert_eq!(
+            keys("a中\r\t\x7f\x03".as_bytes()),
+            vec![
+                Event::Key(Key::Char('a')),
+                Event::Key(Key::Char('中')),
+                Event::Key(Key::Enter),
+                Event::Key(Key::Tab),
+                Event::Key(Key::Backspace),
+                Event::Key(Key::Ctrl('c')),
+            ]
+        );
+        assert_eq!(
+            keys(b"\x1b[A\x1bOB\x1b[Z\x1b[3~\x1b[1;5C\x1b[99x\x1b"),
+            vec![
+                Event::Key(Key::Up),
+                Event::Key(Key::Down),
+                Event::Key(Key::BackTab),
+                Event::Key(Key::Delete),
+                Event::Key(Key::Right),
+                Event::Key(Key::Esc),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_sgr_mouse_events() {
+        let mouse = |kind, x, y| Event::Mouse(Mouse { kind, x, y });
+        assert_eq!(
+            keys(b"\x1b[<0;10;5M\x1b[<0;10;5m\x1b[<2;1;1M\x1b[<65;3;4M\x1b[<32;1;1M"),
+            vec![
+                mouse(MouseKind::Press(MouseButton::Left), 9, 4),
+                mouse(MouseKind::Release, 9, 4),
+                mouse(MouseKind::Press(MouseButton::Right), 0, 0),
+                mouse(MouseKind::ScrollDown, 2, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn wheel_with_modifiers_is_not_a_click() {
+        let mouse = |kind| Event::Mouse(Mouse { kind, x: 0, y: 0 });
+        let event = |code: u16| keys(format!("\x1b[<{};1;1M", code).as_bytes());
+        // * Shift、Alt、Ctrl 加滚轮
+        for code in [68, 72, 80, 92] {
+            assert_eq!(event(code), vec![mouse(MouseKind::ScrollUp)]);
+            assert_eq!(event(code + 1), vec![mouse(MouseKind::ScrollDown)]);
+        }
+        // * 左右滚动和额外的按键都忽略
+        for code in [66, 67, 70, 71, 128, 129] {
+            assert_eq!(event(code), vec![]);
+        }
+        // * Ctrl 加左键仍然是按下左键
+        assert_eq!(event(16), vec![mouse(MouseKind::Press(MouseButton::Left))]);
+    }
+
+    #[test]
+    fn keeps_incomplete_input_for_the_next_read() {
+        let mut parser = Parser::new();
+        assert_eq!(parser.feed(b"x\x1b[<0;1"), vec![Event::Key(Key::Char('x'))]);
+        assert_eq!(
+            parser.feed(b";2M\xe4\xb8"),
+            vec![Event::Mouse(Mouse {
+                kind: MouseKind::Press(MouseButton::Left),
+                x: 0,
+                y: 1,
+            })]
+        );
+        assert_eq!(parser.feed(b"\xad"), vec![Event::Key(Key::Char('中'))]);
+    }
+}
@@ -0,0 +1,326 @@
+// * 布局: 在绘制之前给每个组件分配一个矩形区域
+// + 布局是一棵树:
+//   - Item(i) 是叶子, 代表 Screen.components 中的第 i 个组件
+//   - Stack 把子节点沿一个方向(竖直或者水平)依次排列, 可以设置内边距、间距和交叉轴上的对齐方式
+// + 每个子节点在排列方向上的大小:
+//   - Fixed(n): 固定 n 个单元格
+//   - Auto: 使用组件自己希望的大小(Draw::size), Stack 则是它的子节点需要的大小
+//   - Flex(weight): 按权重分配剩下的空间
+// + 空间不够时, 排在后面的子节点先被压缩, 最后可能得到一个空的矩形
+// ? 计算布局只需要组件希望的大小, 不需要 Backend 和 Buffer, 所以可以单独测试
+use crate::canvas::Rect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    Fixed(u16),
+    Auto,
+    Flex(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Vertical,
+    Horizontal,
+}
+
+// * 子节点在交叉轴上(竖直排列时是水平方向)的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    // * 占满交叉轴
+    Stretch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+    Item(usize),
+    Stack(Stack),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+    direction: Direction,
+    children: Vec<(Size, Layout)>,
+    padding: u16,
+    spacing: u16,
+    align: Align,
+}
+
+impl Stack {
+    pub fn new(direction: Direction) -> Stack {
+        Stack {
+            direction,
+            children: Vec::new(),
+            padding: 0,
+            spacing: 0,
+            align: Align::Stretch,
+        }
+    }
+
+    // * 例如: Layout::vertical().item(Size::Fixed(3), 0).child(Size::Flex(1), Layout::horizontal()...)
+    pub fn child<L: Into<Layout>>(mut self, size: Size, layout: L) -> Stack {
+        self.children.push((size, layout.into()));
+        self
+    }
+
+    pub fn item(self, size: Size, index: usize) -> Stack {
+        self.child(size, Layout::Item(index))
+    }
+
+    pub fn padding(mut self, padding: u16) -> Stack {
+        self.padding = padding;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: u16) -> Stack {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Stack {
+        self.align = align;
+        self
+    }
+
+    // * (排列方向上的长度, 交叉轴上的长度)
+    fn split(&self, (width, height): (u16, u16)) -> (u16, u16) {
+        match self.direction {
+            Direction::Vertical => (height, width),
+            Direction::Horizontal => (width, height),
+        }
+    }
+
+    fn gaps(&self) -> u16 {
+        let gaps = self.children.len().saturating_sub(1) as u16;
+        gaps.saturating_mul(self.spacing)
+    }
+
+    // * 所有子节点都按希望的大小排列时需要的空间, Flex 的子节点不占空间
+    fn preferred<F: Fn(usize) -> (u16, u16)>(&self, sizes: &F) -> (u16, u16) {
+        let mut main: u16 = 0;
+        let mut cross: u16 = 0;
+        for (size, child) in &self.children {
+            let (child_main, child_cross) = self.split(child.preferred(sizes));
+            main = main.saturating_add(match size {
+                Size::Fixed(n) => *n,
+                Size::Auto => child_main,
+                Size::Flex(_) => 0,
+            });
+            cross = cross.max(child_cross);
+        }
+        let padding = self.padding.saturating_mul(2);
+        let main = main.saturating_add(self.gaps()).saturating_add(padding);
+        let cross = cross.saturating_add(padding);
+        match self.direction {
+            Direction::Vertical => (cross, main),
+            Direction::Horizontal => (main, cross),
+        }
+    }
+
+    fn compute<F: Fn(usize) -> (u16, u16)>(
+        &self,
+        area: Rect,
+        sizes: &F,
+        out: &mut Vec<(usize, Rect)>,
+    ) {
+        let inner = area.inner(self.padding);
+        let (main_len, cross_len) = self.split((inner.width, inner.height));
+
+        // * 先确定固定大小和 Auto 的子节点, 剩下的空间按权重分给 Flex
+        let preferred: Vec<(u16, u16)> = self
+            .children
+            .iter()
+            .map(|(_, child)| self.split(child.preferred(sizes)))
+            .collect();
+        let available = main_len.saturating_sub(self.gaps());
+        let used: u16 = self
+            .children
+            .iter()
+            .zip(&preferred)
+            .map(|((size, _), (main, _))| match size {
+                Size::Fixed(n) => *n,
+                Size::Auto => *main,
+                Size::Flex(_) => 0,
+            })
+            .fold(0, u16::saturating_add);
+        let total_weight: u32 = self
+            .children
+            .iter()
+            .map(|(size, _)| match size {
+                Size::Flex(weight) => *weight as u32,
+                _ => 0,
+            })
+            .sum();
+        let mut free = available.saturating_sub(used) as u32;
+        let mut weight_left = total_weight;
+
+        let mut offset: u16 = 0;
+        for ((size, child), (pref_main, pref_cross)) in self.children.iter().zip(preferred) {
+            let wanted = match size {
+                Size::Fixed(n) => *n,
+                Size::Auto => pref_main,
+                // * 按比例分配, 最后一个 Flex 拿走除不尽的部分
+                Size::Flex(weight) => {
+                    let weight = *weight as u32;
+                    let share = (free * weight).checked_div(weight_left).unwrap_or(0);
+                    free -= share;
+                    weight_left -= weight;
+                    share as u16
+                }
+            };
+            let len = wanted.min(main_len.saturating_sub(offset));
+            let cross = match self.align {
+                Align::Stretch => cross_len,
+                _ => pref_cross.min(cross_len),
+            };
+            let cross_offset = match self.align {
+                Align::Start | Align::Stretch => 0,
+                Align::Center => (cross_len - cross) / 2,
+                Align::End => cross_len - cross,
+            };
+            let rect = match self.direction {
+                Direction::Vertical => {
+                    Rect::new(inner.x + cross_offset, inner.y + offset, cross, len)
+                }
+                Direction::Horizontal => {
+                    Rect::new(inner.x + offset, inner.y + cross_offset, len, cross)
+                }
+            };
+            child.compute_into(rect, sizes, out);
+            offset = offset
+                .saturating_add(len)
+                .saturating_add(self.spacing)
+                .min(main_len);
+        }
+    }
+}
+
+impl Layout {
+    pub fn vertical() -> Stack {
+        Stack::new(Direction::Vertical)
+    }
+
+    pub fn horizontal() -> Stack {
+        Stack::new(Direction::Horizontal)
+    }
+
+    // * 这个节点希望的大小(宽, 高), sizes 返回第 i 个组件希望的大小
+    pub fn preferred<F: Fn(usize) -> (u16, u16)>(&self, sizes: &F) -> (u16, u16) {
+        match self {
+            Layout::Item(index) => sizes(*index),
+            Layout::Stack(stack) => stack.preferred(sizes),
+        }
+    }
+
+    // * 在 area 中排列, 返回每个组件(下标)分到的区域, 顺序和布局树中出现的顺序一致
+    // + 同一个下标出现多次时, 这个组件会被画多次
+    pub fn compute<F: Fn(usize) -> (u16, u16)>(&self, area: Rect, sizes: F) -> Vec<(usize, Rect)> {
+        let mut out = Vec::new();
+        self.compute_into(area, &sizes, &mut out);
+        out
+    }
+
+    fn compute_into<F: Fn(usize) -> (u16, u16)>(
+        &self,
+        area: Rect,
+        sizes: &F,
+        out: &mut Vec<(usize, Rect)>,
+    ) {
+        match self {
+            Layout::Item(index) => out.push((*index, area)),
+            Layout::Stack(stack) => stack.compute(area, sizes, out),
+        }
+    }
+}
+
+impl Stack {
+    pub fn into_layout(self) -> Layout {
+        Layout::Stack(self)
+    }
+}
+
+impl From<Stack> for Layout {
+    fn from(stack: Stack) -> Layout {
+        Layout::Stack(stack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // * 组件 i 希望的大小是 (10 + i, 1 + i)
+    fn sizes(index: usize) -> (u16, u16) {
+        (10 + index as u16, 1 + index as u16)
+    }
+
+    fn compute(layout: impl Into<Layout>, width: u16, height: u16) -> Vec<(usize, Rect)> {
+        layout.into().compute(Rect::new(0, 0, width, height), sizes)
+    }
+
+    #[test]
+    fn stacks_fixed_auto_and_flex_children() {
+        let layout = Layout::vertical()
+            .item(Size::Fixed(3), 0)
+            .item(Size::Auto, 2)
+            .item(Size::Flex(1), 1)
+            .item(Size::Flex(2), 3);
+        // * 剩下 20 - 3 - 3 = 14 行, 按 1:2 分成 4 和 10
+        assert_eq!(
+            compute(layout, 40, 20),
+            vec![
+                (0, Rect::new(0, 0, 40, 3)),
+                (2, Rect::new(0, 3, 40, 3)),
+                (1, Rect::new(0, 6, 40, 4)),
+                (3, Rect::new(0, 10, 40, 10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_padding_spacing_and_alignment() {
+        let layout = Layout::horizontal()
+            .padding(1)
+            .spacing(2)
+            .align(Align::Center)
+            .item(Size::Auto, 0)
+            .item(Size::Flex(1), 1);
+        // * 内部区域是 (1, 1, 28, 8), 组件 0 高 1, 组件 1 高 2
+        assert_eq!(
+            compute(layout, 30, 10),
+            vec![(0, Rect::new(1, 4, 10, 1)), (1, Rect::new(13, 4, 16, 2)),]
+        );
+
+        let layout = Layout::vertical()
+            .align(Align::End)
+            .item(Size::Auto, 0)
+            .item(Size::Auto, 40);
+        // * 希望的宽度超过可用宽度时被裁剪
+        assert_eq!(
+            compute(layout, 30, 50),
+            vec![(0, Rect::new(20, 0, 10, 1)), (40, Rect::new(0, 1, 30, 41))]
+        );
+    }
+
+    #[test]
+    fn nests_stacks_and_shrinks_when_space_runs_out() {
+        let row = Layout::horizontal().item(Size::Auto, 0).item(Size::Auto, 1);
+        // * 嵌套的 Stack 用 Auto 时, 大小由它的子节点决定: 宽 10 + 11, 高 max(1, 2)
+        assert_eq!(Layout::from(row.clone()).preferred(&sizes), (21, 2));
+        let layout = Layout::vertical()
+            .child(Size::Auto, row)
+            .item(Size::Fixed(5), 2)
+            .item(Size::Fixed(5), 3);
+        assert_eq!(
+            compute(layout, 15, 8),
+            vec![
+                (0, Rect::new(0, 0, 10, 2)),
+                (1, Rect::new(10, 0, 5, 2)),
+                (2, Rect::new(0, 2, 15, 5)),
+                (3, Rect::new(0, 7, 15, 1)),
+            ]
+        );
+    }
+}
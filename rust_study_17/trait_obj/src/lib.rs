@@ -1,5 +1,54 @@
+// * 画布和终端后端, 见 canvas.rs 和 backend.rs
+pub mod backend;
+pub mod canvas;
+// * 键盘和鼠标事件, 见 event.rs
+pub mod event;
+// * 给组件分配区域的布局, 见 layout.rs
+pub mod layout;
+// * 选择框, 见 select.rs
+pub mod select;
+// * 不需要终端的快照测试, 见 snapshot.rs
+pub mod snapshot;
+// * Label、TextField、Checkbox 等常用组件, 见 widgets.rs
+pub mod widgets;
+
+use std::io::{self, Read};
+
+use backend::Backend;
+use canvas::{Buffer, Canvas, Rect, Style};
+use event::{Event, Key, Mouse, MouseButton, MouseKind, Parser};
+use layout::{Align, Layout, Size};
+
 pub trait Draw {
-    fn draw(&self);
+    // * 画到 canvas 上, canvas 的大小就是分配给这个组件的区域
+    fn draw(&self, canvas: &mut Canvas);
+    // * 组件希望占用的大小(宽, 高), 以字符单元格为单位
+    fn size(&self) -> (u16, u16);
+    // * 能处理事件的组件返回 Some(self), 见 Widget
+    // ? trait 对象不能直接转换成另一个 trait 对象, 所以由组件自己提供这个转换
+    fn as_widget(&mut self) -> Option<&mut dyn Widget> {
+        None
+    }
+}
+
+// * 可以交互的组件: 除了绘制, 还能响应键盘和鼠标事件
+// + 键盘事件只发给获得焦点的组件, 鼠标事件发给鼠标下面的组件
+// + 实现了 Widget 的组件还要在 Draw::as_widget 中返回 Some(self), Screen 才会把事件发给它
+pub trait Widget: Draw {
+    // * 处理事件, 返回 true 表示事件被处理了
+    // + 鼠标事件的坐标已经换算成相对于组件区域左上角的坐标
+    fn on_event(&mut self, event: &Event) -> bool;
+    // * 能否获得焦点, 按 Tab 时会跳过不能获得焦点的组件
+    fn focusable(&self) -> bool {
+        true
+    }
+    // * 获得或者失去焦点时调用, 组件可以据此改变外观
+    fn set_focused(&mut self, _focused: bool) {}
+    // * 需要保持可见的一行(例如选中的行), 相对于组件区域的顶部
+    // + 组件放在 ScrollView 中时, 处理完事件后视图会滚动到这一行
+    fn focus_row(&self) -> Option<u16> {
+        None
+    }
 }
 
 // * 在标准库中, 克隆对象的trait就是一个不符合对象安全的例子, 签名如下, 会返回Self
@@ -12,12 +61,188 @@ pub struct Screen {
     pub components: Vec<Box<dyn Draw>>,
     // * 这里使用dyn Clone 就会报错, 报错信息: Clone 这个trait 不能变成对象, 因为它的返回类型是Self, 所以他不是对象安全的
     // pub components_clones: Vec<Box<dyn Clone>>,
+    // * 组件的布局, 为 None 时从上往下依次排列, 每个组件占用它希望的大小
+    pub layout: Option<Layout>,
+    // * 获得焦点的组件下标, 只能通过 focus 修改, 这样组件的 set_focused 才会被调用
+    focus: Option<usize>,
 }
 
 impl Screen {
-    pub fn run(&self) {
-        for component in self.components.iter() {
-            component.draw();
+    pub fn new(components: Vec<Box<dyn Draw>>) -> Screen {
+        Screen {
+            components,
+            layout: None,
+            focus: None,
+        }
+    }
+
+    pub fn with_layout<L: Into<Layout>>(mut self, layout: L) -> Screen {
+        self.layout = Some(layout.into());
+        self
+    }
+
+    // * 在 area 中给每个组件分配区域, 返回 (组件下标, 区域)
+    // + 布局中引用了不存在的组件时, 这个组件的大小按 (0, 0) 计算, 绘制时被跳过
+    pub fn layout(&self, area: Rect) -> Vec<(usize, Rect)> {
+        let sizes = |index: usize| self.components.get(index).map_or((0, 0), |c| c.size());
+        match &self.layout {
+            Some(layout) => layout.compute(area, sizes),
+            None => (0..self.components.len())
+                .fold(Layout::vertical().align(Align::Start), |stack, index| {
+                    stack.item(Size::Auto, index)
+                })
+                .into_layout()
+                .compute(area, sizes),
+        }
+    }
+
+    // * 先计算布局, 再把每个组件画到分配给它的区域上, 超出区域的部分会被裁掉
+    pub fn render(&self, buffer: &mut Buffer) {
+        for (index, area) in self.layout(buffer.area()) {
+            if let Some(component) = self.components.get(index) {
+                component.draw(&mut buffer.canvas(area));
+            }
+        }
+    }
+
+    // * 把所有组件画到一个和屏幕一样大的 Buffer 上, 然后交给后端显示
+    pub fn run<B: Backend>(&self, backend: &mut B) -> io::Result<()> {
+        let (width, height) = backend.size();
+        let mut buffer = Buffer::new(width, height);
+        self.render(&mut buffer);
+        backend.draw(&buffer)
+    }
+
+    pub fn focused(&self) -> Option<usize> {
+        self.focus
+    }
+
+    // * 把焦点交给第 index 个组件, 它不存在或者不能获得焦点时, 没有组件获得焦点
+    pub fn focus(&mut self, index: Option<usize>) {
+        if let Some(widget) = self.focus.and_then(|i| self.widget(i)) {
+            widget.set_focused(false);
+        }
+        self.focus = None;
+        if let Some(index) = index.filter(|&i| self.is_focusable(i)) {
+            if let Some(widget) = self.widget(index) {
+                widget.set_focused(true);
+            }
+            self.focus = Some(index);
+        }
+    }
+
+    // * 按组件的顺序把焦点交给下一个(或上一个)能获得焦点的组件, 到头之后从另一头开始
+    pub fn focus_next(&mut self) {
+        self.move_focus(true);
+    }
+
+    pub fn focus_prev(&mut self) {
+        self.move_focus(false);
+    }
+
+    fn move_focus(&mut self, forward: bool) {
+        let len = self.components.len();
+        if len == 0 {
+            return;
+        }
+        let start = match (self.focus, forward) {
+            (Some(i), true) => i + 1,
+            (Some(i), false) => i + len - 1,
+            (None, true) => 0,
+            (None, false) => len - 1,
+        };
+        let next = (0..len)
+            .map(|step| {
+                if forward {
+                    (start + step) % len
+                } else {
+                    (start + len - step) % len
+                }
+            })
+            .find(|&i| self.is_focusable(i));
+        self.focus(next);
+    }
+
+    fn widget(&mut self, index: usize) -> Option<&mut dyn Widget> {
+        self.components.get_mut(index)?.as_widget()
+    }
+
+    fn is_focusable(&mut self, index: usize) -> bool {
+        self.widget(index).is_some_and(|w| w.focusable())
+    }
+
+    // * 分发一个事件, area 是整个屏幕的区域, 用来计算鼠标下面是哪个组件, 返回事件是否被处理
+    // + Tab 和 Shift + Tab 用来切换焦点, 其他按键发给获得焦点的组件
+    // + 鼠标事件发给鼠标下面的组件, 按下鼠标时这个组件同时获得焦点
+    pub fn handle_event(&mut self, event: &Event, area: Rect) -> bool {
+        match event {
+            Event::Key(Key::Tab) => {
+                self.focus_next();
+                true
+            }
+            Event::Key(Key::BackTab) => {
+                self.focus_prev();
+                true
+            }
+            Event::Key(_) => match self.focus {
+                Some(index) => self.widget(index).is_some_and(|w| w.on_event(event)),
+                None => false,
+            },
+            Event::Mouse(mouse) => {
+                // * 后画的组件盖在先画的上面, 所以从后往前找
+                let hit = self
+                    .layout(area)
+                    .into_iter()
+                    .rev()
+                    .find(|(_, rect)| rect.contains(mouse.x, mouse.y));
+                let Some((index, rect)) = hit else {
+                    return false;
+                };
+                if let MouseKind::Press(_) = mouse.kind {
+                    if self.is_focusable(index) {
+                        self.focus(Some(index));
+                    }
+                }
+                let local = Event::Mouse(Mouse {
+                    x: mouse.x - rect.x,
+                    y: mouse.y - rect.y,
+                    ..*mouse
+                });
+                self.widget(index).is_some_and(|w| w.on_event(&local))
+            }
+        }
+    }
+
+    // * 交互式运行: 画出屏幕, 从 input 读取按键和鼠标事件, 处理完一批事件后重新绘制
+    // + input 读完(例如标准输入被关闭), 或者按下 Ctrl + C, 或者没有组件处理 ESC 时结束
+    // + 开始时焦点交给第一个能获得焦点的组件
+    // ? 从终端读取之前需要先进入 raw 模式, 见 backend::RawMode
+    pub fn run_events<B: Backend, R: Read>(
+        &mut self,
+        backend: &mut B,
+        mut input: R,
+    ) -> io::Result<()> {
+        if self.focus.is_none() {
+            self.focus_next();
+        }
+        let mut parser = Parser::new();
+        let mut bytes = [0; 64];
+        loop {
+            self.run(backend)?;
+            let n = input.read(&mut bytes)?;
+            if n == 0 {
+                return Ok(());
+            }
+            let (width, height) = backend.size();
+            let area = Rect::new(0, 0, width, height);
+            for event in parser.feed(&bytes[..n]) {
+                if event == Event::Key(Key::Ctrl('c')) {
+                    return Ok(());
+                }
+                if !self.handle_event(&event, area) && event == Event::Key(Key::Esc) {
+                    return Ok(());
+                }
+            }
         }
     }
 }
@@ -25,12 +250,86 @@ impl Screen {
 pub struct Button {
     pub width: i32,
     pub height: i32,
-    pub label: String
+    pub label: String,
+    // * 按钮被点击(鼠标左键, 或者获得焦点时按回车、空格)时调用
+    on_click: Option<Box<dyn FnMut()>>,
+    focused: bool,
+}
+
+impl Button {
+    pub fn new(width: i32, height: i32, label: &str) -> Button {
+        Button {
+            width,
+            height,
+            label: String::from(label),
+            on_click: None,
+            focused: false,
+        }
+    }
+
+    // * 例如: Button::new(20, 3, "OK").on_click(move || clicks.set(clicks.get() + 1))
+    pub fn on_click<F: FnMut() + 'static>(mut self, callback: F) -> Button {
+        self.on_click = Some(Box::new(callback));
+        self
+    }
+
+    pub fn click(&mut self) {
+        if let Some(callback) = self.on_click.as_mut() {
+            callback();
+        }
+    }
 }
 
 impl Draw for Button {
-    fn draw(&self) {
-        // 实现细节 ———— 绘制一个按钮
+    // * 带边框的按钮, 文字在中间; 放不下边框时只画文字; 获得焦点时文字反色显示
+    fn draw(&self, canvas: &mut Canvas) {
+        let (width, height) = (canvas.width(), canvas.height());
+        let inner = if width >= 3 && height >= 3 {
+            canvas.draw_border(Style::new());
+            Rect::new(1, 1, width - 2, height - 2)
+        } else {
+            Rect::new(0, 0, width, height)
+        };
+        let label: String = self.label.chars().take(inner.width as usize).collect();
+        let len = label.chars().count() as u16;
+        let x = inner.x + (inner.width - len) / 2;
+        let y = inner.y + inner.height.saturating_sub(1) / 2;
+        let style = if self.focused {
+            Style::new().bold().reverse()
+        } else {
+            Style::new().bold()
+        };
+        canvas.print(x, y, &label, style);
+    }
+
+    // ? width 和 height 是 i32, 负数按 0 处理
+    fn size(&self) -> (u16, u16) {
+        let clamp = |v: i32| v.clamp(0, u16::MAX as i32) as u16;
+        (clamp(self.width), clamp(self.height))
+    }
+
+    fn as_widget(&mut self) -> Option<&mut dyn Widget> {
+        Some(self)
+    }
+}
+
+impl Widget for Button {
+    fn on_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Key(Key::Enter | Key::Char(' '))
+            | Event::Mouse(Mouse {
+                kind: MouseKind::Press(MouseButton::Left),
+                ..
+            }) => {
+                self.click();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
     }
 }
 
@@ -50,4 +349,179 @@ where
             component.draw();
         }
     }
-} */
\ No newline at end of file
+} */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use backend::TestBackend;
+    use snapshot::assert_snapshot;
+
+    fn button(width: i32, height: i32, label: &str) -> Button {
+        Button::new(width, height, label)
+    }
+
+    // * 画出来的文字和样式都不关心的组件, 用来测试焦点会跳过它
+    struct Text;
+
+    impl Draw for Text {
+        fn draw(&self, _canvas: &mut Canvas) {}
+
+        fn size(&self) -> (u16, u16) {
+            (4, 1)
+        }
+    }
+
+    // * 点击时把 id 记到 clicks 里
+    fn counting(label: &str, id: u32, clicks: &Rc<Cell<u32>>) -> Box<dyn Draw> {
+        let clicks = Rc::clone(clicks);
+        Box::new(button(10, 3, label).on_click(move || clicks.set(clicks.get() * 10 + id)))
+    }
+
+    #[test]
+    fn draws_buttons_with_centered_labels() {
+        let mut buffer = Buffer::new(12, 5);
+        let screen = Screen::new(vec![
+            Box::new(button(10, 3, "OK")),
+            Box::new(button(6, 1, "Cancel!")),
+        ]);
+        screen.render(&mut buffer);
+        assert_eq!(
+            buffer.lines(),
+            vec!["┌────────┐", "│   OK   │", "└────────┘", "Cancel", ""]
+        );
+        assert!(buffer.get(4, 1).unwrap().style.bold);
+    }
+
+    #[test]
+    fn draws_components_into_layout_areas() {
+        let screen = Screen::new(vec![
+            Box::new(button(0, 0, "Yes")),
+            Box::new(button(0, 0, "No")),
+        ])
+        .with_layout(
+            Layout::horizontal()
+                .spacing(1)
+                .item(Size::Flex(1), 0)
+                .item(Size::Flex(1), 1)
+                .item(Size::Fixed(3), 7),
+        );
+        let mut buffer = Buffer::new(15, 3);
+        screen.render(&mut buffer);
+        assert_eq!(
+            screen.layout(buffer.area()),
+            vec![
+                (0, Rect::new(0, 0, 5, 3)),
+                (1, Rect::new(6, 0, 5, 3)),
+                (7, Rect::new(12, 0, 3, 3)),
+            ]
+        );
+        assert_eq!(
+            buffer.lines(),
+            vec!["┌───┐ ┌───┐", "│Yes│ │No │", "└───┘ └───┘"]
+        );
+    }
+
+    #[test]
+    fn tab_moves_focus_and_keys_click_the_focused_button() {
+        let clicks = Rc::new(Cell::new(0));
+        let mut screen = Screen::new(vec![
+            counting("A", 1, &clicks),
+            Box::new(Text),
+            counting("B", 2, &clicks),
+        ]);
+        let area = Rect::new(0, 0, 20, 10);
+        let key = |key| Event::Key(key);
+
+        // * 没有焦点时按键没人处理
+        assert!(!screen.handle_event(&key(Key::Enter), area));
+        screen.handle_event(&key(Key::Tab), area);
+        assert_eq!(screen.focused(), Some(0));
+        // * Text 不能获得焦点, 被跳过; 到头之后回到第一个
+        screen.handle_event(&key(Key::Tab), area);
+        assert_eq!(screen.focused(), Some(2));
+        assert!(screen.handle_event(&key(Key::Char(' ')), area));
+        screen.handle_event(&key(Key::Tab), area);
+        assert_eq!(screen.focused(), Some(0));
+        screen.handle_event(&key(Key::BackTab), area);
+        assert_eq!(screen.focused(), Some(2));
+        assert!(screen.handle_event(&key(Key::Enter), area));
+        assert!(!screen.handle_event(&key(Key::Char('x')), area));
+        assert_eq!(clicks.get(), 22);
+
+        // * 获得焦点的按钮文字反色显示
+        let mut buffer = Buffer::new(20, 10);
+        screen.render(&mut buffer);
+        assert!(buffer.get(4, 5).unwrap().style.reverse);
+        assert!(!buffer.get(4, 1).unwrap().style.reverse);
+    }
+
+    #[test]
+    fn mouse_clicks_go_to_the_component_under_the_pointer() {
+        let clicks = Rc::new(Cell::new(0));
+        let mut screen = Screen::new(vec![counting("A", 1, &clicks), counting("B", 2, &clicks)])
+            .with_layout(Layout::horizontal().item(Size::Auto, 0).item(Size::Auto, 1));
+        let area = Rect::new(0, 0, 30, 5);
+        let press = |x, y| {
+            Event::Mouse(Mouse {
+                kind: MouseKind::Press(MouseButton::Left),
+                x,
+                y,
+            })
+        };
+
+        assert!(screen.handle_event(&press(12, 1), area));
+        assert_eq!(screen.focused(), Some(1));
+        assert!(screen.handle_event(&press(0, 0), area));
+        assert_eq!(screen.focused(), Some(0));
+        // * 空白的地方没有组件
+        assert!(!screen.handle_event(&press(25, 1), area));
+        assert_eq!(clicks.get(), 21);
+    }
+
+    #[test]
+    fn runs_an_event_loop_over_raw_input() {
+        let clicks = Rc::new(Cell::new(0));
+        let mut screen = Screen::new(vec![counting("A", 1, &clicks), counting("B", 2, &clicks)]);
+        let mut backend = TestBackend::new(12, 6);
+        // * 开始时焦点在 A 上: 回车, Tab 到 B, 鼠标点 B, ESC 没人处理所以结束, 后面的回车不会被处理
+        let input: &[u8] = b"\r\t\x1b[<0;2;5M\x1b\r";
+        screen.run_events(&mut backend, input).unwrap();
+        assert_eq!(clicks.get(), 12);
+        assert_eq!(screen.focused(), Some(1));
+        // * 每批输入之后重新绘制一次
+        assert_eq!(backend.frames(), 1);
+        assert_snapshot("event_loop", backend.buffer());
+    }
+
+    #[test]
+    fn renders_nested_layouts() {
+        let screen = Screen::new(vec![
+            Box::new(button(0, 3, "Title")),
+            Box::new(button(8, 3, "Yes")),
+            Box::new(button(8, 3, "No")),
+            Box::new(button(0, 1, "status")),
+        ])
+        .with_layout(
+            Layout::vertical()
+                .padding(1)
+                .item(Size::Auto, 0)
+                .child(
+                    Size::Flex(1),
+                    Layout::horizontal()
+                        .spacing(2)
+                        .align(Align::Center)
+                        .item(Size::Auto, 1)
+                        .item(Size::Auto, 2),
+                )
+                .item(Size::Fixed(1), 3),
+        );
+        let mut backend = TestBackend::new(24, 12);
+        screen.run(&mut backend).unwrap();
+        assert_snapshot("nested_layouts", backend.buffer());
+    }
+}
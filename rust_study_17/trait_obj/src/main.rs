@@ -37,42 +37,63 @@
     例见lib.rs
 */
 
-use trait_obj::{Button, Screen};
-use trait_obj::Draw;
-
-struct SelectBox {
-    width: u32,
-    height: u32,
-    options: Vec<String>
-}
-
-impl Draw for SelectBox {
-    // * 在lib中说了, 只要这个SelectBox实现了 Draw这个trait, 就都可以放到Screen里面
-    fn draw(&self) {
-        // 绘制一个选择框
-    }
-}
+use std::cell::Cell;
+use std::io;
+use std::process;
+use std::rc::Rc;
 
+use trait_obj::backend::{RawMode, TerminalBackend};
+use trait_obj::layout::{Align, Layout, Size};
+use trait_obj::{Button, Screen};
+use trait_obj::select::SelectBox;
+use trait_obj::widgets::{Checkbox, Label, TextField};
 
 fn main() {
-    let screen = Screen {
-        components: vec![
-            Box::new(SelectBox {
-                width: 75,
-                height: 10,
-                options: vec![
-                    String::from("yes"),
-                    String::from("Maybe"),
-                    String::from("fuck"),
-                ]
-            }),
-            Box::new(Button {
-                width: 50,
-                height: 20,
-                label: String::from("mother fucker"),
-            })
+    let clicks = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&clicks);
+    let mut screen = Screen::new(vec![
+            // * SelectBox 见 select.rs, 只要实现了 Draw 这个 trait, 就都可以放到 Screen 里面
+            Box::new(SelectBox::new(20, 5, &["yes", "Maybe", "fuck"])),
+            Box::new(
+                Button::new(20, 3, "mother fucker").on_click(move || counter.set(counter.get() + 1)),
+            ),
             // 能放到components里面的, 只有实现了 Draw这个 trait的才能放入
-        ]
+            // ? 终端里中文占两个单元格, 画布按一个单元格计算, 所以组件上的文字用英文
+            Box::new(Label::new("Widgets from widgets.rs mix freely with the components above").width(40)),
+            Box::new(TextField::new(20).placeholder("type something")),
+            Box::new(Checkbox::new("Remember me")),
+        ])
+        // * 选择框和按钮左右排列, 各自占用希望的大小, 下面依次是其他组件
+        .with_layout(
+            Layout::vertical()
+                .padding(1)
+                .spacing(1)
+                .align(Align::Start)
+                .child(
+                    Size::Auto,
+                    Layout::horizontal()
+                        .spacing(2)
+                        .align(Align::Start)
+                        .item(Size::Auto, 0)
+                        .item(Size::Auto, 1),
+                )
+                .item(Size::Auto, 2)
+                .item(Size::Auto, 3)
+                .item(Size::Auto, 4),
+        );
+    // * 画到终端上, 终端大小取自环境变量 COLUMNS 和 LINES
+    // + 标准输入是终端时进入 raw 模式, 可以用 Tab、方向键、回车和鼠标操作, ESC 或 Ctrl + C 退出
+    // + 否则(例如在管道中运行)只画一次
+    let mut backend = TerminalBackend::stdout();
+    let result = match RawMode::enable() {
+        Ok(_raw) => screen.run_events(&mut backend, io::stdin()),
+        Err(_) => screen.run(&mut backend),
     };
-    screen.run();
+    if let Err(e) = result {
+        eprintln!("绘制失败: {}", e);
+        process::exit(1);
+    }
+    if clicks.get() > 0 {
+        println!("按钮被点击了 {} 次", clicks.get());
+    }
 }
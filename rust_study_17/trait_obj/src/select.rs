@@ -0,0 +1,179 @@
+// * 选择框, 最早写在 main.rs 中, 作为 Screen 中 trait 对象的例子
+// ? 和 TextField 一样, 在 draw 时才知道能显示几个选项, 处理鼠标点击时要用到, 所以记在 Cell 里
+use std::cell::Cell;
+
+use crate::canvas::{Canvas, Rect, Style};
+use crate::event::{Event, Key, Mouse, MouseButton, MouseKind};
+use crate::{Draw, Widget};
+
+// * 选择框: 边框里面每行一个选项, 选项比可见的行数多时, 滚动到选中的选项
+// + 选中的选项加粗, 获得焦点时反色显示
+pub struct SelectBox {
+    pub width: u16,
+    pub height: u16,
+    pub options: Vec<String>,
+    // * 选中的选项下标
+    selected: usize,
+    focused: bool,
+    // * 最后一次绘制时能显示的选项数
+    visible: Cell<u16>,
+}
+
+impl SelectBox {
+    pub fn new<S: AsRef<str>>(width: u16, height: u16, options: &[S]) -> SelectBox {
+        SelectBox {
+            width,
+            height,
+            options: options.iter().map(|o| o.as_ref().to_string()).collect(),
+            selected: 0,
+            focused: false,
+            visible: Cell::new(height.saturating_sub(2)),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.options.len().saturating_sub(1));
+    }
+
+    // * 第一行显示的选项下标
+    fn scroll(&self, visible: u16) -> usize {
+        self.selected.saturating_sub((visible as usize).max(1) - 1)
+    }
+}
+
+impl Draw for SelectBox {
+    fn draw(&self, canvas: &mut Canvas) {
+        canvas.draw_border(Style::new());
+        let rows = canvas.height().saturating_sub(2);
+        self.visible.set(rows);
+        let width = canvas.width().saturating_sub(2);
+        let start = self.scroll(rows);
+        for (i, option) in self
+            .options
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(rows as usize)
+        {
+            let style = match (i == self.selected, self.focused) {
+                (true, true) => Style::new().bold().reverse(),
+                (true, false) => Style::new().bold(),
+                _ => Style::new(),
+            };
+            let mut row = canvas.sub(Rect::new(1, 1 + (i - start) as u16, width, 1));
+            if i == self.selected {
+                row.fill(' ', style);
+            }
+            row.print(0, 0, &format!("• {}", option), style);
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    fn as_widget(&mut self) -> Option<&mut dyn Widget> {
+        Some(self)
+    }
+}
+
+impl Widget for SelectBox {
+    // * 上下键和滚轮移动选中的选项, 鼠标点击直接选中那一行
+    fn on_event(&mut self, event: &Event) -> bool {
+        let last = self.options.len().saturating_sub(1);
+        match event {
+            Event::Key(Key::Up)
+            | Event::Mouse(Mouse {
+                kind: MouseKind::ScrollUp,
+                ..
+            }) => self.selected = self.selected.saturating_sub(1),
+            Event::Key(Key::Down)
+            | Event::Mouse(Mouse {
+                kind: MouseKind::ScrollDown,
+                ..
+            }) => self.selected = (self.selected + 1).min(last),
+            Event::Key(Key::Home) => self.selected = 0,
+            Event::Key(Key::End) => self.selected = last,
+            // * 第 0 行是边框, 只有显示出来的选项可以被点中
+            Event::Mouse(Mouse {
+                kind: MouseKind::Press(MouseButton::Left),
+                y,
+                ..
+            }) if *y >= 1 && *y <= self.visible.get() => {
+                let index = self.scroll(self.visible.get()) + (*y - 1) as usize;
+                if index >= self.options.len() {
+                    return false;
+                }
+                self.selected = index;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    // * 第 0 行是边框
+    fn focus_row(&self) -> Option<u16> {
+        let row = self.selected - self.scroll(self.visible.get()) + 1;
+        Some(row.min(u16::MAX as usize) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Buffer;
+    use crate::snapshot::assert_snapshot;
+
+    fn key(key: Key) -> Event {
+        Event::Key(key)
+    }
+
+    fn click(x: u16, y: u16) -> Event {
+        Event::Mouse(Mouse {
+            kind: MouseKind::Press(MouseButton::Left),
+            x,
+            y,
+        })
+    }
+
+    fn render(component: &dyn Draw, width: u16, height: u16) -> Buffer {
+        let mut buffer = Buffer::new(width, height);
+        component.draw(&mut buffer.canvas(buffer.area()));
+        buffer
+    }
+
+    #[test]
+    fn moves_and_clicks_select_box_options() {
+        let mut select = SelectBox::new(12, 4, &["yes", "maybe", "no"]);
+        assert_eq!(select.size(), (12, 4));
+        select.set_focused(true);
+        assert!(select.on_event(&key(Key::Down)));
+        let buffer = render(&select, 12, 4);
+        assert_snapshot("select_box", &buffer);
+        assert!(buffer.get(10, 2).unwrap().style.reverse);
+
+        // * 只能显示两个选项, 选中最后一个时向下滚动一行
+        assert!(select.on_event(&key(Key::End)));
+        assert_eq!(
+            render(&select, 12, 4).lines()[1..3],
+            ["│• maybe   │", "│• no      │"]
+        );
+        assert!(select.on_event(&click(3, 1)));
+        assert_eq!(select.selected(), 1);
+        assert!(select.on_event(&key(Key::Up)));
+        assert_eq!(select.selected(), 0);
+        render(&select, 12, 4);
+        // * 下边框和被挡住的选项都点不中
+        assert!(!select.on_event(&click(3, 3)));
+        assert_eq!(select.selected(), 0);
+        assert!(!SelectBox::new(12, 4, &["only"]).on_event(&click(3, 2)));
+    }
+}
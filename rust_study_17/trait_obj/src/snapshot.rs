@@ -0,0 +1,153 @@
+// * 快照测试: 把画出来的 Buffer 转成文字, 和保存在文件里的快照比较
+// + 快照文件就是 Buffer::lines() 按行保存的文字, 可以直接打开查看, 改动也能在 git diff 里看出来
+// + 设置环境变量 UPDATE_SNAPSHOTS=1 运行测试时, 不比较, 而是把当前的结果写进快照文件
+//   - 新增快照: UPDATE_SNAPSHOTS=1 cargo test
+//   - 修改了组件的外观之后, 同样重新生成, 然后检查 git diff
+// + 快照文件不存在并且不是更新模式时, 测试失败, 避免漏提交快照文件
+// ? 快照只保存文字, 不保存样式; 样式(例如获得焦点时的反色)用 Buffer::get 单独检查
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::canvas::Buffer;
+
+pub const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+// * 这个 crate 自己的快照目录
+pub fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src")
+        .join("snapshots")
+}
+
+// * 快照的内容: 每行去掉行尾空格, 以换行结尾
+pub fn to_text(buffer: &Buffer) -> String {
+    let mut text = buffer.lines().join("\n");
+    text.push('\n');
+    text
+}
+
+// * 环境变量设置了, 并且不是空字符串或者 "0"
+pub fn update_mode() -> bool {
+    env::var(UPDATE_ENV).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Missing(PathBuf),
+    Mismatch { path: PathBuf, diff: String },
+    Io(io::Error),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Missing(path) => write!(
+                f,
+                "快照 {} 不存在, 使用 {}=1 运行测试来生成",
+                path.display(),
+                UPDATE_ENV
+            ),
+            SnapshotError::Mismatch { path, diff } => write!(
+                f,
+                "和快照 {} 不一致 (- 快照, + 实际), 确认改动正确后使用 {}=1 更新:\n{}",
+                path.display(),
+                UPDATE_ENV,
+                diff
+            ),
+            SnapshotError::Io(e) => write!(f, "读写快照失败: {}", e),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+// * 比较 actual 和 path 中的快照, update 为 true 时把 actual 写进快照
+pub fn check(path: &Path, actual: &str, update: bool) -> Result<(), SnapshotError> {
+    if update {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(SnapshotError::Io)?;
+        }
+        return fs::write(path, actual).map_err(SnapshotError::Io);
+    }
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(SnapshotError::Missing(path.to_path_buf()))
+        }
+        Err(e) => return Err(SnapshotError::Io(e)),
+    };
+    if expected == actual {
+        return Ok(());
+    }
+    Err(SnapshotError::Mismatch {
+        path: path.to_path_buf(),
+        diff: diff(&expected, actual),
+    })
+}
+
+// * 和 dir/name.txt 比较, 不一致时 panic, 在测试中使用
+pub fn assert_snapshot_in(dir: &Path, name: &str, buffer: &Buffer) {
+    let path = dir.join(format!("{}.txt", name));
+    if let Err(e) = check(&path, &to_text(buffer), update_mode()) {
+        panic!("{}", e);
+    }
+}
+
+// * 和这个 crate 的 src/snapshots/name.txt 比较
+pub fn assert_snapshot(name: &str, buffer: &Buffer) {
+    assert_snapshot_in(&snapshot_dir(), name, buffer);
+}
+
+// * 逐行比较, 相同的行前面是两个空格, 不同的行分别用 - 和 + 标出快照和实际的内容
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => out.push(format!("  {}", e)),
+            (e, a) => {
+                out.extend(e.map(|e| format!("- {}", e)));
+                out.extend(a.map(|a| format!("+ {}", a)));
+            }
+        }
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Style;
+    use std::process;
+
+    #[test]
+    fn writes_then_compares_snapshots() {
+        let dir = env::temp_dir().join(format!("trait_obj-snapshots-{}", process::id()));
+        let path = dir.join("hello.txt");
+        let mut buffer = Buffer::new(5, 2);
+        buffer
+            .canvas(buffer.area())
+            .print(0, 0, "hello", Style::new());
+
+        assert!(matches!(
+            check(&path, &to_text(&buffer), false),
+            Err(SnapshotError::Missing(_))
+        ));
+        check(&path, &to_text(&buffer), true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello\n\n");
+        check(&path, &to_text(&buffer), false).unwrap();
+
+        buffer.set(4, 0, '!', Style::new());
+        let error = check(&path, &to_text(&buffer), false).unwrap_err();
+        match &error {
+            SnapshotError::Mismatch { diff, .. } => assert_eq!(diff, "- hello\n+ hell!\n  "),
+            other => panic!("{:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
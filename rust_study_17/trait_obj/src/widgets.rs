@@ -0,0 +1,905 @@
+// * 常用组件, 都实现了 Draw, 可以和 Button 等组件一起放进 Screen.components
+// + Label: 自动换行的文字
+// + TextField: 单行输入框, 有光标, 可以编辑
+// + Checkbox: 复选框
+// + SelectBox: 带边框的选择框, 每行一个选项, 见 select.rs
+// + ProgressBar: 进度条
+// + Table: 表格, 列宽使用和布局一样的 Size(固定、按内容、按比例), 可以选中一行
+// + ScrollView: 给内容比较大的组件加上滚动条, 一次只显示一部分
+// + 能交互的组件同时实现了 Widget
+// ? 组件在 draw 中才知道自己分到的区域有多大, 处理鼠标事件时又需要这个大小,
+//   所以 TextField、Table、ScrollView 在 draw 时把它记在 Cell 里
+use std::cell::Cell;
+
+use crate::canvas::{Canvas, Color, Rect, Style};
+use crate::event::{Event, Key, Mouse, MouseButton, MouseKind};
+use crate::layout::{Layout, Size};
+use crate::{Draw, Widget};
+
+fn char_len(text: &str) -> u16 {
+    text.chars().count().min(u16::MAX as usize) as u16
+}
+
+// * 按单词把 text 折成每行最多 width 个字符, 保留原有的换行; 比 width 还长的单词会被拆开
+// + width 为 0 时不折行
+pub fn wrap(text: &str, width: u16) -> Vec<String> {
+    let width = width as usize;
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        if width == 0 {
+            lines.push(paragraph.to_string());
+            continue;
+        }
+        let mut line = String::new();
+        let mut len = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            let needed = if len == 0 {
+                word.len()
+            } else {
+                len + 1 + word.len()
+            };
+            if needed <= width {
+                if len > 0 {
+                    line.push(' ');
+                    len += 1;
+                }
+                line.extend(&word);
+                len += word.len();
+                continue;
+            }
+            if len > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > width {
+                lines.push(word.drain(..width).collect());
+            }
+            line.extend(&word);
+            len = word.len();
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+pub struct Label {
+    pub text: String,
+    // * 希望的宽度, 超过这个宽度就折行; 0 表示按最长的一行
+    pub width: u16,
+    pub style: Style,
+}
+
+impl Label {
+    pub fn new(text: &str) -> Label {
+        Label {
+            text: String::from(text),
+            width: 0,
+            style: Style::new(),
+        }
+    }
+
+    pub fn width(mut self, width: u16) -> Label {
+        self.width = width;
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Label {
+        self.style = style;
+        self
+    }
+}
+
+impl Draw for Label {
+    // * 按实际分到的宽度折行, 放不下的行被裁掉
+    fn draw(&self, canvas: &mut Canvas) {
+        for (y, line) in wrap(&self.text, canvas.width()).iter().enumerate() {
+            if y >= canvas.height() as usize {
+                break;
+            }
+            canvas.print(0, y as u16, line, self.style);
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        let lines = wrap(&self.text, self.width);
+        let width = lines.iter().map(|l| char_len(l)).max().unwrap_or(0);
+        let height = lines.len().min(u16::MAX as usize) as u16;
+        (width.max(self.width), height)
+    }
+}
+
+// * 单行输入框, cursor 是光标前面的字符数
+// + 文字比输入框长时, 水平滚动, 保证光标可见
+pub struct TextField {
+    pub width: u16,
+    pub placeholder: String,
+    value: String,
+    cursor: usize,
+    focused: bool,
+    // * 最后一次绘制时的宽度
+    visible: Cell<u16>,
+}
+
+impl TextField {
+    pub fn new(width: u16) -> TextField {
+        TextField {
+            width,
+            placeholder: String::new(),
+            value: String::new(),
+            cursor: 0,
+            focused: false,
+            visible: Cell::new(width),
+        }
+    }
+
+    // * 输入框为空时显示的提示文字
+    pub fn placeholder(mut self, placeholder: &str) -> TextField {
+        self.placeholder = String::from(placeholder);
+        self
+    }
+
+    // * 设置初始内容, 光标放在最后
+    pub fn with_value(mut self, value: &str) -> TextField {
+        self.set_value(value);
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+        self.value = String::from(value);
+        self.cursor = self.value.chars().count();
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // * 第 index 个字符在 value 中的字节位置
+    fn byte_index(&self, index: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(index)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    // * 显示的第一个字符, 光标(包括行尾的光标)要在可见范围内
+    fn scroll(&self, width: u16) -> usize {
+        self.cursor
+            .saturating_sub((width as usize).saturating_sub(1))
+    }
+}
+
+impl Draw for TextField {
+    fn draw(&self, canvas: &mut Canvas) {
+        let width = canvas.width();
+        self.visible.set(width);
+        if self.value.is_empty() && !self.placeholder.is_empty() {
+            // * 提示文字用灰色(加粗的黑色)显示
+            let style = Style::new().fg(Color::Black).bold();
+            canvas.print(0, 0, &self.placeholder, style);
+        } else {
+            let start = self.scroll(width);
+            let text: String = self.value.chars().skip(start).collect();
+            canvas.print(0, 0, &text, Style::new());
+        }
+        // * 获得焦点时光标所在的单元格反色显示
+        if self.focused {
+            let x = (self.cursor - self.scroll(width)) as u16;
+            let ch = self.value.chars().nth(self.cursor).unwrap_or(' ');
+            canvas.set(x, 0, ch, Style::new().reverse());
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        (self.width, 1)
+    }
+
+    fn as_widget(&mut self) -> Option<&mut dyn Widget> {
+        Some(self)
+    }
+}
+
+impl Widget for TextField {
+    fn on_event(&mut self, event: &Event) -> bool {
+        let len = self.value.chars().count();
+        match event {
+            Event::Key(Key::Char(c)) => {
+                let at = self.byte_index(self.cursor);
+                self.value.insert(at, *c);
+                self.cursor += 1;
+            }
+            Event::Key(Key::Backspace) if self.cursor > 0 => {
+                self.cursor -= 1;
+                let at = self.byte_index(self.cursor);
+                self.value.remove(at);
+            }
+            Event::Key(Key::Delete) if self.cursor < len => {
+                let at = self.byte_index(self.cursor);
+                self.value.remove(at);
+            }
+            Event::Key(Key::Left) => self.cursor = self.cursor.saturating_sub(1),
+            Event::Key(Key::Right) => self.cursor = (self.cursor + 1).min(len),
+            Event::Key(Key::Home) => self.cursor = 0,
+            Event::Key(Key::End) => self.cursor = len,
+            // * 点击时把光标移到点击的位置
+            Event::Mouse(Mouse {
+                kind: MouseKind::Press(MouseButton::Left),
+                x,
+                ..
+            }) => {
+                let start = self.scroll(self.visible.get());
+                self.cursor = (start + *x as usize).min(len);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+}
+
+pub struct Checkbox {
+    pub label: String,
+    checked: bool,
+    focused: bool,
+    // * 勾选状态改变时调用, 参数是新的状态
+    on_change: Option<Box<dyn FnMut(bool)>>,
+}
+
+impl Checkbox {
+    pub fn new(label: &str) -> Checkbox {
+        Checkbox {
+            label: String::from(label),
+            checked: false,
+            focused: false,
+            on_change: None,
+        }
+    }
+
+    pub fn checked(mut self, checked: bool) -> Checkbox {
+        self.checked = checked;
+        self
+    }
+
+    pub fn on_change<F: FnMut(bool) + 'static>(mut self, callback: F) -> Checkbox {
+        self.on_change = Some(Box::new(callback));
+        self
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
+    pub fn toggle(&mut self) {
+        self.checked = !self.checked;
+        if let Some(callback) = self.on_change.as_mut() {
+            callback(self.checked);
+        }
+    }
+}
+
+impl Draw for Checkbox {
+    // * "[x] 文字", 获得焦点时文字反色显示
+    fn draw(&self, canvas: &mut Canvas) {
+        let mark = if self.checked { "[x]" } else { "[ ]" };
+        let x = canvas.print(0, 0, mark, Style::new().bold());
+        let style = if self.focused {
+            Style::new().reverse()
+        } else {
+            Style::new()
+        };
+        canvas.print(x + 1, 0, &self.label, style);
+    }
+
+    fn size(&self) -> (u16, u16) {
+        (char_len(&self.label).saturating_add(4), 1)
+    }
+
+    fn as_widget(&mut self) -> Option<&mut dyn Widget> {
+        Some(self)
+    }
+}
+
+impl Widget for Checkbox {
+    fn on_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Key(Key::Enter | Key::Char(' '))
+            | Event::Mouse(Mouse {
+                kind: MouseKind::Press(MouseButton::Left),
+                ..
+            }) => {
+                self.toggle();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+}
+
+// * 进度条: "█████░░░░░  50%", 右边 5 个单元格显示百分比
+pub struct ProgressBar {
+    pub width: u16,
+    pub style: Style,
+    // * 0.0 ~ 1.0
+    progress: f64,
+}
+
+impl ProgressBar {
+    pub fn new(width: u16) -> ProgressBar {
+        ProgressBar {
+            width,
+            style: Style::new(),
+            progress: 0.0,
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> ProgressBar {
+        self.style = style;
+        self
+    }
+
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+
+    // * 超出 0.0 ~ 1.0 的值被截断, NaN 按 0 处理
+    pub fn set_progress(&mut self, progress: f64) {
+        self.progress = if progress.is_nan() {
+            0.0
+        } else {
+            progress.clamp(0.0, 1.0)
+        };
+    }
+}
+
+impl Draw for ProgressBar {
+    fn draw(&self, canvas: &mut Canvas) {
+        let percent = format!("{:>4}%", (self.progress * 100.0).floor() as u8);
+        let bar = canvas.width().saturating_sub(char_len(&percent));
+        let filled = (self.progress * bar as f64).floor() as u16;
+        for x in 0..bar {
+            let ch = if x < filled { '█' } else { '░' };
+            canvas.set(x, 0, ch, self.style);
+        }
+        canvas.print(bar, 0, &percent, Style::new());
+    }
+
+    fn size(&self) -> (u16, u16) {
+        (self.width, 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub title: String,
+    pub width: Size,
+}
+
+// * 表格: 第一行是表头, 第二行是分隔线, 下面每行一条数据, 列之间空一格
+// + 列宽: Fixed(n) 固定宽度, Auto 按表头和内容中最长的一个, Flex 按比例分剩下的宽度
+// + 行数超过可见的行数时, 滚动到选中的那一行
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+    selected: Option<usize>,
+    focused: bool,
+    // * 最后一次绘制时能显示的数据行数
+    visible: Cell<u16>,
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            selected: None,
+            focused: false,
+            visible: Cell::new(0),
+        }
+    }
+
+    pub fn column(mut self, title: &str, width: Size) -> Table {
+        self.columns.push(Column {
+            title: String::from(title),
+            width,
+        });
+        self
+    }
+
+    // * 少于列数的单元格显示为空, 多出来的被忽略
+    pub fn row<S: AsRef<str>>(mut self, cells: &[S]) -> Table {
+        self.rows
+            .push(cells.iter().map(|c| c.as_ref().to_string()).collect());
+        self
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn select(&mut self, row: Option<usize>) {
+        self.selected = row.filter(|&r| r < self.rows.len());
+    }
+
+    fn content_width(&self, column: usize) -> u16 {
+        let title = self.columns.get(column).map_or(0, |c| char_len(&c.title));
+        self.rows
+            .iter()
+            .filter_map(|row| row.get(column))
+            .map(|cell| char_len(cell))
+            .fold(title, u16::max)
+    }
+
+    // * 每一列的横坐标和宽度, 复用布局的计算方式
+    fn column_areas(&self, width: u16) -> Vec<Rect> {
+        let layout = self
+            .columns
+            .iter()
+            .enumerate()
+            .fold(Layout::horizontal().spacing(1), |stack, (i, column)| {
+                stack.item(column.width, i)
+            });
+        Layout::from(layout)
+            .compute(Rect::new(0, 0, width, 1), |i| (self.content_width(i), 1))
+            .into_iter()
+            .map(|(_, rect)| rect)
+            .collect()
+    }
+
+    // * 第一行显示的数据下标
+    fn scroll(&self, visible: u16) -> usize {
+        match self.selected {
+            Some(selected) if visible > 0 => selected.saturating_sub(visible as usize - 1),
+            _ => 0,
+        }
+    }
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+impl Draw for Table {
+    fn draw(&self, canvas: &mut Canvas) {
+        let visible = canvas.height().saturating_sub(2);
+        self.visible.set(visible);
+        let areas = self.column_areas(canvas.width());
+        for (column, area) in self.columns.iter().zip(&areas) {
+            canvas.sub(Rect::new(area.x, 0, area.width, 1)).print(
+                0,
+                0,
+                &column.title,
+                Style::new().bold(),
+            );
+        }
+        for x in 0..canvas.width() {
+            canvas.set(x, 1, '─', Style::new());
+        }
+        let start = self.scroll(visible);
+        for (i, row) in self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(visible as usize)
+        {
+            let y = (i - start) as u16 + 2;
+            let style = match (self.selected == Some(i), self.focused) {
+                (true, true) => Style::new().reverse(),
+                (true, false) => Style::new().bold(),
+                _ => Style::new(),
+            };
+            if self.selected == Some(i) {
+                canvas
+                    .sub(Rect::new(0, y, canvas.width(), 1))
+                    .fill(' ', style);
+            }
+            for (cell, area) in row.iter().zip(&areas) {
+                canvas
+                    .sub(Rect::new(area.x, y, area.width, 1))
+                    .print(0, 0, cell, style);
+            }
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        let widths: u16 = (0..self.columns.len())
+            .map(|i| match self.columns[i].width {
+                Size::Fixed(n) => n,
+                _ => self.content_width(i),
+            })
+            .fold(0, u16::saturating_add);
+        let gaps = self.columns.len().saturating_sub(1) as u16;
+        let height = self.rows.len().saturating_add(2).min(u16::MAX as usize) as u16;
+        (widths.saturating_add(gaps), height)
+    }
+
+    fn as_widget(&mut self) -> Option<&mut dyn Widget> {
+        Some(self)
+    }
+}
+
+impl Widget for Table {
+    // * 上下键和滚轮移动选中的行, 点击直接选中那一行
+    fn on_event(&mut self, event: &Event) -> bool {
+        let last = self.rows.len().checked_sub(1);
+        let next = match event {
+            Event::Key(Key::Up)
+            | Event::Mouse(Mouse {
+                kind: MouseKind::ScrollUp,
+                ..
+            }) => self.selected.map_or(last, |s| Some(s.saturating_sub(1))),
+            Event::Key(Key::Down)
+            | Event::Mouse(Mouse {
+                kind: MouseKind::ScrollDown,
+                ..
+            }) => self
+                .selected
+                .map_or(Some(0), |s| last.map(|last| (s + 1).min(last))),
+            Event::Key(Key::Home) => Some(0),
+            Event::Key(Key::End) => last,
+            // * 前两行是表头和分隔线
+            Event::Mouse(Mouse {
+                kind: MouseKind::Press(MouseButton::Left),
+                y,
+                ..
+            }) if *y >= 2 => {
+                let row = self.scroll(self.visible.get()) + (*y - 2) as usize;
+                if row >= self.rows.len() {
+                    return false;
+                }
+                Some(row)
+            }
+            _ => return false,
+        };
+        self.select(next);
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    // * 前两行是表头和分隔线
+    fn focus_row(&self) -> Option<u16> {
+        let start = self.scroll(self.visible.get());
+        self.selected
+            .map(|selected| (selected - start + 2).min(u16::MAX as usize) as u16)
+    }
+}
+
+// * 滚动视图: 内容按它希望的大小来画, 只显示其中从 offset 开始的几行
+// + 内容比视图高时, 最右边一列是滚动条
+// + 事件先交给内容(如果它是 Widget), 内容处理之后滚动到内容的 focus_row, 让选中的行保持可见
+// + 内容不处理时, 上下键和滚轮用来滚动
+pub struct ScrollView {
+    pub width: u16,
+    pub height: u16,
+    content: Box<dyn Draw>,
+    offset: u16,
+    // * 最后一次绘制时的高度
+    visible: Cell<u16>,
+}
+
+impl ScrollView {
+    pub fn new(content: Box<dyn Draw>, width: u16, height: u16) -> ScrollView {
+        ScrollView {
+            width,
+            height,
+            content,
+            offset: 0,
+            visible: Cell::new(height),
+        }
+    }
+
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    // * 滚动到第 y 行, 最多滚动到内容的最后一行刚好在视图底部
+    pub fn scroll_to(&mut self, y: u16) {
+        let max = self.content.size().1.saturating_sub(self.visible.get());
+        self.offset = y.min(max);
+    }
+
+    // * 内容的第 row 行不在视图中时, 滚动最少的行数让它出现
+    pub fn scroll_into_view(&mut self, row: u16) {
+        let visible = self.visible.get().max(1);
+        if row < self.offset {
+            self.scroll_to(row);
+        } else if row >= self.offset.saturating_add(visible) {
+            self.scroll_to(row - visible + 1);
+        }
+    }
+}
+
+impl Draw for ScrollView {
+    fn draw(&self, canvas: &mut Canvas) {
+        let (width, height) = (canvas.width(), canvas.height());
+        self.visible.set(height);
+        let (content_width, content_height) = self.content.size();
+        let scrolling = content_height > height;
+        let view_width = if scrolling {
+            width.saturating_sub(1)
+        } else {
+            width
+        };
+
+        // * 只有可见的部分会写进画布, 滚动条那一列不会被内容覆盖
+        let offset = self.offset.min(content_height.saturating_sub(height));
+        self.content.draw(
+            &mut canvas.sub(Rect::new(0, 0, view_width, height)).scrolled(
+                content_width,
+                content_height,
+                offset,
+            ),
+        );
+
+        // * 滑块的长度和位置与可见部分在内容中的比例一致
+        if scrolling && width > 0 && height > 0 {
+            let thumb = ((height as u32 * height as u32) / content_height as u32).max(1) as u16;
+            let max_offset = content_height - height;
+            let top = ((height - thumb) as u32 * offset as u32 / max_offset as u32) as u16;
+            for y in 0..height {
+                let ch = if y >= top && y < top + thumb {
+                    '█'
+                } else {
+                    '│'
+                };
+                canvas.set(width - 1, y, ch, Style::new());
+            }
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    fn as_widget(&mut self) -> Option<&mut dyn Widget> {
+        Some(self)
+    }
+}
+
+impl Widget for ScrollView {
+    fn on_event(&mut self, event: &Event) -> bool {
+        // * 鼠标坐标换算成内容中的坐标
+        let forwarded = match event {
+            Event::Mouse(mouse) => Event::Mouse(Mouse {
+                y: mouse.y.saturating_add(self.offset),
+                ..*mouse
+            }),
+            _ => *event,
+        };
+        if let Some(content) = self.content.as_widget() {
+            if content.on_event(&forwarded) {
+                if let Some(row) = content.focus_row() {
+                    self.scroll_into_view(row);
+                }
+                return true;
+            }
+        }
+        let page = self.visible.get().max(1);
+        match event {
+            Event::Key(Key::Up)
+            | Event::Mouse(Mouse {
+                kind: MouseKind::ScrollUp,
+                ..
+            }) => self.scroll_to(self.offset.saturating_sub(1)),
+            Event::Key(Key::Down)
+            | Event::Mouse(Mouse {
+                kind: MouseKind::ScrollDown,
+                ..
+            }) => self.scroll_to(self.offset.saturating_add(1)),
+            Event::Key(Key::Home) => self.scroll_to(0),
+            Event::Key(Key::End) => self.scroll_to(u16::MAX),
+            Event::Key(Key::Char(' ')) => self.scroll_to(self.offset.saturating_add(page)),
+            _ => return false,
+        }
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        if let Some(content) = self.content.as_widget() {
+            content.set_focused(focused);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::TestBackend;
+    use crate::canvas::Buffer;
+    use crate::snapshot::assert_snapshot;
+    use crate::Screen;
+    use std::rc::Rc;
+
+    fn key(key: Key) -> Event {
+        Event::Key(key)
+    }
+
+    fn click(x: u16, y: u16) -> Event {
+        Event::Mouse(Mouse {
+            kind: MouseKind::Press(MouseButton::Left),
+            x,
+            y,
+        })
+    }
+
+    fn render(component: &dyn Draw, width: u16, height: u16) -> Buffer {
+        let mut buffer = Buffer::new(width, height);
+        component.draw(&mut buffer.canvas(buffer.area()));
+        buffer
+    }
+
+    #[test]
+    fn wraps_words_and_splits_long_ones() {
+        assert_eq!(
+            wrap("the quick brown fox\njumps", 10),
+            vec!["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap("abcdefghij kl", 4), vec!["abcd", "efgh", "ij", "kl"]);
+        assert_eq!(wrap("a  b\n\nc", 0), vec!["a  b", "", "c"]);
+
+        let label = Label::new("hello wide world").width(11);
+        assert_eq!(label.size(), (11, 2));
+        assert_eq!(render(&label, 6, 2).lines(), vec!["hello", "wide"]);
+    }
+
+    #[test]
+    fn edits_text_fields_at_the_cursor() {
+        let mut field = TextField::new(6).with_value("héllo");
+        for event in [
+            key(Key::Home),
+            key(Key::Right),
+            key(Key::Delete),
+            key(Key::Char('e')),
+            key(Key::End),
+            key(Key::Backspace),
+            key(Key::Char('!')),
+            key(Key::Char('!')),
+        ] {
+            assert!(field.on_event(&event));
+        }
+        assert_eq!(field.value(), "hell!!");
+        assert_eq!(field.cursor(), 6);
+        assert!(!field.on_event(&key(Key::Tab)));
+
+        // * 光标在最后, 文字向左滚动一格
+        field.set_focused(true);
+        let buffer = render(&field, 6, 1);
+        assert_eq!(buffer.lines(), vec!["ell!!"]);
+        assert!(buffer.get(5, 0).unwrap().style.reverse);
+        field.on_event(&click(1, 0));
+        assert_eq!(field.cursor(), 2);
+
+        let empty = TextField::new(8).placeholder("name");
+        assert_eq!(render(&empty, 8, 1).lines(), vec!["name"]);
+    }
+
+    #[test]
+    fn toggles_checkboxes_and_fills_progress_bars() {
+        let changes = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&changes);
+        let mut checkbox = Checkbox::new("Remember me").on_change(move |checked| {
+            counter.set(counter.get() * 10 + checked as u32 + 1);
+        });
+        assert_eq!(checkbox.size(), (15, 1));
+        assert!(checkbox.on_event(&key(Key::Char(' '))));
+        assert!(checkbox.is_checked());
+        assert_eq!(render(&checkbox, 15, 1).lines(), vec!["[x] Remember me"]);
+        checkbox.on_event(&click(0, 0));
+        assert_eq!(changes.get(), 21);
+        assert_eq!(render(&checkbox, 15, 1).lines(), vec!["[ ] Remember me"]);
+
+        let mut bar = ProgressBar::new(15);
+        bar.set_progress(0.5);
+        assert_eq!(render(&bar, 15, 1).lines(), vec!["█████░░░░░  50%"]);
+        bar.set_progress(7.0);
+        assert_eq!(bar.progress(), 1.0);
+        assert_eq!(render(&bar, 10, 1).lines(), vec!["█████ 100%"]);
+    }
+
+    fn table() -> Table {
+        Table::new()
+            .column("Id", Size::Fixed(3))
+            .column("Name", Size::Auto)
+            .column("Email", Size::Flex(1))
+            .row(&["1", "alice", "alice@example.com"])
+            .row(&["2", "bob", "bob@example.com"])
+            .row(&["3", "carol", "c@example.com"])
+            .row(&["4", "dave"])
+    }
+
+    #[test]
+    fn sizes_table_columns_and_moves_the_selection() {
+        let mut table = table();
+        assert_eq!(table.size(), (3 + 5 + 17 + 2, 6));
+        table.on_event(&key(Key::Down));
+        table.set_focused(true);
+        let buffer = render(&table, 24, 6);
+        assert_snapshot("table", &buffer);
+        assert!(buffer.get(20, 2).unwrap().style.reverse);
+
+        // * 只能显示两行数据时, 滚动到选中的行
+        table.on_event(&key(Key::End));
+        assert_eq!(table.selected(), Some(3));
+        assert_eq!(
+            render(&table, 24, 4).lines()[2..],
+            ["3   carol c@example.com", "4   dave"]
+        );
+        table.on_event(&click(5, 2));
+        assert_eq!(table.selected(), Some(2));
+        assert!(!table.on_event(&click(5, 1)));
+    }
+
+    #[test]
+    fn scroll_views_show_part_of_their_content() {
+        let mut view = ScrollView::new(Box::new(table()), 20, 4);
+        render(&view, 20, 4);
+        // * 内容是 Widget 时先交给内容处理, 选中的行还在视图中, 不需要滚动
+        assert!(view.on_event(&key(Key::Down)));
+        assert_eq!(view.offset(), 0);
+        // * 选中的行移出视图时, 视图跟着滚动
+        assert!(view.on_event(&Event::Mouse(Mouse {
+            kind: MouseKind::ScrollDown,
+            x: 0,
+            y: 0,
+        })));
+        assert!(view.on_event(&key(Key::Down)));
+        assert_eq!(view.offset(), 1);
+        assert!(view.on_event(&key(Key::End)));
+        assert_eq!(view.offset(), 2);
+        assert_eq!(
+            render(&view, 20, 4).lines()[2..],
+            ["3   carol c@example█", "4   dave           █"]
+        );
+        // * 点击的坐标换算成内容中的坐标, 点中的行已经可见
+        assert!(view.on_event(&click(0, 2)));
+        assert_eq!(view.offset(), 2);
+        assert!(render(&view, 20, 4).get(0, 2).unwrap().style.bold);
+
+        let mut view = ScrollView::new(
+            Box::new(Label::new("one two three four five six").width(5)),
+            8,
+            3,
+        );
+        render(&view, 8, 3);
+        view.on_event(&key(Key::Down));
+        assert_eq!(view.offset(), 1);
+        view.on_event(&key(Key::End));
+        assert_eq!(view.offset(), 3);
+        assert_snapshot("scroll_view", &render(&view, 8, 3));
+        view.on_event(&key(Key::Home));
+        assert_eq!(
+            render(&view, 8, 3).lines(),
+            vec!["one    █", "two    │", "three  │"]
+        );
+    }
+
+    #[test]
+    fn mixes_widgets_in_a_screen() {
+        let mut screen = Screen::new(vec![
+            Box::new(Label::new("Sign up").style(Style::new().bold())),
+            Box::new(TextField::new(12).placeholder("user name")),
+            Box::new(Checkbox::new("Subscribe")),
+            Box::new(ProgressBar::new(16)),
+        ]);
+        let mut backend = TestBackend::new(16, 4);
+        let input: &[u8] = b"bob\t ";
+        screen.run_events(&mut backend, input).unwrap();
+        assert_eq!(screen.focused(), Some(2));
+        assert_snapshot("widgets", backend.buffer());
+    }
+}
@@ -0,0 +1,171 @@
+// * 不依赖第三方库的简单基准测试
+// + 每个用例先预热几次, 再重复运行多次, 统计每次耗时的均值、中位数、标准差和最小值
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub runs: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    pub std_dev: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    // * 根据每次运行的耗时计算统计结果, samples 不能为空
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        assert!(!samples.is_empty(), "至少需要一次运行结果");
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let runs = sorted.len();
+        let nanos: Vec<f64> = sorted.iter().map(|d| d.as_nanos() as f64).collect();
+        let mean = nanos.iter().sum::<f64>() / runs as f64;
+        let variance = nanos.iter().map(|n| (n - mean).powi(2)).sum::<f64>() / runs as f64;
+        // ? 偶数个样本时取中间两个的平均值
+        let median = if runs.is_multiple_of(2) {
+            (sorted[runs / 2 - 1] + sorted[runs / 2]) / 2
+        } else {
+            sorted[runs / 2]
+        };
+        Stats {
+            runs,
+            mean: Duration::from_nanos(mean as u64),
+            median,
+            std_dev: Duration::from_nanos(variance.sqrt() as u64),
+            min: sorted[0],
+            max: sorted[runs - 1],
+        }
+    }
+
+    // * 每秒处理多少百万个元素, 以中位数计算, 不容易受偶尔的系统抖动影响
+    pub fn throughput(&self, elements: usize) -> f64 {
+        let secs = self.median.as_secs_f64();
+        if secs == 0.0 {
+            return f64::INFINITY;
+        }
+        elements as f64 / secs / 1_000_000.0
+    }
+}
+
+// * 运行 warmup 次预热后, 再运行 runs 次并计时
+// + setup 的耗时不计入结果, 例如每次运行前把残差复制到新的 buffer 里
+pub fn measure<S, T, F>(warmup: usize, runs: usize, mut setup: S, mut routine: F) -> Stats
+where
+    S: FnMut() -> T,
+    F: FnMut(&mut T),
+{
+    for _ in 0..warmup {
+        let mut input = setup();
+        routine(&mut input);
+        std::hint::black_box(&input);
+    }
+    let samples: Vec<Duration> = (0..runs.max(1))
+        .map(|_| {
+            let mut input = setup();
+            let start = Instant::now();
+            routine(std::hint::black_box(&mut input));
+            let elapsed = start.elapsed();
+            // ? black_box 防止编译器发现结果没被使用, 把整个计算优化掉
+            std::hint::black_box(&input);
+            elapsed
+        })
+        .collect();
+    Stats::from_samples(&samples)
+}
+
+// * 结果表格中的一行
+pub struct Row {
+    pub name: String,
+    pub size: usize,
+    pub stats: Stats,
+}
+
+// * 把结果打印成对齐的表格
+pub struct Table {
+    pub rows: Vec<Row>,
+}
+
+fn micros(d: Duration) -> String {
+    format!("{:.1}", d.as_secs_f64() * 1_000_000.0)
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<10} {:>10} {:>6} {:>12} {:>12} {:>12} {:>12} {:>10}",
+            "kernel", "size", "runs", "mean(us)", "median(us)", "stddev(us)", "min(us)", "Melem/s"
+        )?;
+        writeln!(f, "{}", "-".repeat(92))?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:<10} {:>10} {:>6} {:>12} {:>12} {:>12} {:>12} {:>10.1}",
+                row.name,
+                row.size,
+                row.stats.runs,
+                micros(row.stats.mean),
+                micros(row.stats.median),
+                micros(row.stats.std_dev),
+                micros(row.stats.min),
+                row.stats.throughput(row.size)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_from_samples() {
+        let samples: Vec<Duration> = [4, 1, 3, 2]
+            .iter()
+            .map(|&n| Duration::from_micros(n))
+            .collect();
+        let stats = Stats::from_samples(&samples);
+        assert_eq!(stats.runs, 4);
+        assert_eq!(stats.min, Duration::from_micros(1));
+        assert_eq!(stats.max, Duration::from_micros(4));
+        assert_eq!(stats.median, Duration::from_nanos(2500));
+        assert_eq!(stats.mean, Duration::from_nanos(2500));
+        // ? sqrt(1.25) * 1000 ≈ 1118
+        assert_eq!(stats.std_dev, Duration::from_nanos(1118));
+        assert!((stats.throughput(2500) - 1000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn measure_runs_setup_every_time() {
+        let mut setups = 0;
+        let stats = measure(
+            2,
+            5,
+            || {
+                setups += 1;
+                vec![0u8; 16]
+            },
+            |v| v[0] += 1,
+        );
+        assert_eq!(setups, 7);
+        assert_eq!(stats.runs, 5);
+    }
+
+    #[test]
+    fn table_has_one_line_per_row() {
+        let stats = Stats::from_samples(&[Duration::from_micros(10)]);
+        let table = Table {
+            rows: vec![Row {
+                name: String::from("iter"),
+                size: 1000,
+                stats,
+            }],
+        };
+        let text = table.to_string();
+        assert_eq!(text.lines().count(), 3);
+        assert!(text.lines().nth(2).unwrap().starts_with("iter"));
+    }
+}
@@ -0,0 +1,36 @@
+// * 把 FLAC 文件解码成 WAV
+// + 用法: cargo run --release --bin flac2wav -- input.flac output.wav
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process;
+
+use test_iterator_performance::flac;
+
+fn run(input: &str, output: &str) -> Result<(), Box<dyn Error>> {
+    let stream = flac::decode_file(input)?;
+    stream.write_wav(BufWriter::new(File::create(output)?))?;
+    println!(
+        "{} -> {}: {} 声道, {} Hz, {} 位, {} 个样本",
+        input,
+        output,
+        stream.info.channels,
+        stream.info.sample_rate,
+        stream.info.bits_per_sample,
+        stream.len()
+    );
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("用法: flac2wav <input.flac> <output.wav>");
+        process::exit(2);
+    }
+    if let Err(e) = run(&args[1], &args[2]) {
+        eprintln!("解码失败: {}", e);
+        process::exit(1);
+    }
+}
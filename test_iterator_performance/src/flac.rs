@@ -0,0 +1,224 @@
+// * FLAC 解码
+// + main.rs 里测试的那段循环, 就是 FLAC 解码时恢复 LPC 残差的部分, 这里把它补全成一个完整的解码器
+// + 文件结构: "fLaC" 标记 -> 元数据块(第一个必须是 STREAMINFO) -> 一个接一个的音频帧
+// + 解码时会校验每个帧头的 CRC-8、每个帧的 CRC-16, 以及全部样本的 MD5
+// ? 只使用标准库, 支持最多 8 个声道、4 到 24 位的采样
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+pub mod bit_reader;
+pub mod crc;
+pub mod frame;
+pub mod md5;
+pub mod metadata;
+pub mod wav;
+
+pub use frame::{ChannelAssignment, Frame, FrameHeader};
+pub use metadata::StreamInfo;
+
+#[derive(Debug)]
+pub enum FlacError {
+    Io(io::Error),
+    // * 文件不是以 "fLaC" 开头
+    InvalidMarker,
+    InvalidMetadata(&'static str),
+    InvalidFrame(&'static str),
+    HeaderCrcMismatch,
+    FrameCrcMismatch,
+    Md5Mismatch,
+    Unsupported(&'static str),
+    UnexpectedEof,
+}
+
+impl fmt::Display for FlacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlacError::Io(e) => write!(f, "读取文件失败: {}", e),
+            FlacError::InvalidMarker => write!(f, "不是 FLAC 文件"),
+            FlacError::InvalidMetadata(msg) => write!(f, "元数据错误: {}", msg),
+            FlacError::InvalidFrame(msg) => write!(f, "音频帧错误: {}", msg),
+            FlacError::HeaderCrcMismatch => write!(f, "帧头 CRC-8 校验失败"),
+            FlacError::FrameCrcMismatch => write!(f, "音频帧 CRC-16 校验失败"),
+            FlacError::Md5Mismatch => write!(f, "解码结果的 MD5 和 STREAMINFO 中的不一致"),
+            FlacError::Unsupported(msg) => write!(f, "不支持: {}", msg),
+            FlacError::UnexpectedEof => write!(f, "文件意外结束"),
+        }
+    }
+}
+
+impl Error for FlacError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FlacError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FlacError {
+    fn from(e: io::Error) -> Self {
+        FlacError::Io(e)
+    }
+}
+
+// * 解码后的整段音频
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlacStream {
+    pub info: StreamInfo,
+    // * 各声道交错排列的样本: L0 R0 L1 R1 ...
+    pub samples: Vec<i32>,
+}
+
+impl FlacStream {
+    // * 每个声道的样本数
+    pub fn len(&self) -> usize {
+        self.samples.len() / self.info.channels as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // * 取出某一个声道的样本
+    pub fn channel(&self, index: usize) -> impl Iterator<Item = i32> + '_ {
+        self.samples
+            .iter()
+            .skip(index)
+            .step_by(self.info.channels as usize)
+            .copied()
+    }
+
+    pub fn write_wav<W: Write>(&self, writer: W) -> io::Result<()> {
+        wav::write(writer, &self.info, &self.samples)
+    }
+}
+
+// * 解码整个文件, 并校验 MD5
+pub fn decode(data: &[u8]) -> Result<FlacStream, FlacError> {
+    let (info, mut pos) = metadata::read_header(data)?;
+    let channels = info.channels as usize;
+    // ? total_samples 来自文件头, 可能是伪造的, 预留的空间不超过输入的字节数, 不够时 Vec 会自己增长
+    let expected = (info.total_samples as usize).saturating_mul(channels);
+    let mut samples = Vec::with_capacity(expected.min(data.len()));
+    let mut md5 = md5::Md5::new();
+    let width = info.bits_per_sample.div_ceil(8) as usize;
+
+    // ? total_samples 为 0 表示未知, 这时一直解码到文件结束; 否则解码够了就停下, 忽略文件末尾的标签等数据
+    while pos < data.len()
+        && (info.total_samples == 0 || ((samples.len() / channels) as u64) < info.total_samples)
+    {
+        let (frame, len) = frame::decode_frame(&data[pos..], &info)?;
+        pos += len;
+        for i in 0..frame.header.block_size as usize {
+            for channel in &frame.channels {
+                let sample = channel[i];
+                // * MD5 按小端序、每个样本占整数个字节计算
+                md5.update(&sample.to_le_bytes()[..width]);
+                samples.push(sample);
+            }
+        }
+    }
+
+    if info.total_samples != 0 && (samples.len() / channels) as u64 != info.total_samples {
+        return Err(FlacError::InvalidFrame(
+            "解码出的样本数和 STREAMINFO 不一致",
+        ));
+    }
+    if info.has_md5() && md5.finalize() != info.md5 {
+        return Err(FlacError::Md5Mismatch);
+    }
+    Ok(FlacStream { info, samples })
+}
+
+pub fn decode_file<P: AsRef<Path>>(path: P) -> Result<FlacStream, FlacError> {
+    decode(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // * fixtures 下的文件都很小, 覆盖了所有子帧类型和立体声去相关方式, STREAMINFO 中带有 MD5
+    const MONO16: &[u8] = include_bytes!("../fixtures/mono16.flac");
+    const STEREO16: &[u8] = include_bytes!("../fixtures/stereo16.flac");
+    const MONO8: &[u8] = include_bytes!("../fixtures/mono8.flac");
+
+    #[test]
+    fn decodes_mono_fixture() {
+        // * 包含 wasted bits、VERBATIM、带转义分区的 8 阶 LPC 和 CONSTANT 子帧
+        let stream = decode(MONO16).unwrap();
+        assert_eq!(stream.info.sample_rate, 44100);
+        assert_eq!(stream.len(), 4000);
+        assert_eq!(stream.samples[..6], [0, 752, 1504, 2248, 2984, 3708]);
+        // * 最后一块是 CONSTANT 子帧
+        assert!(stream.samples[3072..].iter().all(|&s| s == -7));
+    }
+
+    #[test]
+    fn decodes_every_stereo_decorrelation() {
+        // * 四个帧分别是独立、left/side、side/right、mid/side
+        let stream = decode(STEREO16).unwrap();
+        assert_eq!(stream.info.channels, 2);
+        assert_eq!(stream.len(), 1900);
+        assert_eq!(stream.samples[..6], [10, 3366, 703, 3709, 1422, 3942]);
+        assert_eq!(stream.channel(0).count(), 1900);
+        assert_eq!(
+            stream.channel(1).take(3).collect::<Vec<_>>(),
+            vec![3366, 3709, 3942]
+        );
+    }
+
+    #[test]
+    fn decodes_8_bit_fixture() {
+        let stream = decode(MONO8).unwrap();
+        assert_eq!(stream.info.sample_rate, 8000);
+        assert_eq!(stream.info.bits_per_sample, 8);
+        assert_eq!(stream.samples[..6], [0, 16, 31, 45, 59, 71]);
+    }
+
+    #[test]
+    fn detects_corruption() {
+        // * 改动第二个帧(VERBATIM 子帧)中的一个样本, CRC-16 会校验失败
+        let (info, start) = metadata::read_header(MONO16).unwrap();
+        let (_, first_len) = frame::decode_frame(&MONO16[start..], &info).unwrap();
+        let mut data = MONO16.to_vec();
+        data[start + first_len + 100] ^= 0x10;
+        assert!(matches!(decode(&data), Err(FlacError::FrameCrcMismatch)));
+
+        // * 改动第一个帧头的帧号, CRC-8 会校验失败
+        let mut data = MONO16.to_vec();
+        data[54 + 4] ^= 0x01;
+        assert!(matches!(decode(&data), Err(FlacError::HeaderCrcMismatch)));
+
+        // * 改动 STREAMINFO 中的 MD5
+        let mut data = MONO16.to_vec();
+        data[8 + 18] ^= 0xFF;
+        assert!(matches!(decode(&data), Err(FlacError::Md5Mismatch)));
+
+        assert!(matches!(
+            decode(&MONO16[..MONO16.len() - 100]),
+            Err(FlacError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn does_not_trust_total_samples_for_allocation() {
+        // * STREAMINFO 从第 8 个字节开始, 第 10 个字节之后是 20 位采样率、3 位声道数、5 位位数和 36 位样本总数
+        let mut forged = MONO16.to_vec();
+        forged[21] |= 0x0F;
+        forged[22..26].fill(0xFF);
+        let (info, _) = metadata::read_header(&forged).unwrap();
+        assert_eq!(info.total_samples, (1 << 36) - 1);
+        assert!(matches!(decode(&forged), Err(FlacError::InvalidFrame(_))));
+    }
+
+    #[test]
+    fn decode_file_reports_io_errors() {
+        let err = decode_file("fixtures/does-not-exist.flac").unwrap_err();
+        assert!(matches!(err, FlacError::Io(_)));
+        assert!(err.source().is_some());
+    }
+}
@@ -0,0 +1,100 @@
+// * 按位读取字节流, FLAC 中的字段都是大端序、按位紧密排列的
+use super::FlacError;
+
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    // * 当前读到第几个 bit
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data, pos: 0 }
+    }
+
+    // * 读取 n 个 bit 组成的无符号整数, n 最多为 32
+    pub fn read_bits(&mut self, n: u32) -> Result<u32, FlacError> {
+        debug_assert!(n <= 32);
+        if self.pos + n as usize > self.data.len() * 8 {
+            return Err(FlacError::UnexpectedEof);
+        }
+        let mut value: u64 = 0;
+        let mut remaining = n;
+        while remaining > 0 {
+            let byte = self.data[self.pos / 8];
+            let offset = (self.pos % 8) as u32;
+            // ? 当前字节中还剩下多少个 bit 没读, 一次最多读完这些
+            let take = (8 - offset).min(remaining);
+            let bits = (byte >> (8 - offset - take)) & ((1u16 << take) - 1) as u8;
+            value = (value << take) | bits as u64;
+            self.pos += take as usize;
+            remaining -= take;
+        }
+        Ok(value as u32)
+    }
+
+    // * 读取 n 个 bit 组成的有符号整数(二进制补码)
+    pub fn read_signed(&mut self, n: u32) -> Result<i32, FlacError> {
+        if n == 0 {
+            return Ok(0);
+        }
+        let value = self.read_bits(n)?;
+        // ? 先把符号位移动到最高位, 再算术右移回来, 就完成了符号扩展
+        let shift = 32 - n;
+        Ok(((value << shift) as i32) >> shift)
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, FlacError> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    // * 一元编码: 数出连续的 0 的个数, 直到遇到 1
+    pub fn read_unary(&mut self) -> Result<u32, FlacError> {
+        let mut count = 0;
+        while !self.read_bit()? {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    // * 跳到下一个字节的开头
+    pub fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.pos.is_multiple_of(8)
+    }
+
+    // * 当前位置所在的字节下标, 只有在字节对齐时才有意义
+    pub fn byte_pos(&self) -> usize {
+        self.pos / 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_across_byte_boundaries() {
+        let mut reader = BitReader::new(&[0b1010_1100, 0b0011_1111, 0xFF]);
+        assert_eq!(reader.read_bits(3).unwrap(), 0b101);
+        assert_eq!(reader.read_bits(7).unwrap(), 0b011_0000);
+        assert_eq!(reader.read_signed(4).unwrap(), -1);
+        assert!(!reader.is_aligned());
+        reader.align();
+        assert_eq!(reader.byte_pos(), 2);
+        assert_eq!(reader.read_bits(8).unwrap(), 0xFF);
+        assert!(matches!(reader.read_bit(), Err(FlacError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn reads_unary_and_signed_values() {
+        let mut reader = BitReader::new(&[0b0001_1111, 0b0000_0010]);
+        assert_eq!(reader.read_unary().unwrap(), 3);
+        assert_eq!(reader.read_signed(5).unwrap(), -2);
+        assert_eq!(reader.read_bits(0).unwrap(), 0);
+        assert_eq!(reader.read_unary().unwrap(), 5);
+    }
+}
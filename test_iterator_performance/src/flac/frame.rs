@@ -0,0 +1,438 @@
+// * 音频帧: 帧头 + 每个声道一个子帧 + CRC-16
+// + 子帧有四种: CONSTANT(整块都是同一个值)、VERBATIM(未压缩)、FIXED(固定系数预测)、LPC(线性预测)
+// + FIXED 和 LPC 都是 "预测值 + 残差", 残差使用 Rice 编码
+use super::bit_reader::BitReader;
+use super::crc::{crc16, crc8};
+use super::metadata::StreamInfo;
+use super::FlacError;
+use crate::lpc;
+
+// * 支持的最大采样位数, 立体声的 side 声道会多 1 位, 这样所有计算都能放在 i32 里
+pub const MAX_BITS_PER_SAMPLE: u8 = 24;
+
+// * 多声道之间的去相关方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelAssignment {
+    // * 每个声道单独编码, 参数为声道数
+    Independent(u8),
+    // * 左声道 + side(左 - 右)
+    LeftSide,
+    // * side + 右声道
+    SideRight,
+    // * mid(左右平均) + side
+    MidSide,
+}
+
+impl ChannelAssignment {
+    pub fn channels(&self) -> u8 {
+        match self {
+            ChannelAssignment::Independent(n) => *n,
+            _ => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub variable_block_size: bool,
+    pub block_size: u32,
+    pub sample_rate: u32,
+    pub channel_assignment: ChannelAssignment,
+    pub bits_per_sample: u8,
+    // * 固定块大小时是帧号, 可变块大小时是这一帧第一个样本的编号
+    pub number: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub header: FrameHeader,
+    // * 已经完成去相关的各声道样本
+    pub channels: Vec<Vec<i32>>,
+}
+
+// * 解码 data 开头的一个音频帧, 返回帧和它占用的字节数
+pub fn decode_frame(data: &[u8], info: &StreamInfo) -> Result<(Frame, usize), FlacError> {
+    let mut reader = BitReader::new(data);
+    let header = read_header(&mut reader, data, info)?;
+
+    let mut channels = Vec::with_capacity(header.channel_assignment.channels() as usize);
+    for channel in 0..header.channel_assignment.channels() {
+        // ? side 声道是两个声道的差, 需要多 1 位
+        let is_side = matches!(
+            (header.channel_assignment, channel),
+            (ChannelAssignment::LeftSide, 1)
+                | (ChannelAssignment::SideRight, 0)
+                | (ChannelAssignment::MidSide, 1)
+        );
+        let bits = header.bits_per_sample as u32 + is_side as u32;
+        channels.push(decode_subframe(
+            &mut reader,
+            bits,
+            header.block_size as usize,
+        )?);
+    }
+
+    // * 帧尾: 补齐到整字节, 然后是整个帧(不含 CRC 本身)的 CRC-16
+    // + 先校验再还原声道, 损坏的帧不会进入后面的计算
+    reader.align();
+    let end = reader.byte_pos();
+    let expected = reader.read_bits(16)? as u16;
+    if crc16(&data[..end]) != expected {
+        return Err(FlacError::FrameCrcMismatch);
+    }
+    decorrelate(header.channel_assignment, &mut channels)?;
+    Ok((Frame { header, channels }, end + 2))
+}
+
+fn read_header(
+    reader: &mut BitReader,
+    data: &[u8],
+    info: &StreamInfo,
+) -> Result<FrameHeader, FlacError> {
+    if reader.read_bits(14)? != 0b11_1111_1111_1110 {
+        return Err(FlacError::InvalidFrame("找不到帧同步码"));
+    }
+    if reader.read_bit()? {
+        return Err(FlacError::InvalidFrame("帧头保留位必须为 0"));
+    }
+    let variable_block_size = reader.read_bit()?;
+    let block_size_code = reader.read_bits(4)?;
+    let sample_rate_code = reader.read_bits(4)?;
+    let channel_assignment = match reader.read_bits(4)? {
+        n @ 0..=7 => ChannelAssignment::Independent(n as u8 + 1),
+        8 => ChannelAssignment::LeftSide,
+        9 => ChannelAssignment::SideRight,
+        10 => ChannelAssignment::MidSide,
+        _ => return Err(FlacError::InvalidFrame("保留的声道分配方式")),
+    };
+    let bits_per_sample = match reader.read_bits(3)? {
+        0 => info.bits_per_sample,
+        1 => 8,
+        2 => 12,
+        4 => 16,
+        5 => 20,
+        6 => 24,
+        7 => return Err(FlacError::Unsupported("32 位采样")),
+        _ => return Err(FlacError::InvalidFrame("保留的采样位数")),
+    };
+    if reader.read_bit()? {
+        return Err(FlacError::InvalidFrame("帧头保留位必须为 0"));
+    }
+    let number = read_utf8_number(reader)?;
+
+    let block_size = match block_size_code {
+        0 => return Err(FlacError::InvalidFrame("保留的块大小")),
+        1 => 192,
+        2..=5 => 576 << (block_size_code - 2),
+        6 => reader.read_bits(8)? + 1,
+        7 => reader.read_bits(16)? + 1,
+        _ => 256 << (block_size_code - 8),
+    };
+    let sample_rate = match sample_rate_code {
+        0 => info.sample_rate,
+        1 => 88_200,
+        2 => 176_400,
+        3 => 192_000,
+        4 => 8_000,
+        5 => 16_000,
+        6 => 22_050,
+        7 => 24_000,
+        8 => 32_000,
+        9 => 44_100,
+        10 => 48_000,
+        11 => 96_000,
+        12 => reader.read_bits(8)? * 1000,
+        13 => reader.read_bits(16)?,
+        14 => reader.read_bits(16)? * 10,
+        _ => return Err(FlacError::InvalidFrame("非法的采样率")),
+    };
+
+    // * 帧头最后一个字节是前面所有字节的 CRC-8
+    let end = reader.byte_pos();
+    if crc8(&data[..end]) != reader.read_bits(8)? as u8 {
+        return Err(FlacError::HeaderCrcMismatch);
+    }
+
+    if channel_assignment.channels() != info.channels {
+        return Err(FlacError::InvalidFrame("声道数和 STREAMINFO 不一致"));
+    }
+    if bits_per_sample != info.bits_per_sample {
+        return Err(FlacError::InvalidFrame("采样位数和 STREAMINFO 不一致"));
+    }
+    if bits_per_sample > MAX_BITS_PER_SAMPLE {
+        return Err(FlacError::Unsupported("超过 24 位的采样"));
+    }
+    Ok(FrameHeader {
+        variable_block_size,
+        block_size,
+        sample_rate,
+        channel_assignment,
+        bits_per_sample,
+        number,
+    })
+}
+
+// * 帧号使用类似 UTF-8 的变长编码, 最多 7 个字节 36 位
+fn read_utf8_number(reader: &mut BitReader) -> Result<u64, FlacError> {
+    let first = reader.read_bits(8)?;
+    // ? 开头有几个 1 就表示一共有几个字节
+    let len = (first as u8).leading_ones();
+    let (mut value, extra) = match len {
+        0 => return Ok(first as u64),
+        2..=7 => ((first & (0x7F >> len)) as u64, len - 1),
+        _ => return Err(FlacError::InvalidFrame("非法的帧号编码")),
+    };
+    for _ in 0..extra {
+        let byte = reader.read_bits(8)?;
+        if byte & 0xC0 != 0x80 {
+            return Err(FlacError::InvalidFrame("非法的帧号编码"));
+        }
+        value = (value << 6) | (byte & 0x3F) as u64;
+    }
+    Ok(value)
+}
+
+fn decode_subframe(
+    reader: &mut BitReader,
+    bits: u32,
+    block_size: usize,
+) -> Result<Vec<i32>, FlacError> {
+    if reader.read_bit()? {
+        return Err(FlacError::InvalidFrame("子帧填充位必须为 0"));
+    }
+    let kind = reader.read_bits(6)?;
+    // * wasted bits: 所有样本的低 k 位都是 0, 编码时先去掉, 解码后再左移回来
+    let wasted = if reader.read_bit()? {
+        reader.read_unary()? + 1
+    } else {
+        0
+    };
+    if wasted >= bits {
+        return Err(FlacError::InvalidFrame("wasted bits 超过了采样位数"));
+    }
+    let bits = bits - wasted;
+
+    let mut samples = match kind {
+        0b000000 => vec![reader.read_signed(bits)?; block_size],
+        0b000001 => (0..block_size)
+            .map(|_| reader.read_signed(bits))
+            .collect::<Result<_, _>>()?,
+        0b001000..=0b001100 => {
+            let order = (kind & 0b111) as usize;
+            decode_fixed(reader, bits, block_size, order)?
+        }
+        0b100000..=0b111111 => {
+            let order = (kind & 0b11111) as usize + 1;
+            decode_lpc(reader, bits, block_size, order)?
+        }
+        _ => return Err(FlacError::InvalidFrame("保留的子帧类型")),
+    };
+    if wasted > 0 {
+        for sample in samples.iter_mut() {
+            *sample <<= wasted;
+        }
+    }
+    Ok(samples)
+}
+
+// * 先读出 order 个不经过预测的预热样本
+fn read_warm_up(
+    reader: &mut BitReader,
+    bits: u32,
+    block_size: usize,
+    order: usize,
+) -> Result<Vec<i32>, FlacError> {
+    if order > block_size {
+        return Err(FlacError::InvalidFrame("预测阶数大于块大小"));
+    }
+    let mut buffer = Vec::with_capacity(block_size);
+    for _ in 0..order {
+        buffer.push(reader.read_signed(bits)?);
+    }
+    Ok(buffer)
+}
+
+// * 固定预测的系数, 按 "最早的样本在前" 排列, 和 lpc::restore 的约定一致
+// ? 例如二阶: 2 * s[i-1] - s[i-2]
+const FIXED_COEFFICIENTS: [&[i64]; 5] = [&[], &[1], &[-1, 2], &[1, -3, 3], &[-1, 4, -6, 4]];
+
+fn decode_fixed(
+    reader: &mut BitReader,
+    bits: u32,
+    block_size: usize,
+    order: usize,
+) -> Result<Vec<i32>, FlacError> {
+    let mut buffer = read_warm_up(reader, bits, block_size, order)?;
+    decode_residual(reader, block_size, order, &mut buffer)?;
+    lpc::restore(FIXED_COEFFICIENTS[order], 0, &mut buffer);
+    Ok(buffer)
+}
+
+fn decode_lpc(
+    reader: &mut BitReader,
+    bits: u32,
+    block_size: usize,
+    order: usize,
+) -> Result<Vec<i32>, FlacError> {
+    let mut buffer = read_warm_up(reader, bits, block_size, order)?;
+    let precision = reader.read_bits(4)? + 1;
+    if precision == 16 {
+        return Err(FlacError::InvalidFrame("非法的系数精度"));
+    }
+    let shift = reader.read_signed(5)?;
+    if shift < 0 {
+        return Err(FlacError::InvalidFrame("系数移位不能为负数"));
+    }
+    // * 文件中第一个系数对应最近的样本, 这里反过来, 以便直接使用 lpc::restore
+    let mut coefficients = (0..order)
+        .map(|_| reader.read_signed(precision).map(i64::from))
+        .collect::<Result<Vec<_>, _>>()?;
+    coefficients.reverse();
+    decode_residual(reader, block_size, order, &mut buffer)?;
+    lpc::restore(&coefficients, shift as i16, &mut buffer);
+    Ok(buffer)
+}
+
+// * 读取 Rice 编码的残差, 追加到 buffer 的预热样本之后
+// + 残差被分成 2^partition_order 个分区, 每个分区有自己的 Rice 参数
+fn decode_residual(
+    reader: &mut BitReader,
+    block_size: usize,
+    order: usize,
+    buffer: &mut Vec<i32>,
+) -> Result<(), FlacError> {
+    let param_bits = match reader.read_bits(2)? {
+        0 => 4,
+        1 => 5,
+        _ => return Err(FlacError::InvalidFrame("保留的残差编码方式")),
+    };
+    let escape = (1 << param_bits) - 1;
+    let partition_order = reader.read_bits(4)?;
+    let partitions = 1usize << partition_order;
+    if !block_size.is_multiple_of(partitions) || block_size / partitions < order {
+        return Err(FlacError::InvalidFrame("残差分区大小不合法"));
+    }
+    for partition in 0..partitions {
+        // ? 第一个分区要扣掉预热样本
+        let len = block_size / partitions - if partition == 0 { order } else { 0 };
+        let param = reader.read_bits(param_bits)?;
+        if param == escape {
+            // * 转义: 这个分区的残差直接用固定位数存储
+            let raw_bits = reader.read_bits(5)?;
+            for _ in 0..len {
+                buffer.push(reader.read_signed(raw_bits)?);
+            }
+            continue;
+        }
+        for _ in 0..len {
+            let quotient = reader.read_unary()?;
+            if quotient as u64 >= 1u64 << (32 - param) {
+                return Err(FlacError::InvalidFrame("Rice 编码的残差溢出"));
+            }
+            let folded = (quotient << param) | reader.read_bits(param)?;
+            // ? 残差做过 zigzag 折叠: 0, -1, 1, -2, 2 ... 依次对应 0, 1, 2, 3, 4 ...
+            buffer.push((folded >> 1) as i32 ^ -((folded & 1) as i32));
+        }
+    }
+    Ok(())
+}
+
+// * 把 side / mid 声道还原成左右声道
+// + 结果超出 i32 时说明数据有问题(CRC 碰巧通过, 或者编码器本身有错), 返回错误而不是溢出
+fn decorrelate(assignment: ChannelAssignment, channels: &mut [Vec<i32>]) -> Result<(), FlacError> {
+    let overflow = || FlacError::InvalidFrame("还原左右声道时样本溢出");
+    let (first, second) = match channels {
+        [first, second] => (first, second),
+        _ => return Ok(()),
+    };
+    match assignment {
+        ChannelAssignment::Independent(_) => {}
+        ChannelAssignment::LeftSide => {
+            // * right = left - side
+            for (left, side) in first.iter().zip(second.iter_mut()) {
+                *side = left.checked_sub(*side).ok_or_else(overflow)?;
+            }
+        }
+        ChannelAssignment::SideRight => {
+            // * left = side + right
+            for (side, right) in first.iter_mut().zip(second.iter()) {
+                *side = side.checked_add(*right).ok_or_else(overflow)?;
+            }
+        }
+        ChannelAssignment::MidSide => {
+            // * mid 在编码时右移丢掉了最低位, 这一位和 side 的最低位相同
+            for (mid, side) in first.iter_mut().zip(second.iter_mut()) {
+                let m = mid.checked_mul(2).ok_or_else(overflow)? | (*side & 1);
+                let s = *side;
+                *mid = m.checked_add(s).ok_or_else(overflow)? >> 1;
+                *side = m.checked_sub(s).ok_or_else(overflow)? >> 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_utf8_numbers() {
+        let mut reader = BitReader::new(&[0x7F, 0xC2, 0x80, 0xE1, 0x80, 0x81]);
+        assert_eq!(read_utf8_number(&mut reader).unwrap(), 0x7F);
+        assert_eq!(read_utf8_number(&mut reader).unwrap(), 0x80);
+        assert_eq!(read_utf8_number(&mut reader).unwrap(), 0x1001);
+        let mut reader = BitReader::new(&[0x80]);
+        assert!(read_utf8_number(&mut reader).is_err());
+    }
+
+    #[test]
+    fn decorrelates_stereo_channels() {
+        let left = vec![10, -3, 7];
+        let right = vec![4, 5, -8];
+        let side: Vec<i32> = left.iter().zip(&right).map(|(l, r)| l - r).collect();
+        let mid: Vec<i32> = left.iter().zip(&right).map(|(l, r)| (l + r) >> 1).collect();
+
+        let mut channels = vec![left.clone(), side.clone()];
+        decorrelate(ChannelAssignment::LeftSide, &mut channels).unwrap();
+        assert_eq!(channels, vec![left.clone(), right.clone()]);
+
+        let mut channels = vec![side.clone(), right.clone()];
+        decorrelate(ChannelAssignment::SideRight, &mut channels).unwrap();
+        assert_eq!(channels, vec![left.clone(), right.clone()]);
+
+        let mut channels = vec![mid, side];
+        decorrelate(ChannelAssignment::MidSide, &mut channels).unwrap();
+        assert_eq!(channels, vec![left, right]);
+
+        // * 损坏的数据会让结果超出 i32, 这时返回错误
+        for (assignment, second) in [
+            (ChannelAssignment::LeftSide, i32::MIN),
+            (ChannelAssignment::SideRight, i32::MAX),
+            (ChannelAssignment::MidSide, 0),
+        ] {
+            let mut channels = vec![vec![i32::MAX], vec![second]];
+            assert!(matches!(
+                decorrelate(assignment, &mut channels),
+                Err(FlacError::InvalidFrame(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn decodes_rice_residuals() {
+        // * 参数 1 的一个分区, 4 个残差: 0 -> "1 0", -1 -> "1 1", 1 -> "01 0", -2 -> "01 1"
+        // ? 2 bit 方法 + 4 bit 分区阶数 + 4 bit 参数 = 00 0000 0001, 后面接残差 10 11 010 011
+        let bits = "0000000001".to_owned() + "1011010011";
+        let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+        for (i, bit) in bits.chars().enumerate() {
+            if bit == '1' {
+                bytes[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        let mut reader = BitReader::new(&bytes);
+        let mut buffer = Vec::new();
+        decode_residual(&mut reader, 4, 0, &mut buffer).unwrap();
+        assert_eq!(buffer, vec![0, -1, 1, -2]);
+    }
+}
@@ -0,0 +1,141 @@
+// * 文件开头的 "fLaC" 标记和元数据块
+// + 每个元数据块的头部: 1 bit 是否为最后一块, 7 bit 类型, 24 bit 长度
+// + 第一个元数据块必须是 STREAMINFO, 其余的块(填充、注释、封面等)解码时用不到, 直接跳过
+use super::bit_reader::BitReader;
+use super::FlacError;
+
+const STREAMINFO: u8 = 0;
+const STREAMINFO_LEN: usize = 34;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamInfo {
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+    pub min_frame_size: u32,
+    pub max_frame_size: u32,
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub bits_per_sample: u8,
+    // * 每个声道的样本数, 为 0 表示未知
+    pub total_samples: u64,
+    // * 全 0 表示编码器没有计算 MD5
+    pub md5: [u8; 16],
+}
+
+impl StreamInfo {
+    fn parse(data: &[u8]) -> Result<StreamInfo, FlacError> {
+        let mut reader = BitReader::new(data);
+        let min_block_size = reader.read_bits(16)? as u16;
+        let max_block_size = reader.read_bits(16)? as u16;
+        let min_frame_size = reader.read_bits(24)?;
+        let max_frame_size = reader.read_bits(24)?;
+        let sample_rate = reader.read_bits(20)?;
+        let channels = reader.read_bits(3)? as u8 + 1;
+        let bits_per_sample = reader.read_bits(5)? as u8 + 1;
+        let total_samples = ((reader.read_bits(4)? as u64) << 32) | reader.read_bits(32)? as u64;
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&data[18..STREAMINFO_LEN]);
+
+        if min_block_size < 16 || max_block_size < min_block_size {
+            return Err(FlacError::InvalidMetadata("块大小不合法"));
+        }
+        if sample_rate == 0 {
+            return Err(FlacError::InvalidMetadata("采样率不能为 0"));
+        }
+        if bits_per_sample < 4 {
+            return Err(FlacError::InvalidMetadata("采样位数至少为 4"));
+        }
+        Ok(StreamInfo {
+            min_block_size,
+            max_block_size,
+            min_frame_size,
+            max_frame_size,
+            sample_rate,
+            channels,
+            bits_per_sample,
+            total_samples,
+            md5,
+        })
+    }
+
+    // * MD5 是否可用来校验
+    pub fn has_md5(&self) -> bool {
+        self.md5 != [0; 16]
+    }
+}
+
+// * 解析文件头部, 返回 STREAMINFO 和第一个音频帧在 data 中的偏移
+pub fn read_header(data: &[u8]) -> Result<(StreamInfo, usize), FlacError> {
+    if !data.starts_with(b"fLaC") {
+        return Err(FlacError::InvalidMarker);
+    }
+    let mut pos = 4;
+    let mut info = None;
+    loop {
+        let header = data.get(pos..pos + 4).ok_or(FlacError::UnexpectedEof)?;
+        let is_last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7F;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let body = data
+            .get(pos + 4..pos + 4 + len)
+            .ok_or(FlacError::UnexpectedEof)?;
+
+        match (info.is_none(), block_type) {
+            (true, STREAMINFO) if len == STREAMINFO_LEN => info = Some(StreamInfo::parse(body)?),
+            (true, _) => {
+                return Err(FlacError::InvalidMetadata(
+                    "第一个元数据块必须是 STREAMINFO",
+                ))
+            }
+            (false, STREAMINFO) => {
+                return Err(FlacError::InvalidMetadata("STREAMINFO 只能出现一次"))
+            }
+            // * 127 是保留的非法类型
+            (false, 127) => return Err(FlacError::InvalidMetadata("非法的元数据块类型")),
+            (false, _) => {}
+        }
+
+        pos += 4 + len;
+        if is_last {
+            break;
+        }
+    }
+    // ? 循环至少执行一次, 并且第一次一定会设置 info 或者返回错误
+    Ok((info.unwrap(), pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_streaminfo_of_fixture() {
+        let data = include_bytes!("../../fixtures/stereo16.flac");
+        let (info, offset) = read_header(data).unwrap();
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.total_samples, 1900);
+        assert_eq!(info.max_block_size, 576);
+        assert!(info.has_md5());
+        // * 4 字节标记 + STREAMINFO(4 + 34) + 填充块(4 + 8)
+        assert_eq!(offset, 54);
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        assert!(matches!(
+            read_header(b"RIFF"),
+            Err(FlacError::InvalidMarker)
+        ));
+        assert!(matches!(
+            read_header(b"fLaC\x00\x00"),
+            Err(FlacError::UnexpectedEof)
+        ));
+        // * 第一个块是填充块
+        assert!(matches!(
+            read_header(b"fLaC\x81\x00\x00\x00"),
+            Err(FlacError::InvalidMetadata(_))
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+// * 把解码后的 PCM 写成 WAV 文件
+// + RIFF 头 + fmt 块 + data 块, 所有字段都是小端序
+// + 8 位 WAV 存的是无符号数, 其余位数存的是有符号数
+// + 位数不是 8 的倍数时(比如 12、20 位), 样本放在更宽的容器里并左对齐
+// + RIFF 的长度字段只有 32 位, 数据超过 4 GiB 时没法写成 WAV, 返回 InvalidInput
+use std::io::{self, Write};
+
+use super::metadata::StreamInfo;
+
+pub fn write<W: Write>(mut writer: W, info: &StreamInfo, samples: &[i32]) -> io::Result<()> {
+    let width = info.bits_per_sample.div_ceil(8) as u32;
+    let container_bits = width * 8;
+    let shift = container_bits - info.bits_per_sample as u32;
+    let channels = info.channels as u32;
+    let (riff_len, data_len) = lengths(samples.len(), width)?;
+    let block_align = channels * width;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&riff_len.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // * 1 表示 PCM
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&(channels as u16).to_le_bytes())?;
+    writer.write_all(&info.sample_rate.to_le_bytes())?;
+    writer.write_all(&(info.sample_rate * block_align).to_le_bytes())?;
+    writer.write_all(&(block_align as u16).to_le_bytes())?;
+    writer.write_all(&(container_bits as u16).to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    let mut bytes = Vec::with_capacity(data_len as usize);
+    for &sample in samples {
+        let sample = sample << shift;
+        if width == 1 {
+            bytes.push((sample + 128) as u8);
+        } else {
+            bytes.extend_from_slice(&sample.to_le_bytes()[..width as usize]);
+        }
+    }
+    writer.write_all(&bytes)
+}
+
+// * (RIFF 块的长度, data 块的长度), 先用 u64 计算, 放不进 u32 时返回错误
+fn lengths(samples: usize, width: u32) -> io::Result<(u32, u32)> {
+    let too_large = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "PCM 数据太大, 超出了 WAV 的 4 GiB 限制",
+        )
+    };
+    let data_len = (samples as u64)
+        .checked_mul(width as u64)
+        .ok_or_else(too_large)?;
+    let riff_len = data_len
+        .checked_add(36)
+        .and_then(|len| u32::try_from(len).ok())
+        .ok_or_else(too_large)?;
+    Ok((riff_len, data_len as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(bits_per_sample: u8, channels: u8) -> StreamInfo {
+        StreamInfo {
+            min_block_size: 16,
+            max_block_size: 16,
+            min_frame_size: 0,
+            max_frame_size: 0,
+            sample_rate: 8000,
+            channels,
+            bits_per_sample,
+            total_samples: 0,
+            md5: [0; 16],
+        }
+    }
+
+    #[test]
+    fn writes_16_bit_stereo() {
+        let mut out = Vec::new();
+        write(&mut out, &info(16, 2), &[1, -1, 256, -256]).unwrap();
+        assert_eq!(out.len(), 44 + 8);
+        assert_eq!(&out[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(out[4..8].try_into().unwrap()), 44);
+        assert_eq!(&out[8..16], b"WAVEfmt ");
+        // * 字节率 = 8000 * 2 声道 * 2 字节
+        assert_eq!(u32::from_le_bytes(out[28..32].try_into().unwrap()), 32000);
+        assert_eq!(&out[36..40], b"data");
+        assert_eq!(&out[44..], &[1, 0, 0xFF, 0xFF, 0, 1, 0, 0xFF]);
+    }
+
+    #[test]
+    fn writes_unsigned_8_bit_and_left_justified_12_bit() {
+        let mut out = Vec::new();
+        write(&mut out, &info(8, 1), &[-128, 0, 127]).unwrap();
+        assert_eq!(&out[44..], &[0, 128, 255]);
+
+        let mut out = Vec::new();
+        write(&mut out, &info(12, 1), &[1]).unwrap();
+        assert_eq!(u16::from_le_bytes(out[34..36].try_into().unwrap()), 16);
+        assert_eq!(&out[44..], &[0x10, 0]);
+    }
+
+    #[test]
+    fn rejects_streams_over_4_gib() {
+        assert_eq!(lengths(4, 2).unwrap(), (44, 8));
+        assert_eq!(
+            lengths(1_073_741_814, 4).unwrap(),
+            (u32::MAX - 3, u32::MAX - 39)
+        );
+        let err = lengths(1 << 30, 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        for width in [1, 4] {
+            let err = lengths(usize::MAX, width).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
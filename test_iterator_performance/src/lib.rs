@@ -0,0 +1,6 @@
+// * 音频解码器中恢复残差的几种写法, 见 lpc.rs
+pub mod lpc;
+// * 简单的计时和统计, 见 bench.rs
+pub mod bench;
+// * 完整的 FLAC 解码器, 见 flac.rs
+pub mod flac;
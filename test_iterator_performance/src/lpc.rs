@@ -0,0 +1,131 @@
+// * FLAC 解码时, 每个样本等于 "前 12 个样本的线性预测值" 加上 "残差"
+// + buffer 一开始存放的是残差, 从第 12 个样本开始, 依次原地恢复成真正的样本
+// + 下面是同一个算法的三种写法, 用来比较迭代器是否真的是零开销抽象
+// ? 三种写法都使用 wrapping_add, 保证在 debug 模式下也不会因为溢出 panic, 并且结果完全一致
+
+pub const ORDER: usize = 12;
+
+// * 迭代器写法: zip + map + sum
+pub fn restore_iter(coefficients: &[i64; ORDER], qlp_shift: i16, buffer: &mut [i32]) {
+    for i in ORDER..buffer.len() {
+        let prediction = coefficients
+            .iter()
+            // rust在处理这个zip的时候, 并不会使用所谓的循环, 因为rust知道 buffer有12个成员, 这里使用了所谓的展开策略
+            // 大概理解为下面这段代码复制了12次, 这样来消除循环控制语句带来的性能开销
+            .zip(&buffer[i - ORDER..i]) // 将迭代器的12个元素配对
+            .map(|(&c, &s)| c * s as i64) // 配对后的迭代元组项相乘返回一个新的迭代器
+            .sum::<i64>() // 新的迭代器求和
+            >> qlp_shift;
+        let delta = buffer[i];
+        buffer[i] = (prediction as i32).wrapping_add(delta);
+    }
+}
+
+// * 手写的下标循环
+#[allow(clippy::needless_range_loop)]
+pub fn restore_indexed(coefficients: &[i64; ORDER], qlp_shift: i16, buffer: &mut [i32]) {
+    for i in ORDER..buffer.len() {
+        let mut sum: i64 = 0;
+        for j in 0..ORDER {
+            sum += coefficients[j] * buffer[i - ORDER + j] as i64;
+        }
+        let prediction = sum >> qlp_shift;
+        buffer[i] = (prediction as i32).wrapping_add(buffer[i]);
+    }
+}
+
+// * 手动展开的循环, 相当于编译器对迭代器写法做的优化
+pub fn restore_unrolled(coefficients: &[i64; ORDER], qlp_shift: i16, buffer: &mut [i32]) {
+    let c = coefficients;
+    for i in ORDER..buffer.len() {
+        let s = &buffer[i - ORDER..i];
+        let sum = c[0] * s[0] as i64
+            + c[1] * s[1] as i64
+            + c[2] * s[2] as i64
+            + c[3] * s[3] as i64
+            + c[4] * s[4] as i64
+            + c[5] * s[5] as i64
+            + c[6] * s[6] as i64
+            + c[7] * s[7] as i64
+            + c[8] * s[8] as i64
+            + c[9] * s[9] as i64
+            + c[10] * s[10] as i64
+            + c[11] * s[11] as i64;
+        let prediction = sum >> qlp_shift;
+        buffer[i] = (prediction as i32).wrapping_add(buffer[i]);
+    }
+}
+
+// * 任意阶数的迭代器写法, 解码 FLAC 时使用, 阶数就是 coefficients 的长度
+// + 和上面一样, coefficients[0] 对应最早的那个历史样本
+pub fn restore(coefficients: &[i64], qlp_shift: i16, buffer: &mut [i32]) {
+    let order = coefficients.len();
+    for i in order..buffer.len() {
+        let prediction = coefficients
+            .iter()
+            .zip(&buffer[i - order..i])
+            .map(|(&c, &s)| c * s as i64)
+            .sum::<i64>()
+            >> qlp_shift;
+        let delta = buffer[i];
+        buffer[i] = (prediction as i32).wrapping_add(delta);
+    }
+}
+
+// * 生成固定的测试数据: 一组系数和 len 个残差
+// + 使用 xorshift 伪随机数, 同样的 seed 每次生成同样的数据, 不需要依赖 rand
+pub fn sample_input(len: usize, seed: u64) -> ([i64; ORDER], Vec<i32>) {
+    let mut state = seed.max(1);
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut coefficients = [0i64; ORDER];
+    for c in coefficients.iter_mut() {
+        *c = (next() % 2048) as i64 - 1024;
+    }
+    let residuals = (0..len).map(|_| (next() % 512) as i32 - 256).collect();
+    (coefficients, residuals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_kernels_agree() {
+        let (coefficients, residuals) = sample_input(4096, 42);
+        let mut a = residuals.clone();
+        let mut b = residuals.clone();
+        let mut c = residuals.clone();
+        restore_iter(&coefficients, 10, &mut a);
+        restore_indexed(&coefficients, 10, &mut b);
+        restore_unrolled(&coefficients, 10, &mut c);
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        let mut d = residuals.clone();
+        restore(&coefficients, 10, &mut d);
+        assert_eq!(a, d);
+        // * 前 12 个样本没有足够的历史样本, 保持不变
+        assert_eq!(a[..ORDER], residuals[..ORDER]);
+    }
+
+    #[test]
+    fn restores_a_known_signal() {
+        // * 只有最后一个系数为 1 << shift, 预测值就是前一个样本, 相当于对残差求前缀和
+        let mut coefficients = [0i64; ORDER];
+        coefficients[ORDER - 1] = 1 << 4;
+        let mut buffer = vec![0; ORDER];
+        buffer.extend([1, 1, 1, -2]);
+        restore_iter(&coefficients, 4, &mut buffer);
+        assert_eq!(buffer[ORDER..], [1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn sample_input_is_deterministic() {
+        assert_eq!(sample_input(100, 7), sample_input(100, 7));
+        assert_ne!(sample_input(100, 7).1, sample_input(100, 8).1);
+    }
+}
@@ -1,3 +1,8 @@
+use std::env;
+
+use test_iterator_performance::bench::{self, Row, Table};
+use test_iterator_performance::lpc;
+
 fn main() {
     // println!("Hello, world!");
     // * 之前留了一个疑问, 说是手动写for循环更快
@@ -9,23 +14,53 @@ fn main() {
     // 这套抽象, 在rust中叫做 ———— "零开销抽象" Zero-Cost Abstraction
     // + 意味着在使用抽象时不会引入额外的运行时开销
 
-    // 音频解码器例子
-    let buffer: &mut [i32];
-    let coefficients: [i64; 22];
-    let qlp_shift: i16;
-
-    for i in 12..buffer.len() {
-        let prediction = coefficients.iter()
-            // rust在处理这个zip的时候, 并不会使用所谓的循环, 因为rust知道 buffer有12个成员, 这里使用了所谓的展开策略
-            // 大概理解为下面这段代码复制了12次, 这样来消除循环控制语句带来的性能开销
-            .zip(&buffer[i - 12..i]) // 将迭代器的12个元素配对
-            .map(|(&c, &s)| c * s as i64) // 配对后的迭代元组项相乘返回一个新的迭代器
-            .sum::<i64>() >> qlp_shift; // 新的迭代器求和
-        let delta = buffer[i];
-        buffer[i] = prediction as i32 + delta;
+    // 音频解码器例子, 具体的三种写法见 lpc.rs
+    // + 运行: cargo run --release -- [每个用例的运行次数]
+    // + debug 模式下没有做优化, 迭代器写法会明显更慢, 所以要用 release 模式比较
+    let runs = env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(50);
+    let qlp_shift: i16 = 10;
+
+    let kernels: [(&str, Kernel); 3] = [
+        ("iter", lpc::restore_iter),
+        ("indexed", lpc::restore_indexed),
+        ("unrolled", lpc::restore_unrolled),
+    ];
+
+    let mut table = Table { rows: Vec::new() };
+    for size in [1_000, 10_000, 100_000, 1_000_000] {
+        let (coefficients, residuals) = lpc::sample_input(size, 0x5eed);
+
+        // * 先确认三种写法的结果完全一致, 否则比较速度没有意义
+        let mut expected = residuals.clone();
+        lpc::restore_iter(&coefficients, qlp_shift, &mut expected);
+        for (name, kernel) in kernels {
+            let mut buffer = residuals.clone();
+            kernel(&coefficients, qlp_shift, &mut buffer);
+            assert_eq!(buffer, expected, "{} 的结果和迭代器写法不一致", name);
+        }
+
+        for (name, kernel) in kernels {
+            let stats = bench::measure(
+                3,
+                runs,
+                || residuals.clone(),
+                |buffer| kernel(&coefficients, qlp_shift, buffer),
+            );
+            table.rows.push(Row {
+                name: String::from(name),
+                size,
+                stats,
+            });
+        }
     }
+    print!("{}", table);
 }
 
+type Kernel = fn(&[i64; lpc::ORDER], i16, &mut [i32]);
+
 // 可以大胆使用闭包和迭代器, 因为他们不会带来性能损失, 同时还能保持运行时的性能高效
 // 这也是rust一直以来致力于实现的一个所谓的零开销抽象的环节
 /*